@@ -1,6 +1,9 @@
 extern crate embed_resource;
 
 fn main() {
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() != Ok("windows") {
+        return;
+    }
     embed_resource::compile("assets/tinitime.rc", embed_resource::NONE)
         .manifest_optional()
         .unwrap();
@@ -0,0 +1,120 @@
+-group-separator = .
+-clock = 24
+
+no-label = Ohne Label
+undo-stop = Stopp
+undo-skip = Überspringen
+undo-change = Änderung
+undo-menu = { $action } rückgängig
+undo-hint = { $action } rückgängig: Strg+Umschalt+F1
+next-block = als Nächstes: { $name } um { $time }
+
+menu-extend = Um { $duration } verlängern
+menu-snooze = { $duration } schlummern
+menu-add-timer = Timer hinzufügen
+menu-skip-reminder = { $name }-Pause überspringen
+menu-reset-position = Position zurücksetzen
+
+timer-start = Starten
+timer-pause = Pausieren
+timer-resume = Fortsetzen
+timer-reset = Zurücksetzen
+timer-remove = Entfernen
+
+notify-times-up = Die Zeit ist um
+notify-times-up-text = Strg+F2 verlängert um { $extend }, Strg+F3 schlummert { $snooze }
+notify-break = Pause
+notify-break-text = Mach { $duration } Pause
+notify-break-over = Die Pause ist vorbei
+notify-break-over-text = Zeit, dich wieder zu konzentrieren
+notify-time-left = noch { $duration }
+notify-reminder-text = Zeit für { $duration } Pause
+notify-goal = Tagesziel erreicht
+notify-goal-text = { $progress } heute, { $streak } Tage in Folge
+
+tooltip-leading = leitet { $room }
+tooltip-following = folgt { $room }
+tooltip-goal = Ziel { $progress }
+tooltip-streak = { $streak } Tage in Folge
+
+overlay-skip = Esc drücken, um die Pause zu überspringen
+overlay-skip-in = Überspringen in { $seconds } s möglich
+overlay-break = Zeit für eine Pause
+
+duration-secs = { $seconds } s
+duration-mins = { $minutes } min
+duration-mins-secs = { $minutes } min { $seconds } s
+
+state-running = Läuft
+state-paused = Pausiert
+state-stopped = Gestoppt
+state-finished = Die Zeit ist um
+phase-short-break = Kurze Pause
+phase-long-break = Lange Pause
+help-keys = Leertaste Start/Pause  r zurücksetzen  +/- 1 min  q beenden
+help-quit = q beenden
+
+reflection-title = Sitzung beendet
+reflection-done = Was hast du geschafft?
+reflection-interruptions = Wie oft wurdest du unterbrochen?
+reflection-save = Speichern
+reflection-skip = Überspringen
+
+stats-no-label = (ohne Label)
+stats-tasks = Zeit pro Aufgabe
+stats-projects = Zeit pro Projekt
+stats-apps = Zeit pro Anwendung
+stats-notes = Notizen
+stats-estimates = Schätzungen
+stats-accuracy =
+    { $accuracy } % genau bei { $tasks } { $tasks ->
+        [one] Aufgabe
+       *[other] Aufgaben
+    }, { $over } { $over ->
+        [one] dauerte
+       *[other] dauerten
+    } länger
+stats-total =
+    Insgesamt { $time } in { $count } { $count ->
+        [one] Sitzung
+       *[other] Sitzungen
+    }, { $overrun } überzogen
+stats-interruptions =
+    { $count } { $count ->
+        [one] Unterbrechung
+       *[other] Unterbrechungen
+    } in { $sessions } { $sessions ->
+        [one] Sitzung
+       *[other] Sitzungen
+    }
+
+export-summary = Fokus
+export-description =
+    { $elapsed } von { $planned } min, { $outcome ->
+        [completed] abgeschlossen
+        [stopped] gestoppt
+       *[aborted] abgebrochen
+    }
+export-interruptions = Unterbrechungen: { $count }
+meeting-default = Besprechung
+
+cli-usage = Aufruf:
+cli-not-running = tinitime läuft nicht
+cli-no-data-dir = kein Datenverzeichnis
+cli-tui-windows =
+    --tui läuft nur unter Linux und macOS im Terminal; unter Windows ist
+    tinitime ein Fenster, das `tinitime ctl` und --api von der Kommandozeile steuern
+cli-program-steps =
+    { $path }: { $count } { $count ->
+        [one] Schritt
+       *[other] Schritte
+    }
+cli-meetings-imported =
+    { $count } anstehende { $count ->
+        [one] Besprechung
+       *[other] Besprechungen
+    } importiert
+option-until = --until: erwartet eine Uhrzeit wie 14:55, 2:55pm oder tomorrow 09:00, nicht { $value }
+option-invalid = { $option }: { $value } ist kein gültiger Wert
+option-unknown = unbekannte Option { $option }
+option-idle-windows-only = --idle: Leerlauferkennung gibt es nur unter Windows
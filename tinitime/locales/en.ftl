@@ -0,0 +1,119 @@
+# English is the fallback: every message must be here.
+
+-group-separator = ,
+-clock = 24
+
+no-label = No label
+undo-stop = stop
+undo-skip = skip
+undo-change = change
+undo-menu = Undo { $action }
+undo-hint = Undo { $action }: Ctrl+Shift+F1
+next-block = next: { $name } at { $time }
+
+menu-extend = Extend by { $duration }
+menu-snooze = Snooze for { $duration }
+menu-add-timer = Add timer
+menu-skip-reminder = Skip the { $name } break
+menu-reset-position = Reset position
+
+timer-start = Start
+timer-pause = Pause
+timer-resume = Resume
+timer-reset = Reset
+timer-remove = Remove
+
+notify-times-up = Time's up
+notify-times-up-text = Ctrl+F2 to extend by { $extend }, Ctrl+F3 to snooze for { $snooze }
+notify-break = Break
+notify-break-text = Take a { $duration } break
+notify-break-over = Break is over
+notify-break-over-text = Time to focus again
+notify-time-left = { $duration } left
+notify-reminder-text = Time for a { $duration } break
+notify-goal = Daily goal reached
+notify-goal-text = { $progress } today, { $streak }-day streak
+
+tooltip-leading = leading { $room }
+tooltip-following = following { $room }
+tooltip-goal = goal { $progress }
+tooltip-streak = { $streak }-day streak
+
+overlay-skip = Press Esc to skip the break
+overlay-skip-in = You can skip the break in { $seconds } s
+overlay-break = Time for a break
+
+duration-secs = { $seconds } s
+duration-mins = { $minutes } min
+duration-mins-secs = { $minutes } min { $seconds } s
+
+state-running = Running
+state-paused = Paused
+state-stopped = Stopped
+state-finished = Time's up
+phase-short-break = Short break
+phase-long-break = Long break
+help-keys = space start/pause  r reset  +/- 1 min  q quit
+help-quit = q quit
+
+reflection-title = Session done
+reflection-done = What did you get done?
+reflection-interruptions = How often were you interrupted?
+reflection-save = Save
+reflection-skip = Skip
+
+stats-no-label = (no label)
+stats-tasks = Time per task
+stats-projects = Time per project
+stats-apps = Time per application
+stats-notes = Notes
+stats-estimates = Estimates
+stats-accuracy =
+    { $accuracy } % accurate over { $tasks } { $tasks ->
+        [one] task
+       *[other] tasks
+    }, { $over } took longer
+stats-total =
+    Total { $time } in { $count } { $count ->
+        [one] session
+       *[other] sessions
+    }, { $overrun } overrun
+stats-interruptions =
+    { $count } { $count ->
+        [one] interruption
+       *[other] interruptions
+    } in { $sessions } { $sessions ->
+        [one] session
+       *[other] sessions
+    }
+
+export-summary = Focus
+export-description =
+    { $elapsed } of { $planned } min, { $outcome ->
+        [completed] completed
+        [stopped] stopped
+       *[aborted] aborted
+    }
+export-interruptions = interruptions: { $count }
+meeting-default = Meeting
+
+cli-usage = usage:
+cli-not-running = tinitime is not running
+cli-no-data-dir = no data directory
+cli-tui-windows =
+    --tui runs in a terminal on Linux and macOS only; on Windows tinitime
+    is a window, which `tinitime ctl` and --api control from the command line
+cli-program-steps =
+    { $path }: { $count } { $count ->
+        [one] step
+       *[other] steps
+    }
+cli-meetings-imported =
+    { $count } upcoming { $count ->
+        [one] meeting
+       *[other] meetings
+    } imported
+option-until = --until: expected a time like 14:55, 2:55pm or tomorrow 09:00, not { $value }
+option-invalid = { $option }: { $value } cannot be used
+option-unknown = unknown option { $option }
+option-idle-windows-only = --idle: idle detection is only available on Windows
@@ -0,0 +1,120 @@
+-group-separator = .
+-clock = 24
+
+no-label = Geen label
+undo-stop = stoppen
+undo-skip = overslaan
+undo-change = wijziging
+undo-menu = { $action } ongedaan maken
+undo-hint = { $action } ongedaan maken: Ctrl+Shift+F1
+next-block = hierna: { $name } om { $time }
+
+menu-extend = { $duration } verlengen
+menu-snooze = { $duration } sluimeren
+menu-add-timer = Timer toevoegen
+menu-skip-reminder = { $name }-pauze overslaan
+menu-reset-position = Positie herstellen
+
+timer-start = Starten
+timer-pause = Pauzeren
+timer-resume = Hervatten
+timer-reset = Herstellen
+timer-remove = Verwijderen
+
+notify-times-up = De tijd is om
+notify-times-up-text = Ctrl+F2 verlengt met { $extend }, Ctrl+F3 sluimert { $snooze }
+notify-break = Pauze
+notify-break-text = Neem { $duration } pauze
+notify-break-over = De pauze is voorbij
+notify-break-over-text = Tijd om je weer te concentreren
+notify-time-left = nog { $duration }
+notify-reminder-text = Tijd voor { $duration } pauze
+notify-goal = Dagdoel bereikt
+notify-goal-text = { $progress } vandaag, { $streak } dagen op rij
+
+tooltip-leading = leidt { $room }
+tooltip-following = volgt { $room }
+tooltip-goal = doel { $progress }
+tooltip-streak = { $streak } dagen op rij
+
+overlay-skip = Druk op Esc om de pauze over te slaan
+overlay-skip-in = Je kunt de pauze over { $seconds } s overslaan
+overlay-break = Tijd voor een pauze
+
+duration-secs = { $seconds } s
+duration-mins = { $minutes } min
+duration-mins-secs = { $minutes } min { $seconds } s
+
+state-running = Loopt
+state-paused = Gepauzeerd
+state-stopped = Gestopt
+state-finished = De tijd is om
+phase-short-break = Korte pauze
+phase-long-break = Lange pauze
+help-keys = spatie start/pauze  r herstellen  +/- 1 min  q afsluiten
+help-quit = q afsluiten
+
+reflection-title = Sessie klaar
+reflection-done = Wat heb je gedaan gekregen?
+reflection-interruptions = Hoe vaak werd je onderbroken?
+reflection-save = Opslaan
+reflection-skip = Overslaan
+
+stats-no-label = (geen label)
+stats-tasks = Tijd per taak
+stats-projects = Tijd per project
+stats-apps = Tijd per toepassing
+stats-notes = Notities
+stats-estimates = Schattingen
+stats-accuracy =
+    { $accuracy } % nauwkeurig over { $tasks } { $tasks ->
+        [one] taak
+       *[other] taken
+    }, { $over } { $over ->
+        [one] duurde
+       *[other] duurden
+    } langer
+stats-total =
+    In totaal { $time } in { $count } { $count ->
+        [one] sessie
+       *[other] sessies
+    }, { $overrun } uitgelopen
+stats-interruptions =
+    { $count } { $count ->
+        [one] onderbreking
+       *[other] onderbrekingen
+    } in { $sessions } { $sessions ->
+        [one] sessie
+       *[other] sessies
+    }
+
+export-summary = Focus
+export-description =
+    { $elapsed } van { $planned } min, { $outcome ->
+        [completed] voltooid
+        [stopped] gestopt
+       *[aborted] afgebroken
+    }
+export-interruptions = onderbrekingen: { $count }
+meeting-default = Vergadering
+
+cli-usage = gebruik:
+cli-not-running = tinitime draait niet
+cli-no-data-dir = geen gegevensmap
+cli-tui-windows =
+    --tui draait alleen op Linux en macOS in een terminal; op Windows is
+    tinitime een venster, dat `tinitime ctl` en --api vanaf de opdrachtregel bedienen
+cli-program-steps =
+    { $path }: { $count } { $count ->
+        [one] stap
+       *[other] stappen
+    }
+cli-meetings-imported =
+    { $count } { $count ->
+        [one] komende vergadering
+       *[other] komende vergaderingen
+    } geïmporteerd
+option-until = --until: verwacht een tijd zoals 14:55, 2:55pm of tomorrow 09:00, niet { $value }
+option-invalid = { $option }: { $value } is geen geldige waarde
+option-unknown = onbekende optie { $option }
+option-idle-windows-only = --idle: inactiviteit wordt alleen op Windows herkend
@@ -0,0 +1,502 @@
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    ctl::Command,
+    helpers::{escape_json, same_token},
+    history::data_dir,
+};
+
+pub const DEF_PORT: u16 = 7474;
+const TOKEN_FILE: &str = "api-token";
+const TOKEN_BYTES: usize = 16;
+const MAX_REQUEST_BYTES: u64 = 16 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+const KEEPALIVE: Duration = Duration::from_secs(15);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Status {
+    pub state: &'static str,
+    pub phase: &'static str,
+    pub remaining: u64,
+    pub duration: u64,
+    pub label: Option<String>,
+}
+
+impl Status {
+    pub fn to_json(&self) -> String {
+        let label = match &self.label {
+            Some(label) => format!("\"{}\"", escape_json(label)),
+            None => "null".to_string(),
+        };
+        format!(
+            "{{\"state\":\"{}\",\"phase\":\"{}\",\"remaining\":{},\"duration\":{},\"label\":{}}}",
+            self.state, self.phase, self.remaining, self.duration, label
+        )
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    status: Status,
+    subscribers: Vec<Sender<String>>,
+}
+
+/// Serves `GET /status`, `GET /events` and `POST /start|pause|reset` on the
+/// loopback interface. Every request must carry the token, either as
+/// `Authorization: Bearer TOKEN` or as `?token=TOKEN` for `EventSource`
+/// clients, which cannot set headers. Commands are passed to `handler`, which
+/// returns whether the timer took them.
+pub struct Server {
+    shared: Arc<Mutex<Shared>>,
+    port: u16,
+}
+
+impl Server {
+    pub fn start<F>(port: u16, token: String, handler: F) -> io::Result<Self>
+    where
+        F: Fn(Command) -> bool + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let token = Arc::new(token);
+        let handler = Arc::new(handler);
+        let server = Self {
+            shared: Arc::clone(&shared),
+            port: listener.local_addr()?.port(),
+        };
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let shared = Arc::clone(&shared);
+                let token = Arc::clone(&token);
+                let handler = Arc::clone(&handler);
+                thread::spawn(move || handle(stream, &shared, &token, &*handler));
+            }
+        });
+        Ok(server)
+    }
+
+    /// The port listened on, which is picked by the system when started on 0.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Publishes the current status. Event stream subscribers get a `state`
+    /// event when the state, phase or label changes and a `tick` event when
+    /// only the remaining time does.
+    pub fn update(&self, status: Status) {
+        let mut shared = self.shared.lock().unwrap();
+        if shared.status == status {
+            return;
+        }
+        let old = &shared.status;
+        let event = if old.state == status.state
+            && old.phase == status.phase
+            && old.label == status.label
+        {
+            "tick"
+        } else {
+            "state"
+        };
+        let message = format!("event: {}\ndata: {}\n\n", event, status.to_json());
+        shared
+            .subscribers
+            .retain(|subscriber| subscriber.send(message.clone()).is_ok());
+        shared.status = status;
+    }
+}
+
+/// Reads the token from `api-token` in the data directory, creating a random
+/// one on first use. Elsewhere than on Windows, where the data directory is
+/// the user's own, only the owner may read the file.
+pub fn load_token() -> io::Result<String> {
+    let dir = data_dir().ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+    let path = dir.join(TOKEN_FILE);
+    if let Ok(token) = fs::read_to_string(&path) {
+        let token = token.trim();
+        if !token.is_empty() {
+            return Ok(token.to_string());
+        }
+    }
+    let token = new_token()?;
+    fs::create_dir_all(&dir)?;
+    system::create_private(&path)?.write_all(token.as_bytes())?;
+    Ok(token)
+}
+
+/// Hex digits from the operating system's random number generator.
+fn new_token() -> io::Result<String> {
+    let mut bytes = [0; TOKEN_BYTES];
+    system::fill_random(&mut bytes)?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+#[cfg(windows)]
+mod system {
+    use std::{fs::File, io, path::Path};
+
+    use windows::Win32::Security::Cryptography::{
+        BCryptGenRandom, BCRYPT_USE_SYSTEM_PREFERRED_RNG,
+    };
+
+    pub fn fill_random(buf: &mut [u8]) -> io::Result<()> {
+        unsafe { BCryptGenRandom(None, buf, BCRYPT_USE_SYSTEM_PREFERRED_RNG) }
+            .ok()
+            .map_err(io::Error::other)
+    }
+
+    pub fn create_private(path: &Path) -> io::Result<File> {
+        File::create(path)
+    }
+}
+
+#[cfg(not(windows))]
+mod system {
+    use std::{
+        fs::{File, OpenOptions, Permissions},
+        io::{self, Read},
+        os::unix::fs::{OpenOptionsExt, PermissionsExt},
+        path::Path,
+    };
+
+    pub fn fill_random(buf: &mut [u8]) -> io::Result<()> {
+        File::open("/dev/urandom")?.read_exact(buf)
+    }
+
+    /// The mode only applies to a new file, so one that was there already
+    /// is narrowed down before anything is written to it.
+    pub fn create_private(path: &Path) -> io::Result<File> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)?;
+        file.set_permissions(Permissions::from_mode(0o600))?;
+        Ok(file)
+    }
+}
+
+fn handle(
+    stream: TcpStream,
+    shared: &Mutex<Shared>,
+    token: &str,
+    handler: &dyn Fn(Command) -> bool,
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?).take(MAX_REQUEST_BYTES);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return respond(&stream, "400 Bad Request", "bad request");
+    };
+
+    let mut authorization = None;
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return respond(&stream, "400 Bad Request", "bad request");
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        if name.eq_ignore_ascii_case("authorization") {
+            authorization = Some(value.trim().to_string());
+        } else if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.trim().parse().unwrap_or(0);
+        }
+    }
+    // Drain the body so closing the connection does not reset it before the
+    // client has read the response.
+    io::copy(&mut reader.by_ref().take(content_length), &mut io::sink())?;
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query_token = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="));
+    let given = authorization
+        .as_deref()
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or(query_token);
+    if !given.is_some_and(|given| same_token(given, token)) {
+        return respond(&stream, "401 Unauthorized", "unauthorized");
+    }
+
+    let command = match (method, path) {
+        ("GET", "/status") => None,
+        ("GET", "/events") => return stream_events(&stream, shared),
+        ("POST", "/start") => Some(Command::Start),
+        ("POST", "/pause") => Some(Command::Pause),
+        ("POST", "/reset") => Some(Command::Stop),
+        (_, "/status" | "/events" | "/start" | "/pause" | "/reset") => {
+            return respond(&stream, "405 Method Not Allowed", "method not allowed");
+        }
+        _ => return respond(&stream, "404 Not Found", "not found"),
+    };
+    if let Some(command) = command {
+        if !handler(command) {
+            return respond(&stream, "503 Service Unavailable", "timer not responding");
+        }
+    }
+    let status = shared.lock().unwrap().status.to_json();
+    write_response(&stream, "200 OK", &status)
+}
+
+fn stream_events(mut stream: &TcpStream, shared: &Mutex<Shared>) -> io::Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    let status = {
+        let mut shared = shared.lock().unwrap();
+        shared.subscribers.push(sender);
+        shared.status.to_json()
+    };
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-store\r\n\
+         Connection: close\r\n\r\nevent: state\ndata: {}\n\n",
+        status
+    )?;
+    loop {
+        match receiver.recv_timeout(KEEPALIVE) {
+            Ok(message) => stream.write_all(message.as_bytes())?,
+            Err(RecvTimeoutError::Timeout) => stream.write_all(b": keepalive\n\n")?,
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+    }
+}
+
+fn respond(stream: &TcpStream, status: &str, error: &str) -> io::Result<()> {
+    write_response(stream, status, &format!("{{\"error\":\"{}\"}}", error))
+}
+
+fn write_response(mut stream: &TcpStream, status: &str, body: &str) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Cache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "secret";
+
+    #[test]
+    fn tokens_are_random_hex() {
+        let (first, second) = (new_token().unwrap(), new_token().unwrap());
+        assert_eq!(first.len(), 2 * TOKEN_BYTES);
+        assert!(first.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(first, second);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn token_file_is_private() {
+        use std::{env, os::unix::fs::PermissionsExt, process};
+
+        let path = env::temp_dir().join(format!("tinitime-api-token-{}", process::id()));
+        fs::write(&path, "").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        system::create_private(&path)
+            .unwrap()
+            .write_all(b"token")
+            .unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        let token = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(token, "token");
+    }
+
+    fn server() -> (Server, Arc<Mutex<Vec<Command>>>) {
+        let commands = Arc::new(Mutex::new(vec![]));
+        let taken = Arc::clone(&commands);
+        let server = Server::start(0, TOKEN.to_string(), move |command| {
+            taken.lock().unwrap().push(command.clone());
+            // A timer that is gone, to see the error.
+            command != Command::Pause
+        })
+        .unwrap();
+        (server, commands)
+    }
+
+    fn connect(server: &Server, request: &str) -> TcpStream {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, server.port())).unwrap();
+        stream.set_read_timeout(Some(READ_TIMEOUT)).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        stream
+    }
+
+    /// Sends `request` and returns the status line and body of the response.
+    fn send(server: &Server, request: &str) -> (String, String) {
+        let mut response = String::new();
+        connect(server, request)
+            .read_to_string(&mut response)
+            .unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        (head.lines().next().unwrap().to_string(), body.to_string())
+    }
+
+    fn get(server: &Server, target: &str, token: &str) -> (String, String) {
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer {}\r\n\r\n",
+            target, token
+        );
+        send(server, &request)
+    }
+
+    fn post(server: &Server, path: &str) -> (String, String) {
+        let request = format!(
+            "POST {} HTTP/1.1\r\nAuthorization: Bearer {}\r\nContent-Length: 2\r\n\r\n{{}}",
+            path, TOKEN
+        );
+        send(server, &request)
+    }
+
+    fn running() -> Status {
+        Status {
+            state: "running",
+            phase: "focus",
+            remaining: 1500,
+            duration: 1500,
+            label: Some("Write \"docs\"".to_string()),
+        }
+    }
+
+    #[test]
+    fn status_is_json() {
+        assert_eq!(
+            running().to_json(),
+            "{\"state\":\"running\",\"phase\":\"focus\",\"remaining\":1500,\
+             \"duration\":1500,\"label\":\"Write \\\"docs\\\"\"}"
+        );
+    }
+
+    #[test]
+    fn rejects_missing_and_wrong_tokens() {
+        let (server, commands) = server();
+        let unauthorized = (
+            "HTTP/1.1 401 Unauthorized".to_string(),
+            "{\"error\":\"unauthorized\"}".to_string(),
+        );
+        assert_eq!(send(&server, "GET /status HTTP/1.1\r\n\r\n"), unauthorized);
+        assert_eq!(get(&server, "/status", "secreT"), unauthorized);
+        assert_eq!(get(&server, "/status", "secret2"), unauthorized);
+        assert_eq!(get(&server, "/status?token=nope", ""), unauthorized);
+        let request = "POST /start HTTP/1.1\r\nAuthorization: Basic secret\r\n\r\n";
+        assert_eq!(send(&server, request), unauthorized);
+        assert!(commands.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn serves_the_latest_status() {
+        let (server, _) = server();
+        server.update(running());
+        let (status, body) = get(&server, "/status", TOKEN);
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(body, running().to_json());
+
+        let request = format!("GET /status?token={} HTTP/1.1\r\n\r\n", TOKEN);
+        assert_eq!(send(&server, &request).1, running().to_json());
+    }
+
+    #[test]
+    fn passes_commands_to_the_handler() {
+        let (server, commands) = server();
+        assert_eq!(post(&server, "/start").0, "HTTP/1.1 200 OK");
+        assert_eq!(post(&server, "/reset").0, "HTTP/1.1 200 OK");
+        assert_eq!(
+            post(&server, "/pause"),
+            (
+                "HTTP/1.1 503 Service Unavailable".to_string(),
+                "{\"error\":\"timer not responding\"}".to_string()
+            )
+        );
+        assert_eq!(
+            *commands.lock().unwrap(),
+            [Command::Start, Command::Stop, Command::Pause]
+        );
+
+        assert_eq!(
+            get(&server, "/start", TOKEN).0,
+            "HTTP/1.1 405 Method Not Allowed"
+        );
+        assert_eq!(
+            post(&server, "/status").0,
+            "HTTP/1.1 405 Method Not Allowed"
+        );
+        assert_eq!(post(&server, "/stop").0, "HTTP/1.1 404 Not Found");
+        assert_eq!(commands.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn streams_state_and_tick_events() {
+        let (server, _) = server();
+        server.update(running());
+        let request = format!("GET /events?token={} HTTP/1.1\r\n\r\n", TOKEN);
+        let mut events = BufReader::new(connect(&server, &request));
+        let mut next_event = || {
+            let mut event = String::new();
+            while !event.ends_with("\n\n") {
+                assert_ne!(events.read_line(&mut event).unwrap(), 0);
+            }
+            event
+        };
+
+        let head = next_event();
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(head.contains("Content-Type: text/event-stream\r\n"));
+        let first = format!("event: state\ndata: {}\n\n", running().to_json());
+        assert_eq!(head.split("\r\n\r\n").nth(1), Some(first.as_str()));
+
+        // Subscribed once the first event is out, so these are not missed.
+        let ticked = Status {
+            remaining: 1499,
+            ..running()
+        };
+        server.update(ticked.clone());
+        // Nothing changed, so nothing is sent.
+        server.update(ticked.clone());
+        let paused = Status {
+            state: "paused",
+            ..ticked.clone()
+        };
+        server.update(paused.clone());
+        assert_eq!(
+            next_event(),
+            format!("event: tick\ndata: {}\n\n", ticked.to_json())
+        );
+        assert_eq!(
+            next_event(),
+            format!("event: state\ndata: {}\n\n", paused.to_json())
+        );
+    }
+
+    #[test]
+    fn compares_tokens_fully() {
+        assert!(same_token("secret", "secret"));
+        assert!(!same_token("secreu", "secret"));
+        assert!(!same_token("secre", "secret"));
+        assert!(!same_token("", "secret"));
+    }
+}
@@ -0,0 +1,65 @@
+#[cfg(test)]
+use std::cell::Cell;
+use std::time::{Duration, Instant, SystemTime};
+
+pub trait Clock {
+    fn now(&self) -> Duration;
+    fn wall(&self) -> SystemTime;
+}
+
+#[derive(Clone, Copy)]
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+
+    fn wall(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock that only moves when told to, for tests.
+#[cfg(test)]
+pub struct ManualClock {
+    now: Cell<Duration>,
+    wall: Cell<SystemTime>,
+}
+
+#[cfg(test)]
+impl ManualClock {
+    pub fn new(wall: SystemTime) -> Self {
+        Self {
+            now: Cell::new(Duration::ZERO),
+            wall: Cell::new(wall),
+        }
+    }
+
+    /// Moves both clocks on by `by`.
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+        self.wall.set(self.wall.get() + by);
+    }
+}
+
+#[cfg(test)]
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+
+    fn wall(&self) -> SystemTime {
+        self.wall.get()
+    }
+}
@@ -0,0 +1,332 @@
+use std::{path::PathBuf, time::Duration};
+
+use crate::{
+    api::DEF_PORT as DEF_API_PORT,
+    foreground::Privacy,
+    goal::Goal,
+    helpers::parse_duration,
+    history::clean_label,
+    i18n::tr,
+    idle::IdleAction,
+    pomodoro::{PomodoroConfig, Strictness},
+    program::Step,
+    reminder::Reminder,
+    schedule::Block,
+    timer::SuspendPolicy,
+    until::Until,
+    webhook::Webhook,
+};
+
+const DEF_FADE_ALPHA: u8 = 60;
+
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    pub click_through: bool,
+    pub hover_fade: Option<u8>,
+    pub hide_fullscreen: bool,
+    pub idle_timeout: Option<Duration>,
+    pub idle_action: IdleAction,
+    pub on_sleep: SuspendPolicy,
+    pub on_lock: SuspendPolicy,
+    pub label: Option<String>,
+    pub estimate: Option<u32>,
+    pub wide: bool,
+    pub until: Option<Until>,
+    pub reminders: Vec<Reminder>,
+    pub program: Vec<Step>,
+    pub extend_by: Option<Duration>,
+    pub snooze_for: Option<Duration>,
+    pub pomodoro: Option<PomodoroConfig>,
+    pub break_overlay: Option<Strictness>,
+    pub skip_after: Option<Duration>,
+    pub goal: Option<Goal>,
+    pub api_port: Option<u16>,
+    pub api_token: Option<String>,
+    pub webhook_file: Option<PathBuf>,
+    pub webhooks: Vec<Webhook>,
+    pub text_file: Option<PathBuf>,
+    pub text_template: Option<String>,
+    pub json_file: Option<PathBuf>,
+    pub tui: bool,
+    pub room: Option<String>,
+    pub room_lead: bool,
+    pub room_port: Option<u16>,
+    pub room_token: Option<String>,
+    pub schedule_file: Option<PathBuf>,
+    pub schedule: Vec<Block>,
+    pub track_apps: Option<Privacy>,
+    pub no_reflection: bool,
+    pub verbose: bool,
+    pub log_file: Option<PathBuf>,
+    pub lang: Option<String>,
+}
+
+impl Config {
+    /// Reads the options; values that are given but cannot be used are
+    /// reported rather than left at their defaults.
+    pub fn from_args(args: impl Iterator<Item = String>) -> std::result::Result<Self, Vec<String>> {
+        let mut config = Self::default();
+        let mut errors = vec![];
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            let arg = Arg {
+                name: &name,
+                value: value.as_deref(),
+            };
+            match name.as_str() {
+                "--click-through" => config.click_through = true,
+                "--hover-fade" => {
+                    let alpha = match value {
+                        Some(_) => arg.parse(&mut errors, |value| value.parse().ok()),
+                        None => None,
+                    };
+                    config.hover_fade = Some(alpha.unwrap_or(DEF_FADE_ALPHA));
+                }
+                "--hide-fullscreen" => config.hide_fullscreen = true,
+                "--idle" => {
+                    config.idle_timeout = arg.parse(&mut errors, |value| {
+                        let minutes = value.parse::<u64>().ok().filter(|&minutes| minutes > 0)?;
+                        minutes.checked_mul(60).map(Duration::from_secs)
+                    })
+                }
+                "--idle-action" => {
+                    config.idle_action = arg
+                        .parse(&mut errors, IdleAction::parse)
+                        .unwrap_or_default()
+                }
+                "--on-sleep" => {
+                    config.on_sleep = arg
+                        .parse(&mut errors, SuspendPolicy::parse)
+                        .unwrap_or_default()
+                }
+                "--on-lock" => {
+                    config.on_lock = arg
+                        .parse(&mut errors, SuspendPolicy::parse)
+                        .unwrap_or_default()
+                }
+                "--label" => config.label = arg.parse(&mut errors, clean_label),
+                "--estimate" => {
+                    config.estimate = arg.parse(&mut errors, |value| {
+                        value.parse().ok().filter(|&sessions| sessions > 0)
+                    })
+                }
+                "--wide" => config.wide = true,
+                "--until" => {
+                    let value = value.or_else(|| {
+                        let mut words = vec![args.next_if(|arg| !arg.starts_with("--"))?];
+                        if words[0].eq_ignore_ascii_case("tomorrow") {
+                            words.extend(args.next_if(|arg| !arg.starts_with("--")));
+                        }
+                        words.extend(args.next_if(|arg| {
+                            arg.eq_ignore_ascii_case("am") || arg.eq_ignore_ascii_case("pm")
+                        }));
+                        Some(words.join(" "))
+                    });
+                    match value.as_deref().and_then(Until::parse) {
+                        Some(until) => config.until = Some(until),
+                        None => errors.push(tr!(
+                            "option-until",
+                            value = format!("{:?}", value.unwrap_or_default())
+                        )),
+                    }
+                }
+                "--extend" => config.extend_by = arg.parse(&mut errors, parse_duration),
+                "--snooze" => config.snooze_for = arg.parse(&mut errors, parse_duration),
+                "--pomodoro" => {
+                    config.pomodoro.get_or_insert_with(PomodoroConfig::default);
+                }
+                "--short-break" => {
+                    let duration = arg.parse(&mut errors, parse_duration);
+                    let pomodoro = config.pomodoro.get_or_insert_with(PomodoroConfig::default);
+                    if let Some(duration) = duration {
+                        pomodoro.short_break = duration;
+                    }
+                }
+                "--long-break" => {
+                    let duration = arg.parse(&mut errors, parse_duration);
+                    let pomodoro = config.pomodoro.get_or_insert_with(PomodoroConfig::default);
+                    if let Some(duration) = duration {
+                        pomodoro.long_break = duration;
+                    }
+                }
+                "--long-break-every" => {
+                    let count = arg.parse(&mut errors, |value| {
+                        value.parse().ok().filter(|&count| count > 0)
+                    });
+                    let pomodoro = config.pomodoro.get_or_insert_with(PomodoroConfig::default);
+                    if let Some(count) = count {
+                        pomodoro.long_break_every = count;
+                    }
+                }
+                "--break-overlay" => {
+                    let strictness = match value {
+                        Some(_) => arg.parse(&mut errors, Strictness::parse),
+                        None => None,
+                    };
+                    config.break_overlay = Some(strictness.unwrap_or_default());
+                    config.pomodoro.get_or_insert_with(PomodoroConfig::default);
+                }
+                "--skip-after" => config.skip_after = arg.parse(&mut errors, parse_duration),
+                "--goal" => config.goal = arg.parse(&mut errors, Goal::parse),
+                "--api" => {
+                    let port = match value {
+                        Some(_) => arg.parse(&mut errors, |value| value.parse().ok()),
+                        None => None,
+                    };
+                    config.api_port = Some(port.unwrap_or(DEF_API_PORT));
+                }
+                "--api-token" => config.api_token = arg.parse(&mut errors, non_empty),
+                "--webhooks" => config.webhook_file = arg.parse(&mut errors, non_empty),
+                "--text-file" => config.text_file = arg.parse(&mut errors, non_empty),
+                "--text-template" => config.text_template = arg.parse(&mut errors, non_empty),
+                "--json-file" => config.json_file = arg.parse(&mut errors, non_empty),
+                "--tui" => config.tui = true,
+                "--room" => config.room = arg.parse(&mut errors, clean_label),
+                "--lead" => config.room_lead = true,
+                "--schedule" => config.schedule_file = arg.parse(&mut errors, non_empty),
+                "--room-port" => {
+                    config.room_port = arg.parse(&mut errors, |value| value.parse().ok())
+                }
+                "--room-token" => config.room_token = arg.parse(&mut errors, clean_label),
+                "--track-apps" => {
+                    config.track_apps.get_or_insert_with(Privacy::default);
+                }
+                "--track-titles" => {
+                    config
+                        .track_apps
+                        .get_or_insert_with(Privacy::default)
+                        .titles = true
+                }
+                "--track-ignore" => {
+                    let word = arg.parse(&mut errors, non_empty);
+                    let privacy = config.track_apps.get_or_insert_with(Privacy::default);
+                    privacy.ignore.extend(word);
+                }
+                "--no-reflection" => config.no_reflection = true,
+                "--verbose" => config.verbose = true,
+                "--log-file" => config.log_file = arg.parse(&mut errors, non_empty),
+                "--lang" => config.lang = arg.parse(&mut errors, non_empty),
+                "--remind" => config
+                    .reminders
+                    .extend(arg.parse(&mut errors, Reminder::parse)),
+                _ => errors.push(tr!("option-unknown", option = format!("{:?}", name))),
+            }
+        }
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn needs_watch(&self) -> bool {
+        self.hover_fade.is_some() || self.hide_fullscreen
+    }
+}
+
+/// An option as given on the command line, e.g. `--goal=4h`.
+struct Arg<'a> {
+    name: &'a str,
+    value: Option<&'a str>,
+}
+
+impl Arg<'_> {
+    /// Parses the value, or reports it when it is missing or cannot be used.
+    fn parse<T>(
+        &self,
+        errors: &mut Vec<String>,
+        parse: impl FnOnce(&str) -> Option<T>,
+    ) -> Option<T> {
+        let parsed = self.value.and_then(parse);
+        if parsed.is_none() {
+            errors.push(tr!(
+                "option-invalid",
+                option = self.name,
+                value = format!("{:?}", self.value.unwrap_or_default())
+            ));
+        }
+        parsed
+    }
+}
+
+fn non_empty<T: for<'a> From<&'a str>>(value: &str) -> Option<T> {
+    (!value.is_empty()).then(|| value.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> std::result::Result<Config, Vec<String>> {
+        Config::from_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn reads_values() {
+        let config = parse(&[
+            "--idle=5",
+            "--on-lock=pause",
+            "--goal=4h",
+            "--remind=eyes:20m:20s",
+            "--api",
+            "--hover-fade",
+        ])
+        .unwrap();
+        assert_eq!(config.idle_timeout, Some(Duration::from_secs(300)));
+        assert_eq!(config.on_lock, SuspendPolicy::Pause);
+        assert_eq!(config.goal, Some(Goal::Time(Duration::from_secs(4 * 3600))));
+        assert_eq!(config.reminders.len(), 1);
+        assert_eq!(config.api_port, Some(DEF_API_PORT));
+        assert_eq!(config.hover_fade, Some(DEF_FADE_ALPHA));
+    }
+
+    #[test]
+    fn reports_values_that_cannot_be_used() {
+        let args = [
+            "--idle=soon",
+            "--goal=0",
+            "--estimate=-1",
+            "--remind=eyes:20m",
+            "--extend=0",
+            "--snooze",
+            "--api=http",
+            "--room-port=70000",
+            "--idle-action=sleep",
+            "--on-sleep=halt",
+            "--on-lock=",
+        ];
+        let errors = parse(&args).unwrap_err();
+        assert_eq!(errors.len(), args.len());
+        for (error, arg) in errors.iter().zip(args) {
+            let name = arg.split('=').next().unwrap();
+            assert!(error.starts_with(&format!("{}:", name)), "{}", error);
+        }
+    }
+
+    #[test]
+    fn reports_unknown_options() {
+        let errors = parse(&["--wide", "--widen", "later"]).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("\"--widen\""));
+        assert!(errors[1].contains("\"later\""));
+    }
+
+    #[test]
+    fn break_overlay_implies_pomodoro() {
+        let config = parse(&["--break-overlay=strict"]).unwrap();
+        assert_eq!(config.break_overlay, Some(Strictness::Strict));
+        assert!(config.pomodoro.is_some());
+    }
+
+    #[test]
+    fn idle_minutes_must_fit() {
+        assert!(parse(&["--idle=0"]).is_err());
+        assert!(parse(&[&format!("--idle={}", u64::MAX / 60 + 1)]).is_err());
+        let config = parse(&[&format!("--idle={}", u64::MAX / 60)]).unwrap();
+        assert!(config.idle_timeout.is_some());
+    }
+}
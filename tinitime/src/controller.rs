@@ -0,0 +1,1237 @@
+use std::{
+    mem,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    api::{self, Server, Status},
+    clock::Clock,
+    config::Config,
+    ctl::Command,
+    estimate::{self, Estimate, Task},
+    foreground::{ForegroundRecorder, ForegroundSource},
+    goal::{self, GoalStatus},
+    helpers::{ceil_secs, unix_secs},
+    history::{History, Outcome, Session},
+    i18n::{self, tr},
+    idle::{IdleAction, IdleChange, IdleMonitor, IdleSource},
+    localtime::Zone,
+    log,
+    pomodoro::{Phase, Pomodoro},
+    reminder::{Reminder, ReminderEvent, Reminders},
+    room::{Room, RoomState},
+    schedule::Scheduler,
+    textfile::TextFiles,
+    timer::{State, SuspendPolicy, SuspendReason, Timer, DEF_TIME},
+    undo::{Action, UndoStack},
+    until::Until,
+    webhook::{Event, Webhooks},
+};
+
+const DEF_EXTEND: Duration = Duration::from_secs(300);
+const DEF_SNOOZE: Duration = Duration::from_secs(60);
+/// How long a finished session stays open to being extended or snoozed.
+const END_GRACE: Duration = Duration::from_secs(120);
+
+/// Something the user may want to hear about, for the front-end to show in
+/// its own way.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Notice {
+    /// The timer ran out, whatever comes next.
+    Finished,
+    /// A session ran out and can still be extended or snoozed.
+    TimesUp,
+    /// Program step `index` started.
+    Step(usize),
+    /// A Pomodoro break this long started.
+    Break(Duration),
+    BreakOver,
+    /// A scheduled block started with this much of it left.
+    Block(String, Duration),
+    /// The reminder at this index asks for a break.
+    Reminder(usize),
+    GoalReached(GoalStatus),
+    /// The session that started then completed and may be reflected on.
+    Completed(SystemTime),
+    /// The label changed, or the task it belongs to.
+    LabelChanged,
+}
+
+/// Runs sessions, Pomodoro phases, programs, the schedule and rooms. The
+/// window and the terminal only show what it does and pass on input.
+pub struct Controller<C: Clock, Z: Zone> {
+    config: Config,
+    clock: C,
+    zone: Z,
+    timer: Timer,
+    session: Option<Session>,
+    history: Option<History>,
+    label: Option<String>,
+    task: Option<Task>,
+    program_step: Option<usize>,
+    ended_at: Option<Duration>,
+    pomodoro: Option<Pomodoro>,
+    /// Whether a break overlay makes breaks hard to get out of.
+    breaks_enforced: bool,
+    reminders: Reminders,
+    scheduler: Option<Scheduler>,
+    scheduled: bool,
+    next_block: Option<String>,
+    room: Option<Room>,
+    room_phase: Option<Phase>,
+    goal_sessions: Vec<Session>,
+    goal_status: Option<GoalStatus>,
+    undo: UndoStack<Snapshot>,
+    deferred: Option<Vec<Session>>,
+    idle: Option<IdleMonitor<Box<dyn IdleSource>>>,
+    away_since: Option<Duration>,
+    paused_by_idle: bool,
+    foreground: Option<ForegroundRecorder<Box<dyn ForegroundSource>>>,
+    webhooks: Option<Webhooks>,
+    text_files: Option<TextFiles>,
+    api: Option<Server>,
+    notices: Vec<Notice>,
+}
+
+impl<C: Clock, Z: Zone> Controller<C, Z> {
+    pub fn new(config: Config, clock: C, zone: Z, history: Option<History>) -> Self {
+        let goal_sessions = match (&config.goal, &history) {
+            (Some(_), Some(history)) => history.load().unwrap_or_default(),
+            _ => vec![],
+        };
+        let mut controller = Self {
+            timer: Timer::new(DEF_TIME),
+            session: None,
+            history,
+            label: None,
+            task: None,
+            program_step: None,
+            ended_at: None,
+            pomodoro: config.pomodoro.clone().map(Pomodoro::new),
+            breaks_enforced: false,
+            reminders: Reminders::new(&config.reminders, clock.now()),
+            scheduler: (!config.schedule.is_empty())
+                .then(|| Scheduler::new(config.schedule.clone())),
+            scheduled: false,
+            next_block: None,
+            room: Room::from_config(&config).and_then(|room| {
+                room.inspect_err(|error| log::warning!("room: {}", error))
+                    .ok()
+            }),
+            room_phase: None,
+            goal_sessions,
+            goal_status: None,
+            undo: UndoStack::new(),
+            deferred: None,
+            idle: None,
+            away_since: None,
+            paused_by_idle: false,
+            foreground: None,
+            webhooks: (!config.webhooks.is_empty()).then(|| Webhooks::new(config.webhooks.clone())),
+            text_files: TextFiles::from_config(&config),
+            api: None,
+            notices: vec![],
+            clock,
+            zone,
+            config,
+        };
+        controller.update_goal();
+        controller.set_label(controller.config.label.clone());
+        if let Some(sessions) = controller.config.estimate {
+            controller.set_estimate(sessions);
+        }
+        controller
+    }
+
+    /// Starts what the options ask for first: the program, a countdown to a
+    /// time of day, or the block the schedule has due.
+    pub fn begin(&mut self) {
+        if !self.config.program.is_empty() {
+            self.start_step(0);
+        } else if let Some(until) = self.config.until {
+            self.start_until(until);
+        } else {
+            self.follow_schedule();
+        }
+    }
+
+    /// Serves the status API if the options ask for it. `handler` gets the
+    /// commands on the server's threads and returns whether they got through.
+    pub fn serve_api<F>(&mut self, handler: F)
+    where
+        F: Fn(Command) -> bool + Send + Sync + 'static,
+    {
+        let Some(port) = self.config.api_port else {
+            return;
+        };
+        let token = match self.config.api_token.clone() {
+            Some(token) => Ok(token),
+            None => api::load_token(),
+        };
+        self.api = token
+            .and_then(|token| Server::start(port, token, handler))
+            .inspect(|server| log::info!("api on port {}", server.port()))
+            .inspect_err(|error| log::warning!("api on port {}: {}", port, error))
+            .ok();
+    }
+
+    /// Pauses or marks sessions while the user is away, if the options ask
+    /// for it.
+    pub fn watch_idle(&mut self, source: impl IdleSource + 'static) {
+        self.idle = self
+            .config
+            .idle_timeout
+            .map(|timeout| IdleMonitor::new(Box::new(source) as Box<dyn IdleSource>, timeout));
+    }
+
+    /// Records which applications are in front during sessions, if the
+    /// options ask for it.
+    pub fn track_apps(&mut self, source: impl ForegroundSource + 'static) {
+        self.foreground = self.config.track_apps.clone().map(|privacy| {
+            ForegroundRecorder::new(Box::new(source) as Box<dyn ForegroundSource>, privacy)
+        });
+    }
+
+    /// Keeps commands from ending breaks, which then only the overlay may
+    /// skip.
+    pub fn enforce_breaks(&mut self) {
+        self.breaks_enforced = true;
+    }
+
+    /// What happened since last asked, oldest first.
+    pub fn notices(&mut self) -> Vec<Notice> {
+        mem::take(&mut self.notices)
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    pub fn timer(&self) -> &Timer {
+        &self.timer
+    }
+
+    pub fn has_session(&self) -> bool {
+        self.session.is_some()
+    }
+
+    pub fn label(&self) -> Option<&String> {
+        self.label.as_ref()
+    }
+
+    pub fn program_step(&self) -> Option<usize> {
+        self.program_step
+    }
+
+    pub fn room(&self) -> Option<&Room> {
+        self.room.as_ref()
+    }
+
+    /// The next scheduled block and when it starts, e.g. `next: standup at
+    /// 09:30`.
+    pub fn next_block(&self) -> Option<&String> {
+        self.next_block.as_ref()
+    }
+
+    pub fn goal_status(&self) -> Option<&GoalStatus> {
+        self.goal_status.as_ref()
+    }
+
+    /// The action that can be undone now, if any.
+    pub fn undo_latest(&self) -> Option<Action> {
+        self.undo.latest()
+    }
+
+    pub fn active_break(&self) -> Option<(&Reminder, Duration)> {
+        self.reminders.active_break(self.clock.now())
+    }
+
+    pub fn reminder(&self, index: usize) -> Option<&Reminder> {
+        self.reminders.get(index)
+    }
+
+    pub fn has_reminders(&self) -> bool {
+        !self.reminders.is_empty()
+    }
+
+    pub fn has_schedule(&self) -> bool {
+        self.scheduler.is_some()
+    }
+
+    pub fn extend_by(&self) -> Duration {
+        self.config.extend_by.unwrap_or(DEF_EXTEND)
+    }
+
+    pub fn snooze_for(&self) -> Duration {
+        self.config.snooze_for.unwrap_or(DEF_SNOOZE)
+    }
+
+    pub fn remaining_secs(&self) -> u64 {
+        ceil_secs(self.timer.remaining(self.clock.now()))
+    }
+
+    pub fn is_on_break(&self) -> bool {
+        !self.timer.is_stopped() && self.phase().is_break()
+    }
+
+    pub fn phase(&self) -> Phase {
+        let pomodoro = self.pomodoro.as_ref().map(Pomodoro::phase);
+        self.room_phase.or(pomodoro).unwrap_or(Phase::Focus)
+    }
+
+    pub fn is_following(&self) -> bool {
+        matches!(self.room, Some(Room::Follow(_)))
+    }
+
+    /// Whether a Pomodoro break is on that the overlay keeps the user to.
+    /// Breaks that a room leader calls are the leader's business.
+    pub fn is_break_enforced(&self) -> bool {
+        let on_break = self
+            .pomodoro
+            .as_ref()
+            .is_some_and(|pomodoro| pomodoro.phase().is_break());
+        self.breaks_enforced && on_break && !self.timer.is_stopped()
+    }
+
+    /// Starts a session of the timer's duration, ending the one before.
+    pub fn start(&mut self) {
+        self.commit_undo();
+        if self.session.is_some() || !self.timer.is_stopped() {
+            self.stop();
+        }
+        self.timer.start(self.clock.now());
+        self.update_goal();
+        let mut session = Session::new(self.clock.wall(), self.timer.duration());
+        session.label = self.label.clone();
+        session.estimate = self.task.as_ref().map(|task| task.estimate);
+        self.session = Some(session);
+        self.fire(Event::Start);
+    }
+
+    fn start_until(&mut self, until: Until) {
+        let now = unix_secs(self.clock.wall());
+        self.timer
+            .set_duration(until.duration_from(now, &self.zone));
+        self.start();
+    }
+
+    fn start_step(&mut self, index: usize) {
+        let Some(step) = self.config.program.get(index).cloned() else {
+            return;
+        };
+        self.program_step = Some(index);
+        self.timer.set_duration(step.duration);
+        self.set_label(Some(step.name));
+        self.notices.push(Notice::Step(index));
+        self.start();
+    }
+
+    fn start_break(&mut self, duration: Duration) {
+        self.timer.set_duration(duration);
+        self.timer.start(self.clock.now());
+        self.notices.push(Notice::Break(duration));
+        self.fire(Event::Break);
+    }
+
+    /// Stops the timer. A session cut short is recorded as stopped, while
+    /// one that ran out and was still open to being extended completed.
+    pub fn stop(&mut self) {
+        let outcome = if self.ended_at.take().is_some() {
+            Outcome::Completed
+        } else {
+            Outcome::Stopped
+        };
+        if outcome == Outcome::Stopped && self.session.is_some() {
+            self.fire(Event::Stop);
+        }
+        self.end_session(outcome);
+        let stepped = self.program_step.take().is_some();
+        if mem::take(&mut self.scheduled) || stepped {
+            self.set_label(self.config.label.clone());
+        }
+        if let Some(pomodoro) = &mut self.pomodoro {
+            pomodoro.skip();
+        }
+        self.timer.stop();
+        self.timer.set_duration(DEF_TIME);
+        self.paused_by_idle = false;
+    }
+
+    /// Starts a session, or stops the current one so that it can be undone.
+    pub fn toggle(&mut self) {
+        if self.is_break_enforced() || self.is_following() {
+            return;
+        }
+        if self.session.is_none() && self.timer.is_stopped() {
+            self.start();
+        } else {
+            self.undoable(Action::Stop, Self::stop);
+        }
+    }
+
+    /// Pauses a running timer or resumes a paused one.
+    pub fn pause(&mut self) {
+        if self.is_following() {
+            return;
+        }
+        let now = self.clock.now();
+        match self.timer.state() {
+            State::Running => self.timer.pause(now, now),
+            State::Paused => {
+                self.paused_by_idle = false;
+                self.timer.resume(now);
+            }
+            State::Stopped => {}
+        }
+    }
+
+    /// Continues the running or just finished session for `by` more time.
+    pub fn extend(&mut self, by: Duration) {
+        if self.session.is_none() || self.program_step.is_some() || self.is_following() {
+            return;
+        }
+        self.undoable(Action::ChangeDuration, |this| {
+            this.ended_at = None;
+            this.timer.extend(this.clock.now(), by);
+        });
+    }
+
+    /// Makes the next session or the current countdown `by` longer.
+    #[cfg_attr(windows, allow(dead_code))]
+    pub fn lengthen(&mut self, by: Duration) {
+        match self.timer.state() {
+            State::Stopped => self.timer.set_duration(self.timer.duration() + by),
+            State::Running | State::Paused => self.timer.extend(self.clock.now(), by),
+        }
+    }
+
+    /// Makes the next session or the current countdown `by` shorter; a
+    /// session to come is kept at least `by` long.
+    #[cfg_attr(windows, allow(dead_code))]
+    pub fn shorten(&mut self, by: Duration) {
+        match self.timer.state() {
+            State::Stopped => {
+                let duration = self.timer.duration().saturating_sub(by).max(by);
+                self.timer.set_duration(duration);
+            }
+            State::Running | State::Paused => self.timer.shorten(self.clock.now(), by),
+        }
+    }
+
+    /// Ends a break early so that it can be undone.
+    pub fn skip_break(&mut self) {
+        if self.is_on_break() {
+            self.undoable(Action::SkipBreak, Self::stop);
+        }
+    }
+
+    pub fn dismiss_reminder(&mut self) {
+        self.reminders.dismiss(self.clock.now());
+    }
+
+    /// Carries out a command from `tinitime ctl` or the API. Named timers
+    /// are left to the front-end that has them.
+    pub fn handle_command(&mut self, command: Command) {
+        match command {
+            Command::Start | Command::Stop | Command::Pause if self.is_break_enforced() => {}
+            Command::Start | Command::Stop if self.is_following() => {}
+            Command::Start => {
+                if self.session.is_none() {
+                    self.start();
+                }
+            }
+            Command::Stop => {
+                if self.session.is_some() || !self.timer.is_stopped() {
+                    self.undoable(Action::Stop, Self::stop);
+                }
+            }
+            Command::Toggle => self.toggle(),
+            Command::Pause => self.pause(),
+            Command::Extend => self.extend(self.extend_by()),
+            Command::Snooze => self.extend(self.snooze_for()),
+            Command::Undo => self.undo(),
+            Command::Label(label) => self.set_label(label),
+            Command::Estimate(sessions) => self.set_estimate(sessions),
+            command @ Command::Timer(..) => log::debug!("{:?} is not available here", command),
+        }
+    }
+
+    /// Everything that needs doing regularly, for front-ends with a single
+    /// loop.
+    #[cfg_attr(windows, allow(dead_code))]
+    pub fn tick(&mut self) {
+        self.follow_room();
+        self.follow_schedule();
+        self.update_reminders();
+        self.update_timer();
+        self.expire_undo();
+        self.publish();
+    }
+
+    /// Moves the timer on; needed often while it runs.
+    pub fn update_timer(&mut self) {
+        self.poll_idle();
+        let now = self.clock.now();
+        if let Some(foreground) = &mut self.foreground {
+            let focusing = self.session.is_some() && self.timer.state() == State::Running;
+            foreground.sample(now, focusing);
+        }
+        if self.timer.tick(now) {
+            self.finish();
+        } else if self
+            .ended_at
+            .is_some_and(|ended_at| now.saturating_sub(ended_at) >= END_GRACE)
+        {
+            self.stop();
+        }
+    }
+
+    /// Moves on to the next program step or Pomodoro phase, if any.
+    fn finish(&mut self) {
+        self.notices.push(Notice::Finished);
+        self.fire(Event::Finish);
+        if self.is_following() {
+            // The leader says what comes next.
+            self.end_session(Outcome::Completed);
+        } else if let Some(index) = self.program_step {
+            self.end_session(Outcome::Completed);
+            if index + 1 < self.config.program.len() {
+                self.start_step(index + 1);
+            } else {
+                self.stop();
+            }
+        } else if let Some(pomodoro) = &mut self.pomodoro {
+            let phase = pomodoro.complete();
+            if phase.is_break() {
+                let duration = pomodoro.break_duration();
+                self.end_session(Outcome::Completed);
+                self.start_break(duration);
+            } else {
+                self.notices.push(Notice::BreakOver);
+                self.stop();
+            }
+        } else {
+            // Kept open for a while so it can still be extended or snoozed
+            // before it gets recorded.
+            self.ended_at = Some(self.clock.now());
+            self.notices.push(Notice::TimesUp);
+        }
+    }
+
+    pub fn update_reminders(&mut self) {
+        for event in self.reminders.tick(self.clock.now()) {
+            if let ReminderEvent::BreakStarted(index) = event {
+                self.notices.push(Notice::Reminder(index));
+                self.fire(Event::Break);
+            }
+        }
+    }
+
+    /// Starts scheduled blocks when they are due and keeps the "next block"
+    /// text up to date. A running program or room takes precedence.
+    pub fn follow_schedule(&mut self) {
+        let Some(scheduler) = &mut self.scheduler else {
+            return;
+        };
+        let due = scheduler
+            .tick(&self.clock, &self.zone)
+            .map(|(block, remaining)| (block.name.clone(), remaining));
+        self.next_block = scheduler.next(&self.clock, &self.zone).map(|block| {
+            let time = i18n::locale().format_time(block.hour, block.minute);
+            tr!("next-block", name = &block.name, time = time)
+        });
+
+        let Some((name, remaining)) = due else {
+            return;
+        };
+        if self.program_step.is_some() || self.is_following() || self.is_break_enforced() {
+            return;
+        }
+        if self.session.is_some() || !self.timer.is_stopped() {
+            self.stop();
+        }
+        self.timer.set_duration(remaining);
+        self.set_label(Some(name.clone()));
+        self.start();
+        self.scheduled = true;
+        self.notices.push(Notice::Block(name, remaining));
+    }
+
+    /// Takes over the room leader's timer. Sessions are still recorded here,
+    /// so every follower keeps its own history.
+    pub fn follow_room(&mut self) {
+        let Some(Room::Follow(follower)) = &self.room else {
+            return;
+        };
+        let Some(room) = follower.poll() else {
+            return;
+        };
+        let now = self.clock.now();
+        let phase_changed = self.room_phase.replace(room.phase) != Some(room.phase);
+        let focusing = room.phase == Phase::Focus && room.state != State::Stopped;
+        if self.session.is_some() && (phase_changed || !focusing) {
+            let outcome = if room.remaining.is_zero() || self.timer.remaining(now).is_zero() {
+                Outcome::Completed
+            } else {
+                Outcome::Stopped
+            };
+            self.end_session(outcome);
+        }
+
+        self.timer.set_duration(room.duration);
+        self.timer.restore(now, room.state, room.remaining);
+        if focusing && self.session.is_none() {
+            let wall = self.clock.wall();
+            let started = wall.checked_sub(self.timer.elapsed(now)).unwrap_or(wall);
+            let mut session = Session::new(started, room.duration);
+            session.label = self.label.clone();
+            session.estimate = self.task.as_ref().map(|task| task.estimate);
+            self.session = Some(session);
+        }
+    }
+
+    /// Pauses or marks the session while the user is away, as the options
+    /// say, and counts the time away as idle. A pause starts when the last
+    /// input was, so the time before going idle is all that counts.
+    fn poll_idle(&mut self) {
+        let Some(change) = self.idle.as_mut().and_then(|monitor| monitor.poll()) else {
+            return;
+        };
+        let now = self.clock.now();
+        match change {
+            IdleChange::Away(idle_time) => {
+                let since = now.saturating_sub(idle_time);
+                self.away_since = Some(since);
+                match self.config.idle_action {
+                    IdleAction::Pause if self.timer.state() == State::Running => {
+                        self.timer.pause(now, since);
+                        self.paused_by_idle = true;
+                    }
+                    IdleAction::Pause => {}
+                    IdleAction::Mark => {
+                        if let Some(session) = &mut self.session {
+                            session.interrupted = true;
+                        }
+                    }
+                }
+            }
+            IdleChange::Back => {
+                if let (Some(since), Some(session)) = (self.away_since.take(), &mut self.session) {
+                    session.idle += now.saturating_sub(since);
+                }
+                if mem::take(&mut self.paused_by_idle) {
+                    self.timer.resume(now);
+                }
+            }
+        }
+    }
+
+    pub fn suspend(&mut self, reason: SuspendReason, policy: SuspendPolicy) {
+        let aborted = self
+            .timer
+            .suspend(reason, policy, self.clock.now(), self.clock.wall());
+        if aborted {
+            self.end_session(Outcome::Aborted);
+            self.stop();
+        }
+    }
+
+    pub fn wake(&mut self, reason: SuspendReason) {
+        self.timer.wake(reason, self.clock.now(), self.clock.wall());
+        self.update_timer();
+    }
+
+    /// Applies the lock policy, and counts a locked session as away.
+    pub fn set_locked(&mut self, locked: bool) {
+        if locked {
+            self.suspend(SuspendReason::Lock, self.config.on_lock);
+        } else {
+            self.wake(SuspendReason::Lock);
+        }
+        if let Some(monitor) = &mut self.idle {
+            monitor.source_mut().set_locked(locked);
+            self.poll_idle();
+        }
+    }
+
+    /// Ends the session cut short by quitting and records what could still
+    /// have been undone.
+    pub fn close(&mut self) {
+        if self.session.is_some() {
+            self.stop();
+        }
+        self.commit_undo();
+    }
+
+    fn end_session(&mut self, outcome: Outcome) {
+        let now = self.clock.now();
+        let Some(mut session) = self.session.take() else {
+            return;
+        };
+        if let Some(since) = self.away_since.take() {
+            session.idle += now.saturating_sub(since);
+        }
+        session.elapsed = self.timer.elapsed(now);
+        session.extended = session.elapsed.saturating_sub(session.planned);
+        session.outcome = outcome;
+        if let Some(foreground) = &mut self.foreground {
+            session.apps = foreground.take();
+        }
+        if outcome == Outcome::Completed && self.program_step.is_none() && !self.is_following() {
+            self.notices.push(Notice::Completed(session.start));
+        }
+        match &mut self.deferred {
+            Some(deferred) => deferred.push(session),
+            None => self.record_session(session),
+        }
+    }
+
+    /// Keeps what the user noted about the session that started at `start`.
+    pub fn reflect(&mut self, start: SystemTime, note: Option<String>, interruptions: Option<u32>) {
+        let reflect = |session: &mut Session| {
+            session.note = note.clone();
+            session.interruptions = interruptions;
+        };
+        // The session may still be held back by an action that can be undone.
+        let held = self
+            .session
+            .iter_mut()
+            .chain(self.deferred.iter_mut().flatten())
+            .chain(
+                self.undo
+                    .states_mut()
+                    .flat_map(|snapshot| snapshot.ended.iter_mut()),
+            )
+            .find(|session| session.start == start);
+        if let Some(session) = held {
+            reflect(session);
+            return;
+        }
+        if let Some(history) = &self.history {
+            match history.update(start, reflect) {
+                Ok(true) => {}
+                Ok(false) => log::warning!("history: the reflected session is missing"),
+                Err(error) => log::warning!("history: {}", error),
+            }
+        }
+    }
+
+    fn record_session(&mut self, session: Session) {
+        if let Some(task) = &mut self.task {
+            task.record(&session);
+            self.notices.push(Notice::LabelChanged);
+        }
+        if let Some(history) = &self.history {
+            if let Err(error) = history.append(&session) {
+                log::warning!("history: {}", error);
+            }
+        }
+        log::debug!(
+            "session {} after {}s",
+            session.outcome.as_str(),
+            session.elapsed.as_secs()
+        );
+        if self.config.goal.is_some() {
+            self.goal_sessions.push(session);
+            self.update_goal();
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            timer: self.timer.clone(),
+            session: self.session.clone(),
+            pomodoro: self.pomodoro.clone(),
+            label: self.label.clone(),
+            program_step: self.program_step,
+            ended_at: self.ended_at,
+            scheduled: self.scheduled,
+            ended: vec![],
+        }
+    }
+
+    /// Runs a destructive `change` so that it can be undone for a while.
+    /// Sessions it ends are only recorded once that time is up.
+    fn undoable(&mut self, action: Action, change: impl FnOnce(&mut Self)) {
+        if self.deferred.is_some() {
+            change(self);
+            return;
+        }
+        let mut snapshot = self.snapshot();
+        self.deferred = Some(vec![]);
+        change(self);
+        snapshot.ended = self.deferred.take().unwrap_or_default();
+        self.undo.push(action, snapshot, self.clock.now());
+    }
+
+    pub fn undo(&mut self) {
+        let Some(snapshot) = self.undo.undo(self.clock.now()) else {
+            return;
+        };
+        self.timer = snapshot.timer;
+        self.session = snapshot.session;
+        self.pomodoro = snapshot.pomodoro;
+        self.program_step = snapshot.program_step;
+        self.ended_at = snapshot.ended_at;
+        self.scheduled = snapshot.scheduled;
+        self.set_label(snapshot.label);
+        self.expire_undo();
+    }
+
+    /// Records what expired undo entries held back.
+    pub fn expire_undo(&mut self) {
+        for snapshot in self.undo.expire(self.clock.now()) {
+            for session in snapshot.ended {
+                self.record_session(session);
+            }
+        }
+    }
+
+    /// Makes every pending action final, e.g. before a new session starts.
+    fn commit_undo(&mut self) {
+        for snapshot in self.undo.clear() {
+            for session in snapshot.ended {
+                self.record_session(session);
+            }
+        }
+    }
+
+    fn update_goal(&mut self) {
+        let Some(goal) = &self.config.goal else {
+            return;
+        };
+        let status = goal::status(goal, &self.goal_sessions, self.clock.wall(), &self.zone);
+        let was_reached = self
+            .goal_status
+            .as_ref()
+            .is_some_and(|status| status.reached);
+        if status.reached && !was_reached && self.goal_status.is_some() {
+            self.notices.push(Notice::GoalReached(status.clone()));
+        }
+        self.goal_status = Some(status);
+    }
+
+    pub fn set_label(&mut self, label: Option<String>) {
+        if self.task.as_ref().map(|task| &task.label) != label.as_ref() {
+            self.task = label.as_deref().and_then(|label| {
+                let sessions = self.history.as_ref()?.load().ok()?;
+                estimate::latest(&sessions, label)
+            });
+        }
+        if let Some(session) = &mut self.session {
+            session.label = label.clone();
+            session.estimate = self.task.as_ref().map(|task| task.estimate);
+        }
+        self.label = label;
+        self.notices.push(Notice::LabelChanged);
+    }
+
+    /// Estimates the current task at `sessions`, counting its sessions from
+    /// now on.
+    pub fn set_estimate(&mut self, sessions: u32) {
+        let Some(label) = self.label.clone() else {
+            return;
+        };
+        let estimate = Estimate {
+            sessions,
+            given: self.clock.wall(),
+        };
+        if let Some(session) = &mut self.session {
+            session.estimate = Some(estimate);
+        }
+        self.task = Some(Task::new(label, estimate));
+        self.notices.push(Notice::LabelChanged);
+    }
+
+    /// The label with the sessions spent on the task so far, e.g.
+    /// `report 2/3`.
+    pub fn task_label(&self) -> Option<String> {
+        let label = self.label.as_ref()?;
+        Some(match &self.task {
+            Some(task) => format!("{} {}", label, task.progress()),
+            None => label.clone(),
+        })
+    }
+
+    pub fn status(&self) -> Status {
+        let phase = if self.active_break().is_some() {
+            "break"
+        } else {
+            self.phase().as_str()
+        };
+        Status {
+            state: self.timer.state().as_str(),
+            phase,
+            remaining: self.remaining_secs(),
+            duration: self.timer.duration().as_secs(),
+            label: self.label.clone(),
+        }
+    }
+
+    /// Shares the status with the text files, the API and the room.
+    pub fn publish(&mut self) {
+        if self.api.is_some() || self.text_files.is_some() {
+            let status = self.status();
+            if let Some(text_files) = &mut self.text_files {
+                text_files.update(&status);
+            }
+            if let Some(api) = &self.api {
+                api.update(status);
+            }
+        }
+        if let Some(Room::Lead(leader)) = &self.room {
+            leader.publish(RoomState {
+                phase: self.phase(),
+                state: self.timer.state(),
+                remaining: self.timer.remaining(self.clock.now()),
+                duration: self.timer.duration(),
+            });
+        }
+    }
+
+    fn fire(&self, event: Event) {
+        if let Some(webhooks) = &self.webhooks {
+            webhooks.fire(event, &self.status());
+        }
+    }
+}
+
+/// What a destructive action may change, to be put back on undo.
+struct Snapshot {
+    timer: Timer,
+    session: Option<Session>,
+    pomodoro: Option<Pomodoro>,
+    label: Option<String>,
+    program_step: Option<usize>,
+    ended_at: Option<Duration>,
+    scheduled: bool,
+    /// Sessions the action ended, recorded only once it can't be undone.
+    ended: Vec<Session>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::RefCell,
+        env, fs,
+        path::{Path, PathBuf},
+        process,
+        rc::Rc,
+        time::UNIX_EPOCH,
+    };
+
+    use super::*;
+    use crate::{
+        clock::ManualClock, idle::FakeIdleSource, localtime::FixedZone, pomodoro::PomodoroConfig,
+        program::Step, undo::UNDO_WINDOW,
+    };
+
+    /// An idle source the test can still change once the controller has it.
+    #[derive(Clone, Default)]
+    struct SharedIdle(Rc<RefCell<FakeIdleSource>>);
+
+    impl SharedIdle {
+        fn set_idle_time(&self, idle_time: Duration) {
+            self.0.borrow_mut().idle_time = idle_time;
+        }
+    }
+
+    impl IdleSource for SharedIdle {
+        fn idle_time(&self) -> Duration {
+            self.0.borrow().idle_time()
+        }
+
+        fn is_locked(&self) -> bool {
+            self.0.borrow().is_locked()
+        }
+
+        fn set_locked(&mut self, locked: bool) {
+            self.0.borrow_mut().set_locked(locked);
+        }
+
+        fn is_screensaver_running(&self) -> bool {
+            self.0.borrow().is_screensaver_running()
+        }
+    }
+
+    /// A controller on a clock that only moves when told to, recording into
+    /// a history of its own.
+    fn controller(name: &str, config: Config) -> (Controller<ManualClock, FixedZone>, PathBuf) {
+        let dir = env::temp_dir().join(format!("tinitime-{}-{}", name, process::id()));
+        let history = History::new(dir.join("history.tsv"));
+        let clock = ManualClock::new(UNIX_EPOCH + Duration::from_secs(1_709_542_800));
+        let mut controller = Controller::new(config, clock, FixedZone(0), Some(history));
+        controller.notices();
+        (controller, dir)
+    }
+
+    /// Takes the sessions recorded so far, removing the history.
+    fn recorded(dir: &Path) -> Vec<Session> {
+        let sessions = History::new(dir.join("history.tsv"))
+            .load()
+            .unwrap_or_default();
+        let _ = fs::remove_dir_all(dir);
+        sessions
+    }
+
+    fn mins(minutes: u64) -> Duration {
+        Duration::from_secs(minutes * 60)
+    }
+
+    #[test]
+    fn pomodoro_alternates_focus_and_breaks() {
+        let config = Config {
+            pomodoro: Some(PomodoroConfig::default()),
+            ..Config::default()
+        };
+        let (mut controller, dir) = controller("controller-pomodoro", config);
+        controller.start();
+        let start = controller.clock().wall();
+        controller.clock().advance(DEF_TIME);
+        controller.update_timer();
+        assert_eq!(
+            controller.notices(),
+            [
+                Notice::Finished,
+                Notice::Completed(start),
+                Notice::Break(mins(5))
+            ]
+        );
+        assert!(controller.is_on_break());
+        assert_eq!(controller.phase(), Phase::ShortBreak);
+        assert!(!controller.has_session());
+
+        controller.clock().advance(mins(5));
+        controller.update_timer();
+        assert_eq!(controller.notices(), [Notice::Finished, Notice::BreakOver]);
+        assert!(controller.timer().is_stopped());
+        assert_eq!(controller.phase(), Phase::Focus);
+
+        let sessions = recorded(&dir);
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].outcome, Outcome::Completed);
+        assert_eq!(sessions[0].elapsed, DEF_TIME);
+    }
+
+    #[test]
+    fn program_steps_follow_each_other() {
+        let step = |name: &str, minutes| Step {
+            name: name.to_string(),
+            duration: mins(minutes),
+            color: None,
+            sound: None,
+        };
+        let config = Config {
+            label: Some("work".to_string()),
+            program: vec![step("warm up", 10), step("sprint", 5)],
+            ..Config::default()
+        };
+        let (mut controller, dir) = controller("controller-program", config);
+        controller.begin();
+        assert!(controller.notices().contains(&Notice::Step(0)));
+        assert_eq!(controller.label().map(String::as_str), Some("warm up"));
+
+        controller.clock().advance(mins(10));
+        controller.update_timer();
+        assert!(controller.notices().contains(&Notice::Step(1)));
+        assert_eq!(controller.program_step(), Some(1));
+        assert_eq!(controller.label().map(String::as_str), Some("sprint"));
+
+        controller.clock().advance(mins(5));
+        controller.update_timer();
+        assert!(controller.timer().is_stopped());
+        assert_eq!(controller.program_step(), None);
+        assert_eq!(controller.label().map(String::as_str), Some("work"));
+
+        let sessions = recorded(&dir);
+        let labels: Vec<_> = sessions
+            .iter()
+            .map(|session| (session.label.as_deref(), session.outcome))
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                (Some("warm up"), Outcome::Completed),
+                (Some("sprint"), Outcome::Completed)
+            ]
+        );
+    }
+
+    #[test]
+    fn finished_session_stays_open_for_extending() {
+        let (mut controller, dir) = controller("controller-extend", Config::default());
+        controller.start();
+        controller.clock().advance(DEF_TIME);
+        controller.update_timer();
+        assert_eq!(controller.notices(), [Notice::Finished, Notice::TimesUp]);
+        assert!(controller.has_session());
+
+        controller.extend(mins(1));
+        assert_eq!(controller.timer().state(), State::Running);
+        controller.clock().advance(mins(1));
+        controller.update_timer();
+        assert!(controller.notices().contains(&Notice::TimesUp));
+
+        controller.clock().advance(END_GRACE);
+        controller.update_timer();
+        assert!(!controller.has_session());
+        assert!(controller.timer().is_stopped());
+
+        let sessions = recorded(&dir);
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].outcome, Outcome::Completed);
+        assert_eq!(sessions[0].extended, mins(1));
+    }
+
+    #[test]
+    fn stopping_is_recorded_once_it_cannot_be_undone() {
+        let (mut controller, dir) = controller("controller-undo", Config::default());
+        controller.start();
+        controller.clock().advance(mins(1));
+        controller.toggle();
+        assert!(controller.timer().is_stopped());
+        assert_eq!(controller.undo_latest(), Some(Action::Stop));
+
+        controller.undo();
+        assert_eq!(controller.timer().state(), State::Running);
+        assert!(controller.has_session());
+        assert_eq!(controller.undo_latest(), None);
+
+        controller.toggle();
+        controller.clock().advance(UNDO_WINDOW);
+        assert_eq!(
+            History::new(dir.join("history.tsv"))
+                .load()
+                .unwrap_or_default()
+                .len(),
+            0
+        );
+        controller.expire_undo();
+        assert_eq!(controller.undo_latest(), None);
+
+        let sessions = recorded(&dir);
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].outcome, Outcome::Stopped);
+        assert_eq!(sessions[0].elapsed, mins(1));
+    }
+
+    #[test]
+    fn enforced_breaks_only_end_by_skipping() {
+        let config = Config {
+            pomodoro: Some(PomodoroConfig::default()),
+            ..Config::default()
+        };
+        let (mut controller, dir) = controller("controller-enforced", config);
+        controller.enforce_breaks();
+        controller.start();
+        controller.clock().advance(DEF_TIME);
+        controller.update_timer();
+        assert!(controller.is_break_enforced());
+
+        controller.handle_command(Command::Stop);
+        controller.toggle();
+        assert!(controller.is_on_break());
+
+        controller.skip_break();
+        assert!(controller.timer().is_stopped());
+        assert_eq!(controller.undo_latest(), Some(Action::SkipBreak));
+        recorded(&dir);
+    }
+
+    fn idle_controller(
+        name: &str,
+        action: IdleAction,
+    ) -> (Controller<ManualClock, FixedZone>, PathBuf, SharedIdle) {
+        let config = Config {
+            idle_timeout: Some(mins(5)),
+            idle_action: action,
+            ..Config::default()
+        };
+        let (mut controller, dir) = controller(name, config);
+        let idle = SharedIdle::default();
+        controller.watch_idle(idle.clone());
+        (controller, dir, idle)
+    }
+
+    #[test]
+    fn idle_pause_only_counts_the_time_before_going_away() {
+        let (mut controller, dir, idle) =
+            idle_controller("controller-idle-pause", IdleAction::Pause);
+        controller.start();
+        controller.clock().advance(mins(10));
+        idle.set_idle_time(mins(5));
+        controller.update_timer();
+        let now = controller.clock().now();
+        assert_eq!(controller.timer().state(), State::Paused);
+        assert_eq!(controller.timer().elapsed(now), mins(5));
+
+        controller.clock().advance(mins(2));
+        idle.set_idle_time(Duration::ZERO);
+        controller.update_timer();
+        let now = controller.clock().now();
+        assert_eq!(controller.timer().state(), State::Running);
+        assert_eq!(controller.timer().remaining(now), DEF_TIME - mins(5));
+
+        controller.stop();
+        let sessions = recorded(&dir);
+        assert_eq!(sessions[0].elapsed, mins(5));
+        assert_eq!(sessions[0].idle, mins(7));
+        assert!(!sessions[0].interrupted);
+    }
+
+    #[test]
+    fn idle_mark_keeps_the_session_running() {
+        let (mut controller, dir, idle) = idle_controller("controller-idle-mark", IdleAction::Mark);
+        controller.start();
+        controller.clock().advance(mins(10));
+        idle.set_idle_time(mins(5));
+        controller.update_timer();
+        assert_eq!(controller.timer().state(), State::Running);
+
+        controller.clock().advance(mins(2));
+        controller.stop();
+        let sessions = recorded(&dir);
+        assert_eq!(sessions[0].elapsed, mins(12));
+        assert_eq!(sessions[0].idle, mins(7));
+        assert!(sessions[0].interrupted);
+    }
+
+    #[test]
+    fn resuming_by_hand_is_not_undone_on_return() {
+        let (mut controller, dir, idle) =
+            idle_controller("controller-idle-resume", IdleAction::Pause);
+        controller.start();
+        controller.clock().advance(mins(10));
+        idle.set_idle_time(mins(5));
+        controller.update_timer();
+        controller.pause();
+        controller.pause();
+        assert_eq!(controller.timer().state(), State::Paused);
+
+        idle.set_idle_time(Duration::ZERO);
+        controller.update_timer();
+        assert_eq!(controller.timer().state(), State::Paused);
+        recorded(&dir);
+    }
+
+    #[test]
+    fn locking_counts_as_away() {
+        let (mut controller, dir, _idle) =
+            idle_controller("controller-idle-lock", IdleAction::Pause);
+        controller.start();
+        controller.clock().advance(mins(1));
+        controller.set_locked(true);
+        assert_eq!(controller.timer().state(), State::Paused);
+
+        controller.clock().advance(mins(3));
+        controller.set_locked(false);
+        let now = controller.clock().now();
+        assert_eq!(controller.timer().state(), State::Running);
+        assert_eq!(controller.timer().elapsed(now), mins(1));
+        recorded(&dir);
+    }
+}
@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+#[cfg(windows)]
+use windows::{
+    core::{Error, Result, PCWSTR},
+    Win32::{
+        Foundation::{E_INVALIDARG, HWND, LPARAM, WPARAM},
+        System::DataExchange::COPYDATASTRUCT,
+        UI::WindowsAndMessaging::{FindWindowW, SendMessageW, WM_COPYDATA},
+    },
+};
+
+#[cfg(windows)]
+use crate::window::WINDOW_CLASS_NAME;
+use crate::{helpers::parse_duration, history::clean_label};
+
+pub const CTL_COMMAND: usize = 0x7474;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Start,
+    Stop,
+    Toggle,
+    Pause,
+    Extend,
+    Snooze,
+    Undo,
+    Label(Option<String>),
+    /// Estimates the current task at this many sessions.
+    Estimate(u32),
+    Timer(String, TimerAction),
+}
+
+/// What to do with a named timer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TimerAction {
+    /// Adds the timer, or restarts it if one by that name exists.
+    Add(Duration),
+    Toggle,
+    Reset,
+    Remove,
+}
+
+impl Command {
+    pub fn parse<S: AsRef<str>>(args: &[S]) -> Option<Self> {
+        let (name, rest) = args.split_first()?;
+        match name.as_ref() {
+            "start" => Some(Command::Start),
+            "stop" => Some(Command::Stop),
+            "toggle" => Some(Command::Toggle),
+            "pause" => Some(Command::Pause),
+            "extend" => Some(Command::Extend),
+            "snooze" => Some(Command::Snooze),
+            "undo" => Some(Command::Undo),
+            "label" => {
+                let label: Vec<&str> = rest.iter().map(AsRef::as_ref).collect();
+                Some(Command::Label(clean_label(&label.join(" "))))
+            }
+            "estimate" => {
+                let sessions = rest.first()?.as_ref().parse().ok()?;
+                (sessions > 0).then_some(Command::Estimate(sessions))
+            }
+            "timer" => {
+                let [action, name, rest @ ..] = rest else {
+                    return None;
+                };
+                let action = match action.as_ref() {
+                    "add" => TimerAction::Add(parse_duration(rest.first()?.as_ref())?),
+                    "toggle" => TimerAction::Toggle,
+                    "reset" => TimerAction::Reset,
+                    "remove" => TimerAction::Remove,
+                    _ => return None,
+                };
+                Some(Command::Timer(clean_label(name.as_ref())?, action))
+            }
+            _ => None,
+        }
+    }
+
+    fn encode(&self) -> String {
+        match self {
+            Command::Start => "start".to_string(),
+            Command::Stop => "stop".to_string(),
+            Command::Toggle => "toggle".to_string(),
+            Command::Pause => "pause".to_string(),
+            Command::Extend => "extend".to_string(),
+            Command::Snooze => "snooze".to_string(),
+            Command::Undo => "undo".to_string(),
+            Command::Label(label) => format!("label\n{}", label.as_deref().unwrap_or_default()),
+            Command::Estimate(sessions) => format!("estimate\n{}", sessions),
+            Command::Timer(name, action) => match action {
+                TimerAction::Add(duration) => {
+                    format!("timer\nadd\n{}\n{}s", name, duration.as_secs())
+                }
+                TimerAction::Toggle => format!("timer\ntoggle\n{}", name),
+                TimerAction::Reset => format!("timer\nreset\n{}", name),
+                TimerAction::Remove => format!("timer\nremove\n{}", name),
+            },
+        }
+    }
+
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let text = std::str::from_utf8(data).ok()?;
+        let args: Vec<&str> = text.split('\n').collect();
+        Self::parse(&args)
+    }
+}
+
+#[cfg(windows)]
+impl Command {
+    /// Sends the command to an already running instance.
+    pub fn send(&self) -> Result<()> {
+        let payload = self.encode();
+        let data = COPYDATASTRUCT {
+            dwData: CTL_COMMAND,
+            cbData: payload.len() as u32,
+            lpData: payload.as_ptr() as *mut _,
+        };
+        unsafe {
+            let window = FindWindowW(WINDOW_CLASS_NAME, PCWSTR::null())?;
+            let handled = SendMessageW(
+                window,
+                WM_COPYDATA,
+                Some(WPARAM(HWND::default().0 as usize)),
+                Some(LPARAM(&data as *const _ as isize)),
+            );
+            if handled.0 == 0 {
+                return Err(Error::from(E_INVALIDARG));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+
+#[cfg(windows)]
+use crate::log;
+
+/// Why tinitime could not start or keep going.
+#[derive(Debug)]
+pub enum Error {
+    /// A file given on the command line has mistakes, one message per line.
+    Config(Vec<String>),
+    #[cfg(windows)]
+    GdiPlus(i32),
+    #[cfg(windows)]
+    Win32(windows::core::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Config(errors) => write!(f, "{}", errors.join("\n")),
+            #[cfg(windows)]
+            Error::GdiPlus(status) => write!(f, "GDI+ failed to start (status {})", status),
+            #[cfg(windows)]
+            Error::Win32(error) => write!(f, "{} ({})", error.message(), error.code()),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(windows)]
+            Error::Win32(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(windows)]
+impl From<windows::core::Error> for Error {
+    fn from(error: windows::core::Error) -> Self {
+        Error::Win32(error)
+    }
+}
+
+/// Logs a failed Win32 call that tinitime carries on without, such as a
+/// menu item that is missing or a window that did not move.
+#[cfg(windows)]
+pub trait LogFailure {
+    fn log_failure(self, call: &str);
+}
+
+#[cfg(windows)]
+impl<T> LogFailure for windows::core::Result<T> {
+    fn log_failure(self, call: &str) {
+        if let Err(error) = self {
+            log::warning!("{}: {}", call, Error::from(error));
+        }
+    }
+}
+
+#[cfg(windows)]
+impl LogFailure for windows::Win32::Foundation::BOOL {
+    fn log_failure(self, call: &str) {
+        self.ok().log_failure(call);
+    }
+}
@@ -0,0 +1,241 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::{
+    helpers::unix_secs,
+    history::{Outcome, Session},
+    localtime::{DateTime, Zone},
+};
+
+/// How many sessions a task was expected to take, as given when it was
+/// started. Sessions keep the estimate they were part of, so giving a new
+/// one starts the count over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Estimate {
+    pub sessions: u32,
+    pub given: SystemTime,
+}
+
+impl Estimate {
+    /// Parses the `SESSIONS GIVEN` form kept in history, where `GIVEN` is in
+    /// Unix seconds.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (sessions, given) = value.split_once(' ')?;
+        Some(Self {
+            sessions: sessions.parse().ok().filter(|&sessions| sessions > 0)?,
+            given: UNIX_EPOCH + Duration::from_secs(given.parse().ok()?),
+        })
+    }
+
+    pub fn to_field(self) -> String {
+        format!("{} {}", self.sessions, unix_secs(self.given))
+    }
+}
+
+/// An estimated task and the completed sessions spent on it so far.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Task {
+    pub label: String,
+    pub estimate: Estimate,
+    pub spent: u32,
+}
+
+impl Task {
+    pub fn new(label: String, estimate: Estimate) -> Self {
+        Self {
+            label,
+            estimate,
+            spent: 0,
+        }
+    }
+
+    /// Counts `session` if it completed as part of this task.
+    pub fn record(&mut self, session: &Session) {
+        if session.outcome == Outcome::Completed
+            && session.label.as_deref() == Some(self.label.as_str())
+            && session.estimate == Some(self.estimate)
+        {
+            self.spent += 1;
+        }
+    }
+
+    /// Sessions spent out of the estimate, e.g. `2/3`.
+    pub fn progress(&self) -> String {
+        format!("{}/{}", self.spent, self.estimate.sessions)
+    }
+
+    /// 1 when the estimate was spot on, down to 0 the further off it was
+    /// either way.
+    pub fn accuracy(&self) -> f64 {
+        let (spent, estimate) = (self.spent as f64, self.estimate.sessions as f64);
+        spent.min(estimate) / spent.max(estimate)
+    }
+}
+
+/// Every estimated task in the history, in the order they were estimated.
+pub fn tasks(sessions: &[Session]) -> Vec<Task> {
+    let mut tasks: Vec<Task> = vec![];
+    for session in sessions {
+        let (Some(label), Some(estimate)) = (&session.label, session.estimate) else {
+            continue;
+        };
+        let index = tasks
+            .iter()
+            .position(|task| task.estimate == estimate && task.label == *label);
+        let index = index.unwrap_or_else(|| {
+            tasks.push(Task::new(label.clone(), estimate));
+            tasks.len() - 1
+        });
+        tasks[index].record(session);
+    }
+    tasks.sort_by_key(|task| task.estimate.given);
+    tasks
+}
+
+/// The latest estimate given for `label`, if any.
+pub fn latest(sessions: &[Session], label: &str) -> Option<Task> {
+    tasks(sessions)
+        .into_iter()
+        .rev()
+        .find(|task| task.label == label)
+}
+
+/// How well the tasks estimated in one month went.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Accuracy {
+    pub year: i32,
+    pub month: u32,
+    pub tasks: usize,
+    /// The mean of the tasks' accuracy.
+    pub accuracy: f64,
+    /// Tasks that took more sessions than estimated.
+    pub over: usize,
+}
+
+/// Estimate accuracy per local month, oldest first. Tasks with no completed
+/// session yet are left out.
+pub fn accuracy_by_month(tasks: &[Task], zone: &impl Zone) -> Vec<Accuracy> {
+    let mut months: Vec<Accuracy> = vec![];
+    for task in tasks.iter().filter(|task| task.spent > 0) {
+        let date = DateTime::from_secs(zone.to_local(unix_secs(task.estimate.given)));
+        let month = match months.last_mut() {
+            Some(month) if (month.year, month.month) == (date.year, date.month) => month,
+            _ => {
+                months.push(Accuracy {
+                    year: date.year,
+                    month: date.month,
+                    tasks: 0,
+                    accuracy: 0.0,
+                    over: 0,
+                });
+                months.last_mut().unwrap()
+            }
+        };
+        // A running mean, so the months need no second pass.
+        month.tasks += 1;
+        month.accuracy += (task.accuracy() - month.accuracy) / month.tasks as f64;
+        if task.spent > task.estimate.sessions {
+            month.over += 1;
+        }
+    }
+    months
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::localtime::FixedZone;
+
+    /// 1 March 2024, 00:00 UTC.
+    const MARCH: u64 = 1_709_251_200;
+
+    fn estimate(sessions: u32, given: u64) -> Estimate {
+        Estimate {
+            sessions,
+            given: UNIX_EPOCH + Duration::from_secs(given),
+        }
+    }
+
+    fn session(label: &str, estimate: Option<Estimate>, outcome: Outcome) -> Session {
+        let start = estimate.map_or(UNIX_EPOCH, |estimate| estimate.given);
+        let mut session = Session::new(start, Duration::from_secs(25 * 60));
+        session.label = Some(label.to_string());
+        session.estimate = estimate;
+        session.outcome = outcome;
+        session
+    }
+
+    #[test]
+    fn estimates_round_trip() {
+        let given = estimate(3, MARCH);
+        assert_eq!(given.to_field(), "3 1709251200");
+        assert_eq!(Estimate::parse(&given.to_field()), Some(given));
+        assert_eq!(Estimate::parse("0 1709251200"), None);
+        assert_eq!(Estimate::parse("3"), None);
+    }
+
+    #[test]
+    fn a_new_estimate_starts_the_count_over() {
+        let first = estimate(2, MARCH);
+        let second = estimate(3, MARCH + 3600);
+        let mut task = Task::new("review".to_string(), first);
+        task.record(&session("review", Some(first), Outcome::Completed));
+        task.record(&session("review", Some(first), Outcome::Stopped));
+        task.record(&session("reading", Some(first), Outcome::Completed));
+        task.record(&session("review", None, Outcome::Completed));
+        assert_eq!(task.progress(), "1/2");
+
+        task = Task::new("review".to_string(), second);
+        task.record(&session("review", Some(first), Outcome::Completed));
+        assert_eq!(task.progress(), "0/3");
+        task.record(&session("review", Some(second), Outcome::Completed));
+        assert_eq!(task.progress(), "1/3");
+    }
+
+    #[test]
+    fn latest_picks_the_last_estimate_of_a_label() {
+        let first = estimate(2, MARCH);
+        let other = estimate(1, MARCH + 1800);
+        let second = estimate(4, MARCH + 3600);
+        let sessions = [
+            session("review", Some(first), Outcome::Completed),
+            session("review", Some(first), Outcome::Completed),
+            session("review", Some(first), Outcome::Completed),
+            session("reading", Some(other), Outcome::Completed),
+            session("review", Some(second), Outcome::Completed),
+            session("review", Some(second), Outcome::Aborted),
+        ];
+        let review = latest(&sessions, "review").unwrap();
+        assert_eq!(review.estimate, second);
+        assert_eq!(review.progress(), "1/4");
+        assert_eq!(latest(&sessions, "reading").unwrap().progress(), "1/1");
+        assert_eq!(latest(&sessions, "writing"), None);
+
+        let tasks = tasks(&sessions);
+        let progress: Vec<_> = tasks.iter().map(Task::progress).collect();
+        assert_eq!(progress, ["3/2", "1/1", "1/4"]);
+    }
+
+    #[test]
+    fn accuracy_is_kept_per_local_month() {
+        let first = estimate(2, MARCH - 1800);
+        let sessions = [
+            session("review", Some(first), Outcome::Completed),
+            session("review", Some(first), Outcome::Completed),
+            session("review", Some(first), Outcome::Completed),
+            session("reading", Some(estimate(2, MARCH)), Outcome::Completed),
+            session("writing", Some(estimate(1, MARCH + 60)), Outcome::Stopped),
+        ];
+        let months = accuracy_by_month(&tasks(&sessions), &FixedZone(3600));
+        assert_eq!(
+            months,
+            [Accuracy {
+                year: 2024,
+                month: 3,
+                tasks: 2,
+                accuracy: (2.0 / 3.0 + 0.5) / 2.0,
+                over: 1,
+            }]
+        );
+        assert_eq!(accuracy_by_month(&tasks(&sessions), &FixedZone(0)).len(), 2);
+    }
+}
@@ -0,0 +1,492 @@
+use std::{path::Path, time::Duration};
+
+use crate::{
+    helpers::{escape_json, load_file, unix_secs},
+    history::Session,
+    ics::format_utc,
+    localtime::{days_from_civil, DateTime, Zone, SECS_PER_DAY},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Ics,
+    Timewarrior,
+    Toggl,
+    Csv,
+}
+
+impl Format {
+    pub fn parse(flag: &str) -> Option<Self> {
+        match flag {
+            "--ics" => Some(Format::Ics),
+            "--timew" => Some(Format::Timewarrior),
+            "--toggl" => Some(Format::Toggl),
+            "--csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// Puts labels matching `pattern`, where `*` stands for any text, in
+/// `project`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProjectRule {
+    pub pattern: String,
+    pub project: String,
+}
+
+impl ProjectRule {
+    fn matches(&self, label: &str) -> bool {
+        let pattern = self.pattern.to_lowercase();
+        let label = label.to_lowercase();
+        let mut parts = pattern.split('*');
+        let first = parts.next().unwrap_or_default();
+        let Some(mut rest) = label.strip_prefix(first) else {
+            return false;
+        };
+        let mut parts: Vec<&str> = parts.collect();
+        let Some(last) = parts.pop() else {
+            return rest.is_empty();
+        };
+        for part in parts {
+            match rest.find(part) {
+                Some(index) => rest = &rest[index + part.len()..],
+                None => return false,
+            }
+        }
+        rest.ends_with(last)
+    }
+}
+
+/// Parses project rules, one `PATTERN = PROJECT` per line; the first
+/// matching rule wins. For example:
+///
+/// ```text
+/// review * = Acme
+/// *standup* = Internal
+/// ```
+pub fn parse_rules(source: &str) -> std::result::Result<Vec<ProjectRule>, Vec<String>> {
+    let mut errors = vec![];
+    let mut rules = vec![];
+    for (index, line) in source.lines().enumerate() {
+        let line_no = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.split_once('=') {
+            Some((pattern, project))
+                if !pattern.trim().is_empty() && !project.trim().is_empty() =>
+            {
+                rules.push(ProjectRule {
+                    pattern: pattern.trim().to_string(),
+                    project: project.trim().to_string(),
+                })
+            }
+            _ => errors.push(format!("{}: expected PATTERN = PROJECT", line_no)),
+        }
+    }
+    if errors.is_empty() {
+        Ok(rules)
+    } else {
+        Err(errors)
+    }
+}
+
+pub fn load_rules(path: &Path) -> std::result::Result<Vec<ProjectRule>, Vec<String>> {
+    load_file(path, parse_rules)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Nearest(Duration),
+    Up(Duration),
+    Down(Duration),
+}
+
+impl Rounding {
+    fn apply(self, duration: Duration) -> Duration {
+        let (unit, secs) = match self {
+            Rounding::Nearest(unit) => (unit.as_secs(), duration.as_secs() + unit.as_secs() / 2),
+            Rounding::Up(unit) => (unit.as_secs(), duration.as_secs() + unit.as_secs() - 1),
+            Rounding::Down(unit) => (unit.as_secs(), duration.as_secs()),
+        };
+        if unit == 0 {
+            return duration;
+        }
+        Duration::from_secs(secs / unit * unit)
+    }
+}
+
+/// Which sessions to export and how. Dates are local days since the epoch
+/// and both ends are included.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub rounding: Option<Rounding>,
+    pub rules: Vec<ProjectRule>,
+}
+
+/// Parses a `YYYY-MM-DD` date into local days since the epoch.
+pub fn parse_date(value: &str) -> Option<i64> {
+    let mut parts = value.splitn(3, '-');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts
+        .next()?
+        .parse()
+        .ok()
+        .filter(|month| (1..=12).contains(month))?;
+    let day = parts
+        .next()?
+        .parse()
+        .ok()
+        .filter(|day| (1..=31).contains(day))?;
+    Some(days_from_civil(year, month, day))
+}
+
+/// A session as the time trackers see it.
+pub struct Entry<'a> {
+    /// Unix seconds.
+    pub start: i64,
+    pub duration: Duration,
+    pub project: Option<String>,
+    pub description: Option<String>,
+    pub session: &'a Session,
+}
+
+impl Entry<'_> {
+    fn end(&self) -> i64 {
+        self.start + self.duration.as_secs() as i64
+    }
+}
+
+/// Picks the sessions within the dates, rounds them and puts them in
+/// projects. A rule decides the project of a label; without one, labels of
+/// the form `project/task` name it themselves. Sessions rounded down to
+/// nothing are left out.
+pub fn entries<'a>(sessions: &'a [Session], options: &Options, zone: &impl Zone) -> Vec<Entry<'a>> {
+    let mut entries = vec![];
+    for session in sessions {
+        let start = unix_secs(session.start);
+        let day = zone.to_local(start).div_euclid(SECS_PER_DAY);
+        if options.from.is_some_and(|from| day < from) || options.to.is_some_and(|to| day > to) {
+            continue;
+        }
+        let duration = match options.rounding {
+            Some(rounding) => rounding.apply(session.elapsed),
+            None => session.elapsed,
+        };
+        if duration.as_secs() == 0 {
+            continue;
+        }
+        let label = session.label.as_deref();
+        let rule = label.and_then(|label| options.rules.iter().find(|rule| rule.matches(label)));
+        let (project, description) = match (rule, label) {
+            (Some(rule), label) => (Some(rule.project.clone()), label.map(String::from)),
+            (None, Some(label)) => match label.split_once('/') {
+                Some((project, task)) => (
+                    Some(project.trim().to_string()),
+                    Some(task.trim().to_string()),
+                ),
+                None => (None, Some(label.to_string())),
+            },
+            (None, None) => (None, None),
+        };
+        entries.push(Entry {
+            start,
+            duration,
+            project,
+            description,
+            session,
+        });
+    }
+    entries
+}
+
+/// Sessions as they are to be exported, for formats that take whole
+/// sessions.
+pub fn sessions(entries: &[Entry]) -> Vec<Session> {
+    let mut sessions = vec![];
+    for entry in entries {
+        let mut session = entry.session.clone();
+        session.elapsed = entry.duration;
+        sessions.push(session);
+    }
+    sessions
+}
+
+/// Intervals in the JSON form that `timew import` reads, with the project
+/// and the task as tags and the note as annotation.
+pub fn timewarrior(entries: &[Entry]) -> String {
+    let intervals: Vec<String> = entries
+        .iter()
+        .map(|entry| {
+            let tags: Vec<String> = [&entry.project, &entry.description]
+                .into_iter()
+                .flatten()
+                .map(|tag| format!("\"{}\"", escape_json(tag)))
+                .collect();
+            let annotation = match &entry.session.note {
+                Some(note) => format!(",\"annotation\":\"{}\"", escape_json(note)),
+                None => String::new(),
+            };
+            format!(
+                "{{\"start\":\"{}\",\"end\":\"{}\",\"tags\":[{}]{}}}",
+                format_utc(entry.start),
+                format_utc(entry.end()),
+                tags.join(","),
+                annotation
+            )
+        })
+        .collect();
+    format!("[\n{}\n]\n", intervals.join(",\n"))
+}
+
+/// The CSV that Toggl Track imports, in local time. Toggl needs the email
+/// of a workspace member on every row.
+pub fn toggl(entries: &[Entry], email: &str, zone: &impl Zone) -> String {
+    let mut csv = String::from("Email,Project,Description,Start date,Start time,Duration\r\n");
+    for entry in entries {
+        let start = DateTime::from_secs(zone.to_local(entry.start));
+        let secs = entry.duration.as_secs();
+        let fields = [
+            csv_field(email),
+            csv_field(entry.project.as_deref().unwrap_or_default()),
+            csv_field(entry.description.as_deref().unwrap_or_default()),
+            format!("{:04}-{:02}-{:02}", start.year, start.month, start.day),
+            format!("{:02}:{:02}:{:02}", start.hour, start.minute, start.second),
+            format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+/// One row per session with local times and the duration in minutes.
+pub fn csv(entries: &[Entry], zone: &impl Zone) -> String {
+    let mut csv =
+        String::from("start,end,minutes,project,description,outcome,interruptions,note\r\n");
+    for entry in entries {
+        let fields = [
+            format_local(entry.start, zone),
+            format_local(entry.end(), zone),
+            format!("{:.2}", entry.duration.as_secs_f64() / 60.0),
+            csv_field(entry.project.as_deref().unwrap_or_default()),
+            csv_field(entry.description.as_deref().unwrap_or_default()),
+            entry.session.outcome.as_str().to_string(),
+            entry
+                .session
+                .interruptions
+                .map(|count| count.to_string())
+                .unwrap_or_default(),
+            csv_field(entry.session.note.as_deref().unwrap_or_default()),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+/// Quotes a field when it holds a separator, quote or line break
+/// (RFC 4180).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn format_local(utc: i64, zone: &impl Zone) -> String {
+    let time = DateTime::from_secs(zone.to_local(utc));
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        time.year, time.month, time.day, time.hour, time.minute, time.second
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+    use crate::{history::Outcome, localtime::FixedZone};
+
+    const CET: FixedZone = FixedZone(3600);
+
+    fn rule(pattern: &str) -> ProjectRule {
+        ProjectRule {
+            pattern: pattern.to_string(),
+            project: "Acme".to_string(),
+        }
+    }
+
+    /// A session starting at `hour` UTC on 4 March 2024.
+    fn session(hour: i64, minutes: u64, label: Option<&str>) -> Session {
+        let start = days_from_civil(2024, 3, 4) * SECS_PER_DAY + hour * 3600;
+        let mut session = Session::new(
+            UNIX_EPOCH + Duration::from_secs(start as u64),
+            Duration::from_secs(25 * 60),
+        );
+        session.elapsed = Duration::from_secs(minutes * 60);
+        session.label = label.map(String::from);
+        session
+    }
+
+    #[test]
+    fn rules_match_with_wildcards() {
+        assert!(rule("review").matches("Review"));
+        assert!(!rule("review").matches("review 42"));
+        assert!(rule("review *").matches("review 42"));
+        assert!(!rule("review *").matches("code review 42"));
+        assert!(rule("*standup").matches("daily standup"));
+        assert!(!rule("*standup").matches("standup notes"));
+        assert!(rule("*standup*").matches("the standup notes"));
+        assert!(rule("acme/*/bug").matches("acme/login/bug"));
+        assert!(!rule("acme/*/bug").matches("acme/login/feature"));
+        assert!(rule("a*b*c").matches("abc"));
+        assert!(!rule("a*b*c").matches("acb"));
+        assert!(rule("*").matches(""));
+    }
+
+    #[test]
+    fn parses_rules() {
+        let rules = parse_rules("# projects\nreview * = Acme\n\n*standup* = Internal\n").unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[1].pattern, "*standup*");
+        assert_eq!(rules[1].project, "Internal");
+        assert_eq!(
+            parse_rules("review\n = Acme\n").unwrap_err(),
+            [
+                "1: expected PATTERN = PROJECT",
+                "2: expected PATTERN = PROJECT"
+            ]
+        );
+    }
+
+    #[test]
+    fn rounds_durations() {
+        let unit = Duration::from_secs(15 * 60);
+        let minutes = |minutes: u64| Duration::from_secs(minutes * 60);
+        assert_eq!(Rounding::Nearest(unit).apply(minutes(7)), minutes(0));
+        assert_eq!(
+            Rounding::Nearest(unit).apply(Duration::from_secs(7 * 60 + 30)),
+            minutes(15)
+        );
+        assert_eq!(Rounding::Nearest(unit).apply(minutes(22)), minutes(15));
+        assert_eq!(Rounding::Up(unit).apply(minutes(15)), minutes(15));
+        assert_eq!(
+            Rounding::Up(unit).apply(Duration::from_secs(15 * 60 + 1)),
+            minutes(30)
+        );
+        assert_eq!(Rounding::Down(unit).apply(minutes(29)), minutes(15));
+        assert_eq!(
+            Rounding::Down(Duration::ZERO).apply(minutes(29)),
+            minutes(29)
+        );
+    }
+
+    #[test]
+    fn filters_by_local_date() {
+        // 23:30 UTC on the 4th is already the 5th in CET.
+        let mut late = session(23, 25, None);
+        late.start += Duration::from_secs(30 * 60);
+        let recorded = [session(9, 25, None), late];
+        let options = Options {
+            from: parse_date("2024-03-05"),
+            ..Options::default()
+        };
+        let picked = entries(&recorded, &options, &CET);
+        assert_eq!(picked.len(), 1);
+        assert_eq!(picked[0].session.start, recorded[1].start);
+
+        let options = Options {
+            to: parse_date("2024-03-04"),
+            ..Options::default()
+        };
+        assert_eq!(entries(&recorded, &options, &CET).len(), 1);
+        assert_eq!(entries(&recorded, &Options::default(), &CET).len(), 2);
+    }
+
+    #[test]
+    fn projects_come_from_rules_or_labels() {
+        let recorded = [
+            session(9, 25, Some("review 42")),
+            session(10, 25, Some("Acme / login page")),
+            session(11, 25, Some("reading")),
+            session(12, 25, None),
+            session(13, 5, Some("review 43")),
+        ];
+        let options = Options {
+            rounding: Some(Rounding::Down(Duration::from_secs(15 * 60))),
+            rules: vec![rule("review *")],
+            ..Options::default()
+        };
+        let entries = entries(&recorded, &options, &CET);
+        let projects: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.project.as_deref(), entry.description.as_deref()))
+            .collect();
+        assert_eq!(
+            projects,
+            [
+                (Some("Acme"), Some("review 42")),
+                (Some("Acme"), Some("login page")),
+                (None, Some("reading")),
+                (None, None),
+            ]
+        );
+        assert_eq!(entries[0].duration, Duration::from_secs(15 * 60));
+        assert_eq!(sessions(&entries)[0].elapsed, Duration::from_secs(15 * 60));
+    }
+
+    #[test]
+    fn writes_timewarrior_intervals() {
+        let mut first = session(9, 25, Some("Acme/login \"page\""));
+        first.note = Some("done".to_string());
+        let recorded = [first, session(10, 10, None)];
+        let entries = entries(&recorded, &Options::default(), &CET);
+        assert_eq!(
+            timewarrior(&entries),
+            "[\n\
+             {\"start\":\"20240304T090000Z\",\"end\":\"20240304T092500Z\",\
+             \"tags\":[\"Acme\",\"login \\\"page\\\"\"],\"annotation\":\"done\"},\n\
+             {\"start\":\"20240304T100000Z\",\"end\":\"20240304T101000Z\",\"tags\":[]}\n\
+             ]\n"
+        );
+    }
+
+    #[test]
+    fn writes_toggl_csv() {
+        let mut long = session(9, 95, Some("Acme/login, signup"));
+        long.elapsed += Duration::from_secs(5);
+        let recorded = [long, session(23, 25, Some("reading"))];
+        let entries = entries(&recorded, &Options::default(), &CET);
+        assert_eq!(
+            toggl(&entries, "me@example.com", &CET),
+            "Email,Project,Description,Start date,Start time,Duration\r\n\
+             me@example.com,Acme,\"login, signup\",2024-03-04,10:00:00,01:35:05\r\n\
+             me@example.com,,reading,2024-03-05,00:00:00,00:25:00\r\n"
+        );
+    }
+
+    #[test]
+    fn writes_csv() {
+        let mut first = session(9, 25, Some("Acme/login"));
+        first.outcome = Outcome::Completed;
+        first.interruptions = Some(2);
+        first.note = Some("said \"done\"".to_string());
+        let mut second = session(10, 10, None);
+        second.elapsed += Duration::from_secs(30);
+        let recorded = [first, second];
+        let entries = entries(&recorded, &Options::default(), &CET);
+        assert_eq!(
+            csv(&entries, &CET),
+            "start,end,minutes,project,description,outcome,interruptions,note\r\n\
+             2024-03-04 10:00:00,2024-03-04 10:25:00,25.00,Acme,login,completed,2,\"said \"\"done\"\"\"\r\n\
+             2024-03-04 11:00:00,2024-03-04 11:10:30,10.50,,,stopped,,\r\n"
+        );
+    }
+}
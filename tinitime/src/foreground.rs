@@ -0,0 +1,318 @@
+use std::{collections::HashMap, time::Duration};
+
+use crate::history::clean_label;
+
+/// Samples closer together than this are left out, so tracking stays cheap
+/// even though the timer ticks more often.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+/// Longer gaps between samples, e.g. after the machine slept, are not counted.
+const MAX_GAP: Duration = Duration::from_secs(10);
+const MAX_TITLE_CHARS: usize = 60;
+/// Stands in for applications and windows the user asked not to record.
+pub const PRIVATE: &str = "(private)";
+
+/// The application in front and the title of its window.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Foreground {
+    pub app: String,
+    pub title: String,
+}
+
+pub trait ForegroundSource {
+    fn foreground(&self) -> Option<Foreground>;
+}
+
+impl<S: ForegroundSource + ?Sized> ForegroundSource for Box<S> {
+    fn foreground(&self) -> Option<Foreground> {
+        (**self).foreground()
+    }
+}
+
+#[cfg(windows)]
+pub use system::SystemForegroundSource;
+
+/// Reports whatever the test sets.
+#[cfg(test)]
+#[derive(Default)]
+pub struct FakeForegroundSource {
+    pub foreground: Option<Foreground>,
+}
+
+#[cfg(test)]
+impl ForegroundSource for FakeForegroundSource {
+    fn foreground(&self) -> Option<Foreground> {
+        self.foreground.clone()
+    }
+}
+
+#[cfg(windows)]
+mod system {
+    use std::path::Path;
+
+    use windows::{
+        core::PWSTR,
+        Win32::{
+            Foundation::CloseHandle,
+            System::Threading::{
+                OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+                PROCESS_QUERY_LIMITED_INFORMATION,
+            },
+            UI::WindowsAndMessaging::{
+                GetForegroundWindow, GetWindowTextW, GetWindowThreadProcessId,
+            },
+        },
+    };
+
+    use super::{Foreground, ForegroundSource};
+
+    #[derive(Default)]
+    pub struct SystemForegroundSource;
+
+    impl ForegroundSource for SystemForegroundSource {
+        fn foreground(&self) -> Option<Foreground> {
+            unsafe {
+                let window = GetForegroundWindow();
+                if window.is_invalid() {
+                    return None;
+                }
+                let mut process_id = 0;
+                GetWindowThreadProcessId(window, Some(&mut process_id));
+                let process =
+                    OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id).ok()?;
+                let mut path = [0u16; 260];
+                let mut len = path.len() as u32;
+                let queried = QueryFullProcessImageNameW(
+                    process,
+                    PROCESS_NAME_WIN32,
+                    PWSTR(path.as_mut_ptr()),
+                    &mut len,
+                );
+                let _ = CloseHandle(process);
+                queried.ok()?;
+                let path = String::from_utf16_lossy(&path[..len as usize]);
+                let app = Path::new(&path).file_stem()?.to_string_lossy().into_owned();
+
+                let mut title = [0u16; 256];
+                let len = GetWindowTextW(window, &mut title).max(0) as usize;
+                let title = String::from_utf16_lossy(&title[..len]);
+                Some(Foreground { app, title })
+            }
+        }
+    }
+}
+
+/// What gets recorded. Application names are kept unless they match one of
+/// the `ignore` words; window titles only when asked for.
+#[derive(Clone, Debug, Default)]
+pub struct Privacy {
+    pub titles: bool,
+    /// Case-insensitive words that hide an application or window title.
+    pub ignore: Vec<String>,
+}
+
+impl Privacy {
+    /// The name to record the time under, e.g. `code` or `firefox: Docs`.
+    pub fn name(&self, foreground: &Foreground) -> String {
+        let ignored = |text: &str| {
+            let text = text.to_lowercase();
+            self.ignore
+                .iter()
+                .any(|word| text.contains(&word.to_lowercase()))
+        };
+        if ignored(&foreground.app) {
+            return PRIVATE.to_string();
+        }
+        let app = clean_label(&foreground.app).unwrap_or_default();
+        let title = clean_label(&foreground.title).unwrap_or_default();
+        if !self.titles || title.is_empty() {
+            return app;
+        }
+        if ignored(&title) {
+            return format!("{}: {}", app, PRIVATE);
+        }
+        let title: String = title.chars().take(MAX_TITLE_CHARS).collect();
+        format!("{}: {}", app, title)
+    }
+}
+
+/// Adds up how long each application was in front while a session ran.
+pub struct ForegroundRecorder<S: ForegroundSource> {
+    source: S,
+    privacy: Privacy,
+    last_sample: Option<Duration>,
+    totals: HashMap<String, Duration>,
+}
+
+impl<S: ForegroundSource> ForegroundRecorder<S> {
+    pub fn new(source: S, privacy: Privacy) -> Self {
+        Self {
+            source,
+            privacy,
+            last_sample: None,
+            totals: HashMap::new(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    /// Credits the time since the last sample to the application in front
+    /// now. While the timer is not running nothing is counted.
+    pub fn sample(&mut self, now: Duration, running: bool) {
+        if !running {
+            self.last_sample = None;
+            return;
+        }
+        let Some(last_sample) = self.last_sample else {
+            self.last_sample = Some(now);
+            return;
+        };
+        let gap = now.saturating_sub(last_sample);
+        if gap < SAMPLE_INTERVAL {
+            return;
+        }
+        self.last_sample = Some(now);
+        if gap > MAX_GAP {
+            return;
+        }
+        if let Some(foreground) = self.source.foreground() {
+            let name = self.privacy.name(&foreground);
+            *self.totals.entry(name).or_default() += gap;
+        }
+    }
+
+    /// Hands out the time per application, longest first, and starts over.
+    pub fn take(&mut self) -> Vec<(String, Duration)> {
+        self.last_sample = None;
+        let mut totals: Vec<_> = self
+            .totals
+            .drain()
+            .filter(|(_, time)| time.as_secs() > 0)
+            .collect();
+        totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        totals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn foreground(app: &str, title: &str) -> Option<Foreground> {
+        Some(Foreground {
+            app: app.to_string(),
+            title: title.to_string(),
+        })
+    }
+
+    fn recorder(privacy: Privacy) -> ForegroundRecorder<FakeForegroundSource> {
+        ForegroundRecorder::new(FakeForegroundSource::default(), privacy)
+    }
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    /// Samples every second from `from` to `to` with `app` in front.
+    fn run(recorder: &mut ForegroundRecorder<FakeForegroundSource>, app: &str, from: u64, to: u64) {
+        recorder.source_mut().foreground = foreground(app, "");
+        for now in from..=to {
+            recorder.sample(secs(now), true);
+        }
+    }
+
+    #[test]
+    fn adds_up_time_per_application_longest_first() {
+        let mut recorder = recorder(Privacy::default());
+        run(&mut recorder, "code", 0, 30);
+        run(&mut recorder, "firefox", 31, 40);
+        run(&mut recorder, "code", 41, 50);
+        run(&mut recorder, "slack", 51, 60);
+        assert_eq!(
+            recorder.take(),
+            [
+                ("code".to_string(), secs(40)),
+                ("firefox".to_string(), secs(10)),
+                ("slack".to_string(), secs(10)),
+            ]
+        );
+        assert_eq!(recorder.take(), []);
+    }
+
+    #[test]
+    fn samples_at_most_once_a_second() {
+        let mut recorder = recorder(Privacy::default());
+        recorder.source_mut().foreground = foreground("code", "");
+        for tenth in 0..=50 {
+            recorder.sample(Duration::from_millis(tenth * 100), true);
+        }
+        assert_eq!(recorder.take(), [("code".to_string(), secs(5))]);
+    }
+
+    #[test]
+    fn leaves_out_long_gaps_and_paused_time() {
+        let mut recorder = recorder(Privacy::default());
+        run(&mut recorder, "code", 0, 5);
+        // The machine slept.
+        run(&mut recorder, "code", 65, 70);
+        // Paused: the time until the next sample is not counted either.
+        recorder.sample(secs(71), false);
+        run(&mut recorder, "code", 80, 82);
+        assert_eq!(recorder.take(), [("code".to_string(), secs(12))]);
+    }
+
+    #[test]
+    fn counts_nothing_without_an_application_in_front() {
+        let mut recorder = recorder(Privacy::default());
+        for now in 0..10 {
+            recorder.sample(secs(now), true);
+        }
+        assert_eq!(recorder.take(), []);
+    }
+
+    #[test]
+    fn take_starts_over() {
+        let mut recorder = recorder(Privacy::default());
+        run(&mut recorder, "code", 0, 10);
+        assert_eq!(recorder.take(), [("code".to_string(), secs(10))]);
+        // The first sample after taking only marks the time.
+        run(&mut recorder, "code", 20, 23);
+        assert_eq!(recorder.take(), [("code".to_string(), secs(3))]);
+    }
+
+    #[test]
+    fn records_titles_only_when_asked() {
+        let window = foreground("firefox", "Docs\tdraft").unwrap();
+        assert_eq!(Privacy::default().name(&window), "firefox");
+        let titles = Privacy {
+            titles: true,
+            ignore: vec![],
+        };
+        assert_eq!(titles.name(&window), "firefox: Docs draft");
+        let untitled = foreground("explorer", "  ").unwrap();
+        assert_eq!(titles.name(&untitled), "explorer");
+        let long = foreground("code", &"x".repeat(100)).unwrap();
+        assert_eq!(
+            titles.name(&long),
+            format!("code: {}", "x".repeat(MAX_TITLE_CHARS))
+        );
+    }
+
+    #[test]
+    fn hides_ignored_applications_and_titles() {
+        let privacy = Privacy {
+            titles: true,
+            ignore: vec!["Bank".to_string(), "keepass".to_string()],
+        };
+        let hidden_app = foreground("KeePassXC", "Passwords").unwrap();
+        assert_eq!(privacy.name(&hidden_app), PRIVATE);
+        let hidden_title = foreground("firefox", "My bank - Login").unwrap();
+        assert_eq!(privacy.name(&hidden_title), format!("firefox: {}", PRIVATE));
+
+        let mut recorder = recorder(privacy);
+        run(&mut recorder, "keepass", 0, 5);
+        assert_eq!(recorder.take(), [(PRIVATE.to_string(), secs(5))]);
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    collections::BTreeMap,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    helpers::{parse_duration, unix_secs},
+    history::{Outcome, Session},
+    localtime::{Zone, SECS_PER_DAY},
+    stats::format_duration,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Goal {
+    Sessions(u32),
+    Time(Duration),
+}
+
+impl Goal {
+    /// A bare number is a count of completed sessions, anything with a unit
+    /// (`4h`, `90m`) is an amount of time.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.parse::<u32>() {
+            Ok(0) => None,
+            Ok(count) => Some(Goal::Sessions(count)),
+            Err(_) => parse_duration(value).map(Goal::Time),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GoalStatus {
+    pub fraction: f32,
+    pub text: String,
+    pub reached: bool,
+    pub streak: u32,
+}
+
+#[derive(Default)]
+struct Day {
+    sessions: u32,
+    time: Duration,
+}
+
+impl Day {
+    fn reached(&self, goal: &Goal) -> bool {
+        match goal {
+            Goal::Sessions(count) => self.sessions >= *count,
+            Goal::Time(time) => self.time >= *time,
+        }
+    }
+}
+
+/// Progress towards today's goal and the number of consecutive days,
+/// ending today or yesterday, on which the goal was reached.
+pub fn status(goal: &Goal, sessions: &[Session], now: SystemTime, zone: &impl Zone) -> GoalStatus {
+    let local_day = |time: SystemTime| zone.to_local(unix_secs(time)).div_euclid(SECS_PER_DAY);
+
+    let mut days: BTreeMap<i64, Day> = BTreeMap::new();
+    for session in sessions {
+        let day = days.entry(local_day(session.start)).or_default();
+        if session.outcome == Outcome::Completed {
+            day.sessions += 1;
+        }
+        day.time += session.elapsed;
+    }
+
+    let today = local_day(now);
+    let progress = days.remove(&today).unwrap_or_default();
+    let reached = progress.reached(goal);
+    let (fraction, text) = match goal {
+        Goal::Sessions(count) => (
+            progress.sessions as f32 / *count as f32,
+            format!("{}/{}", progress.sessions, count),
+        ),
+        Goal::Time(time) => (
+            progress.time.as_secs_f32() / time.as_secs_f32(),
+            format!(
+                "{}/{}",
+                format_duration(progress.time),
+                format_duration(*time)
+            ),
+        ),
+    };
+
+    let mut streak = u32::from(reached);
+    let mut day = today - 1;
+    while days.get(&day).is_some_and(|past| past.reached(goal)) {
+        streak += 1;
+        day -= 1;
+    }
+
+    GoalStatus {
+        fraction: fraction.min(1.0),
+        text,
+        reached,
+        streak,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+    use crate::localtime::{tests::EuropeZone, DateTime};
+
+    const GOAL: Goal = Goal::Sessions(2);
+
+    /// Local time in Central Europe in 2024.
+    fn at(month: u32, day: u32, hour: u32, minute: u32) -> SystemTime {
+        let local = DateTime {
+            year: 2024,
+            month,
+            day,
+            hour,
+            minute,
+            second: 0,
+        };
+        UNIX_EPOCH + Duration::from_secs(EuropeZone.to_utc(local.to_secs()) as u64)
+    }
+
+    fn completed(start: SystemTime) -> Session {
+        let mut session = Session::new(start, Duration::from_secs(1500));
+        session.elapsed = Duration::from_secs(1500);
+        session.outcome = Outcome::Completed;
+        session
+    }
+
+    #[test]
+    fn streak_crosses_midnight_and_dst() {
+        // Clocks go forward on 31 March; the session just after midnight on
+        // 1 April would fall on 31 March at the winter offset.
+        let sessions: Vec<_> = [
+            at(3, 30, 22, 0),
+            at(3, 30, 23, 40),
+            at(3, 31, 0, 30),
+            at(3, 31, 23, 30),
+            at(4, 1, 0, 30),
+            at(4, 1, 9, 0),
+        ]
+        .into_iter()
+        .map(completed)
+        .collect();
+        let status = status(&GOAL, &sessions, at(4, 1, 12, 0), &EuropeZone);
+        assert!(status.reached);
+        assert_eq!(status.text, "2/2");
+        assert_eq!(status.streak, 3);
+    }
+
+    #[test]
+    fn gap_day_breaks_the_streak() {
+        let sessions: Vec<_> = [
+            at(3, 26, 9, 0),
+            at(3, 26, 10, 0),
+            at(3, 28, 9, 0),
+            at(3, 28, 10, 0),
+            at(3, 29, 9, 0),
+            at(3, 29, 10, 0),
+        ]
+        .into_iter()
+        .map(completed)
+        .collect();
+        let status = status(&GOAL, &sessions, at(3, 29, 12, 0), &EuropeZone);
+        assert_eq!(status.streak, 2);
+    }
+
+    #[test]
+    fn unfinished_today_keeps_yesterdays_streak() {
+        let mut sessions: Vec<_> = [
+            at(3, 27, 9, 0),
+            at(3, 27, 10, 0),
+            at(3, 28, 9, 0),
+            at(3, 28, 10, 0),
+            at(3, 29, 9, 0),
+        ]
+        .into_iter()
+        .map(completed)
+        .collect();
+        // Time spent on a stopped session is no completed one.
+        let mut stopped = completed(at(3, 29, 10, 0));
+        stopped.outcome = Outcome::Stopped;
+        sessions.push(stopped);
+
+        let status = status(&GOAL, &sessions, at(3, 29, 12, 0), &EuropeZone);
+        assert!(!status.reached);
+        assert_eq!(status.text, "1/2");
+        assert_eq!(status.fraction, 0.5);
+        assert_eq!(status.streak, 2);
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    fmt, fs,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+pub const fn loword(value: isize) -> i32 {
+    (value & 0xFFFF) as i16 as i32
+}
+
+pub const fn hiword(value: isize) -> i32 {
+    ((value >> 16) & 0xFFFF) as i16 as i32
+}
+
+/// Whole seconds since the Unix epoch, or 0 for earlier times.
+pub fn unix_secs(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() as i64)
+}
+
+/// Reads a file named on the command line and parses it, putting the path
+/// in front of each error, e.g. `focus.txt:3: expected a duration`.
+pub fn load_file<T, E: fmt::Display>(
+    path: &Path,
+    parse: impl FnOnce(&str) -> Result<T, Vec<E>>,
+) -> Result<T, Vec<String>> {
+    let source =
+        fs::read_to_string(path).map_err(|error| vec![format!("{}: {}", path.display(), error)])?;
+    parse(&source).map_err(|errors| {
+        errors
+            .iter()
+            .map(|error| format!("{}:{}", path.display(), error))
+            .collect()
+    })
+}
+
+/// Parses durations like `90s`, `20m`, `1h` or `1h30m`; a bare number is
+/// taken as minutes. Zero and durations too long to count are rejected.
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    if let Ok(minutes) = value.parse::<u64>() {
+        let secs = minutes.checked_mul(60).filter(|&secs| secs > 0)?;
+        return Some(Duration::from_secs(secs));
+    }
+    let mut total: u64 = 0;
+    let mut number = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return None,
+        };
+        let secs = number.parse::<u64>().ok()?.checked_mul(unit)?;
+        total = total.checked_add(secs)?;
+        number.clear();
+    }
+    (number.is_empty() && total > 0).then(|| Duration::from_secs(total))
+}
+
+/// Escapes `value` for use inside a JSON string literal.
+pub fn escape_json(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Replaces each `{NAME}` in `template` for which `lookup` returns a value;
+/// other braces are kept as they are.
+pub fn expand_template(template: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut expanded = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let name = rest[1..].split_once('}').map_or("", |(name, _)| name);
+        match lookup(name) {
+            Some(value) => {
+                expanded.push_str(&value);
+                rest = &rest[name.len() + 2..];
+            }
+            None => {
+                expanded.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Whole seconds, counting a started one, as a countdown shows them.
+pub fn ceil_secs(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+/// Formats seconds as `M:SS`, or `H:MM:SS` from an hour up.
+pub fn format_clock(secs: u64) -> String {
+    match secs / 3600 {
+        0 => format!("{}:{:02}", secs / 60, secs % 60),
+        hours => format!("{}:{:02}:{:02}", hours, secs / 60 % 60, secs % 60),
+    }
+}
+
+/// Compares without returning early, so the time an answer takes does not
+/// reveal how much of a token was right.
+pub fn same_token(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("25"), Some(Duration::from_secs(1500)));
+        assert_eq!(parse_duration("90s"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1h30m"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("1h0m"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("1h30"), None);
+        assert_eq!(parse_duration("20min"), None);
+    }
+
+    #[test]
+    fn rejects_zero_and_overflowing_durations() {
+        assert_eq!(parse_duration("0"), None);
+        assert_eq!(parse_duration("0m0s"), None);
+        assert_eq!(parse_duration(&u64::MAX.to_string()), None);
+        assert_eq!(parse_duration(&format!("{}h", u64::MAX / 3600 + 1)), None);
+        assert_eq!(parse_duration(&format!("{}s1s", u64::MAX)), None);
+    }
+}
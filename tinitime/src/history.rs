@@ -0,0 +1,306 @@
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{estimate::Estimate, helpers::unix_secs};
+
+const HISTORY_FILE: &str = "history.tsv";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Completed,
+    Stopped,
+    Aborted,
+}
+
+impl Outcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::Completed => "completed",
+            Outcome::Stopped => "stopped",
+            Outcome::Aborted => "aborted",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "completed" => Some(Outcome::Completed),
+            "stopped" => Some(Outcome::Stopped),
+            "aborted" => Some(Outcome::Aborted),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Session {
+    pub start: SystemTime,
+    pub planned: Duration,
+    pub elapsed: Duration,
+    pub extended: Duration,
+    pub idle: Duration,
+    pub interrupted: bool,
+    pub outcome: Outcome,
+    pub label: Option<String>,
+    /// Time per application in front, longest first.
+    pub apps: Vec<(String, Duration)>,
+    /// What the user wrote down about a completed session.
+    pub note: Option<String>,
+    pub interruptions: Option<u32>,
+    pub estimate: Option<Estimate>,
+}
+
+impl Session {
+    pub fn new(start: SystemTime, planned: Duration) -> Self {
+        Self {
+            start,
+            planned,
+            elapsed: Duration::ZERO,
+            extended: Duration::ZERO,
+            idle: Duration::ZERO,
+            interrupted: false,
+            outcome: Outcome::Stopped,
+            label: None,
+            apps: vec![],
+            note: None,
+            interruptions: None,
+            estimate: None,
+        }
+    }
+
+    fn to_line(&self) -> String {
+        let mut fields = vec![
+            format!("start={}", unix_secs(self.start)),
+            format!("planned={}", self.planned.as_secs()),
+            format!("elapsed={}", self.elapsed.as_secs()),
+            format!("outcome={}", self.outcome.as_str()),
+        ];
+        if !self.extended.is_zero() {
+            fields.push(format!("extended={}", self.extended.as_secs()));
+        }
+        if !self.idle.is_zero() {
+            fields.push(format!("idle={}", self.idle.as_secs()));
+        }
+        if self.interrupted {
+            fields.push("interrupted=1".to_string());
+        }
+        if let Some(label) = &self.label {
+            fields.push(format!("label={}", label));
+        }
+        for (app, time) in &self.apps {
+            fields.push(format!("app={} {}", time.as_secs(), app));
+        }
+        if let Some(note) = &self.note {
+            fields.push(format!("note={}", note));
+        }
+        if let Some(interruptions) = self.interruptions {
+            fields.push(format!("interruptions={}", interruptions));
+        }
+        if let Some(estimate) = &self.estimate {
+            fields.push(format!("estimate={}", estimate.to_field()));
+        }
+        fields.join("\t")
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let mut session = Self::new(UNIX_EPOCH, Duration::ZERO);
+        let mut outcome = None;
+        for field in line.split('\t') {
+            let (key, value) = field.split_once('=')?;
+            match key {
+                "start" => session.start = UNIX_EPOCH + secs(value)?,
+                "planned" => session.planned = secs(value)?,
+                "elapsed" => session.elapsed = secs(value)?,
+                "extended" => session.extended = secs(value)?,
+                "idle" => session.idle = secs(value)?,
+                "interrupted" => session.interrupted = value == "1",
+                "outcome" => outcome = Outcome::parse(value),
+                "label" => session.label = Some(value.to_string()),
+                "app" => {
+                    let (time, app) = value.split_once(' ')?;
+                    session.apps.push((app.to_string(), secs(time)?));
+                }
+                "note" => session.note = Some(value.to_string()),
+                "interruptions" => session.interruptions = value.parse().ok(),
+                "estimate" => session.estimate = Estimate::parse(value),
+                _ => {}
+            }
+        }
+        session.outcome = outcome?;
+        Some(session)
+    }
+}
+
+pub struct History {
+    path: PathBuf,
+}
+
+impl History {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn open_default() -> Option<Self> {
+        Some(Self::new(data_dir()?.join(HISTORY_FILE)))
+    }
+
+    pub fn append(&self, session: &Session) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", session.to_line())
+    }
+
+    /// Changes the recorded session that started at `start`, to the second
+    /// as the file keeps it, rewriting the file. Returns whether there was
+    /// one.
+    pub fn update(&self, start: SystemTime, change: impl FnOnce(&mut Session)) -> io::Result<bool> {
+        let source = match fs::read_to_string(&self.path) {
+            Ok(source) => source,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(error) => return Err(error),
+        };
+        let start = unix_secs(start);
+        let mut change = Some(change);
+        let mut lines = vec![];
+        for line in source.lines() {
+            match Session::from_line(line) {
+                Some(mut session) if unix_secs(session.start) == start && change.is_some() => {
+                    if let Some(change) = change.take() {
+                        change(&mut session);
+                    }
+                    lines.push(session.to_line());
+                }
+                _ => lines.push(line.to_string()),
+            }
+        }
+        if change.is_some() {
+            return Ok(false);
+        }
+        let temp = self.path.with_extension("tsv.tmp");
+        let mut file = File::create(&temp)?;
+        for line in lines {
+            writeln!(file, "{}", line)?;
+        }
+        file.sync_all()?;
+        fs::rename(&temp, &self.path)?;
+        Ok(true)
+    }
+
+    pub fn load(&self) -> io::Result<Vec<Session>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(error) => return Err(error),
+        };
+        let mut sessions = vec![];
+        for line in BufReader::new(file).lines() {
+            if let Some(session) = Session::from_line(&line?) {
+                sessions.push(session);
+            }
+        }
+        Ok(sessions)
+    }
+
+    pub fn recent_labels(&self, count: usize) -> Vec<String> {
+        let mut labels: Vec<String> = vec![];
+        let sessions = self.load().unwrap_or_default();
+        for label in sessions
+            .into_iter()
+            .rev()
+            .filter_map(|session| session.label)
+        {
+            if labels.len() == count {
+                break;
+            }
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+        labels
+    }
+}
+
+/// Where history and other state are kept: `%APPDATA%\tinitime` on Windows,
+/// `$XDG_DATA_HOME/tinitime` or `~/.local/share/tinitime` elsewhere.
+pub fn data_dir() -> Option<PathBuf> {
+    #[cfg(windows)]
+    let base = PathBuf::from(env::var_os("APPDATA")?);
+    #[cfg(not(windows))]
+    let base = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".local/share"),
+    };
+    Some(base.join("tinitime"))
+}
+
+/// Labels end up in a tab-separated, line-based file, so control characters
+/// are replaced and surrounding whitespace is dropped.
+pub fn clean_label(label: &str) -> Option<String> {
+    let label: String = label
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    let label = label.trim();
+    (!label.is_empty()).then(|| label.to_string())
+}
+
+fn secs(value: &str) -> Option<Duration> {
+    value.parse().ok().map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process;
+
+    use super::*;
+
+    #[test]
+    fn updates_a_session_started_within_a_second() {
+        let dir = env::temp_dir().join(format!("tinitime-history-{}", process::id()));
+        let history = History::new(dir.join(HISTORY_FILE));
+        let start = UNIX_EPOCH + Duration::new(1_709_542_800, 250_000_000);
+        let mut session = Session::new(start, Duration::from_secs(25 * 60));
+        session.elapsed = Duration::from_secs(25 * 60);
+        session.outcome = Outcome::Completed;
+        session.label = Some("review".to_string());
+        history
+            .append(&Session::new(
+                start - Duration::from_secs(3600),
+                Duration::ZERO,
+            ))
+            .unwrap();
+        history.append(&session).unwrap();
+
+        let updated = history
+            .update(start, |session| {
+                session.note = Some("merged".to_string());
+                session.interruptions = Some(1);
+            })
+            .unwrap();
+        assert!(updated);
+        assert!(!history
+            .update(start + Duration::from_secs(1), |_| {})
+            .unwrap());
+
+        let sessions = history.load().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].note, None);
+        let session = &sessions[1];
+        assert_eq!(unix_secs(session.start), 1_709_542_800);
+        assert_eq!(session.elapsed, Duration::from_secs(25 * 60));
+        assert_eq!(session.outcome, Outcome::Completed);
+        assert_eq!(session.label.as_deref(), Some("review"));
+        assert_eq!(session.note.as_deref(), Some("merged"));
+        assert_eq!(session.interruptions, Some(1));
+    }
+}
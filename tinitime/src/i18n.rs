@@ -0,0 +1,384 @@
+use std::{collections::HashMap, env, sync::OnceLock};
+
+const FALLBACK: &str = "en";
+/// The catalogs built into the binary, by language. English has to be
+/// complete; other languages fall back to it.
+const LOCALES: [(&str, &str); 3] = [
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+    ("nl", include_str!("../locales/nl.ftl")),
+];
+
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+/// A value for a `{ $name }` placeable. Numbers also pick plural variants.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Arg {
+    Number(u64),
+    Text(String),
+}
+
+impl From<u64> for Arg {
+    fn from(value: u64) -> Self {
+        Arg::Number(value)
+    }
+}
+
+impl From<u32> for Arg {
+    fn from(value: u32) -> Self {
+        Arg::Number(value.into())
+    }
+}
+
+impl From<usize> for Arg {
+    fn from(value: usize) -> Self {
+        Arg::Number(value as u64)
+    }
+}
+
+impl From<&str> for Arg {
+    fn from(value: &str) -> Self {
+        Arg::Text(value.to_string())
+    }
+}
+
+impl From<String> for Arg {
+    fn from(value: String) -> Self {
+        Arg::Text(value)
+    }
+}
+
+impl From<&String> for Arg {
+    fn from(value: &String) -> Self {
+        Arg::Text(value.clone())
+    }
+}
+
+/// Messages of one language in a subset of the Fluent syntax: `id = text`
+/// with indented continuation lines, `-term = text`, `{ $arg }`, `{ -term }`
+/// and plural selection with `{ $arg -> [one] ... *[other] ... }`.
+#[derive(Default)]
+pub struct Catalog {
+    messages: HashMap<String, String>,
+}
+
+impl Catalog {
+    pub fn parse(source: &str) -> std::result::Result<Self, Vec<String>> {
+        let mut errors = vec![];
+        let mut messages = HashMap::new();
+        let mut current: Option<(String, String)> = None;
+        for (index, line) in source.lines().enumerate() {
+            let line_no = index + 1;
+            if line.starts_with([' ', '\t']) && !line.trim().is_empty() {
+                match &mut current {
+                    Some((_, value)) => {
+                        if !value.is_empty() {
+                            value.push('\n');
+                        }
+                        value.push_str(line.trim());
+                    }
+                    None => errors.push(format!("{}: continuation without a message", line_no)),
+                }
+                continue;
+            }
+            messages.extend(current.take());
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            match line.split_once('=') {
+                Some((id, value)) if is_identifier(id.trim()) => {
+                    current = Some((id.trim().to_string(), value.trim().to_string()));
+                }
+                _ => errors.push(format!("{}: expected ID = VALUE", line_no)),
+            }
+        }
+        messages.extend(current);
+        if errors.is_empty() {
+            Ok(Self { messages })
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// The messages of `other` this catalog lacks.
+    #[cfg(test)]
+    pub fn missing<'a>(&self, other: &'a Catalog) -> Vec<&'a str> {
+        let mut missing: Vec<&str> = other
+            .messages
+            .keys()
+            .filter(|id| !self.messages.contains_key(*id))
+            .map(String::as_str)
+            .collect();
+        missing.sort();
+        missing
+    }
+}
+
+fn is_identifier(id: &str) -> bool {
+    let name = id.strip_prefix('-').unwrap_or(id);
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The catalogs to look messages up in, best match first.
+pub struct Locale {
+    catalogs: Vec<Catalog>,
+}
+
+impl Locale {
+    /// Picks the catalog for a tag like `de-DE` or `nl_NL.UTF-8`, falling
+    /// back to English.
+    pub fn new(tag: Option<&str>) -> Self {
+        let language = tag.map(language).unwrap_or_default();
+        let mut catalogs = vec![];
+        if language != FALLBACK {
+            catalogs.extend(shipped(&language));
+        }
+        catalogs.extend(shipped(FALLBACK));
+        Self { catalogs }
+    }
+
+    fn message(&self, id: &str) -> Option<&str> {
+        self.catalogs
+            .iter()
+            .find_map(|catalog| catalog.messages.get(id))
+            .map(String::as_str)
+    }
+
+    /// Formats message `id`; unknown ids come out as they are.
+    pub fn text(&self, id: &str, args: &[(&str, Arg)]) -> String {
+        match self.message(id) {
+            Some(pattern) => self.format(pattern, args),
+            None => id.to_string(),
+        }
+    }
+
+    fn format(&self, pattern: &str, args: &[(&str, Arg)]) -> String {
+        let mut text = String::new();
+        let mut rest = pattern;
+        while let Some(open) = rest.find('{') {
+            text.push_str(&rest[..open]);
+            let Some(close) = matching_brace(&rest[open..]) else {
+                break;
+            };
+            text.push_str(&self.placeable(&rest[open + 1..open + close], args));
+            rest = &rest[open + close + 1..];
+        }
+        text.push_str(rest);
+        text
+    }
+
+    fn placeable(&self, expression: &str, args: &[(&str, Arg)]) -> String {
+        if let Some((selector, variants)) = expression.split_once("->") {
+            let variant = self.select(self.arg(selector.trim(), args), variants);
+            return self.format(variant.unwrap_or_default(), args);
+        }
+        let expression = expression.trim();
+        if let Some(term) = expression.strip_prefix('-') {
+            return self.text(&format!("-{}", term), args);
+        }
+        if let Some(literal) = expression
+            .strip_prefix('"')
+            .and_then(|literal| literal.strip_suffix('"'))
+        {
+            return literal.to_string();
+        }
+        match self.arg(expression, args) {
+            Some(Arg::Number(number)) => self.format_number(*number),
+            Some(Arg::Text(text)) => text.clone(),
+            None => format!("{{{}}}", expression),
+        }
+    }
+
+    fn arg<'a>(&self, expression: &str, args: &'a [(&str, Arg)]) -> Option<&'a Arg> {
+        let name = expression.strip_prefix('$')?;
+        args.iter()
+            .find(|(arg, _)| *arg == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Picks the variant whose key is the number itself or its plural
+    /// category, else the one marked with `*`.
+    fn select<'a>(&self, selector: Option<&Arg>, variants: &'a str) -> Option<&'a str> {
+        let keys: Vec<String> = match selector {
+            Some(Arg::Number(number)) => vec![number.to_string(), plural(*number).to_string()],
+            Some(Arg::Text(text)) => vec![text.clone()],
+            None => vec![],
+        };
+        let mut default = None;
+        for line in variants.lines().map(str::trim) {
+            let (is_default, line) = match line.strip_prefix('*') {
+                Some(line) => (true, line),
+                None => (false, line),
+            };
+            let Some((key, value)) = line.strip_prefix('[').and_then(|line| line.split_once(']'))
+            else {
+                continue;
+            };
+            let value = value.trim();
+            if keys.iter().any(|wanted| wanted == key.trim()) {
+                return Some(value);
+            }
+            if is_default {
+                default = Some(value);
+            }
+        }
+        default
+    }
+
+    pub fn format_number(&self, number: u64) -> String {
+        let digits = number.to_string();
+        let separator = self.text("-group-separator", &[]);
+        let mut grouped = String::new();
+        for (index, digit) in digits.chars().enumerate() {
+            if index > 0 && (digits.len() - index).is_multiple_of(3) {
+                grouped.push_str(&separator);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+
+    /// A time of day, e.g. `14:30`, or `2:30 PM` where clocks show 12 hours.
+    pub fn format_time(&self, hour: u32, minute: u32) -> String {
+        if self.text("-clock", &[]) != "12" {
+            return format!("{:02}:{:02}", hour, minute);
+        }
+        let suffix = if hour < 12 { "AM" } else { "PM" };
+        let hour = match hour % 12 {
+            0 => 12,
+            hour => hour,
+        };
+        format!("{}:{:02} {}", hour, minute, suffix)
+    }
+}
+
+fn shipped(language: &str) -> Option<Catalog> {
+    let (_, source) = LOCALES.iter().find(|(lang, _)| *lang == language)?;
+    Catalog::parse(source).ok()
+}
+
+/// Where the placeable that `text` starts with ends, counting nested braces.
+fn matching_brace(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The plural category of the shipped languages, which all tell one from
+/// many the same way.
+fn plural(number: u64) -> &'static str {
+    if number == 1 {
+        "one"
+    } else {
+        "other"
+    }
+}
+
+/// The language part of a locale tag, e.g. `de` for `de_DE.UTF-8`.
+fn language(tag: &str) -> String {
+    let language = tag.split(['_', '-', '.', '@']).next().unwrap_or_default();
+    language.to_ascii_lowercase()
+}
+
+/// Uses the given language, or else the one of the user.
+pub fn init(tag: Option<&str>) {
+    let tag = tag.map(String::from).or_else(detect);
+    let _ = LOCALE.set(Locale::new(tag.as_deref()));
+}
+
+pub fn locale() -> &'static Locale {
+    LOCALE.get_or_init(|| Locale::new(detect().as_deref()))
+}
+
+pub fn text(id: &str, args: &[(&str, Arg)]) -> String {
+    locale().text(id, args)
+}
+
+/// The POSIX locale variables win, so a language can be picked per run.
+fn detect() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .or_else(system::user_locale)
+}
+
+#[cfg(windows)]
+mod system {
+    use windows::Win32::Globalization::GetUserDefaultLocaleName;
+
+    pub fn user_locale() -> Option<String> {
+        let mut name = [0u16; 85];
+        let len = unsafe { GetUserDefaultLocaleName(&mut name) };
+        // The length includes the terminating null.
+        (len > 1).then(|| String::from_utf16_lossy(&name[..len as usize - 1]))
+    }
+}
+
+#[cfg(not(windows))]
+mod system {
+    pub fn user_locale() -> Option<String> {
+        None
+    }
+}
+
+macro_rules! tr {
+    ($id:expr) => {
+        $crate::i18n::text($id, &[])
+    };
+    ($id:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::text($id, &[$((stringify!($name), $crate::i18n::Arg::from($value))),+])
+    };
+}
+
+pub(crate) use tr;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_languages_are_complete() {
+        let english = shipped(FALLBACK).unwrap();
+        for (lang, source) in LOCALES {
+            match Catalog::parse(source) {
+                Ok(catalog) => assert_eq!(catalog.missing(&english), [] as [&str; 0], "{}", lang),
+                Err(errors) => panic!("{}: {:?}", lang, errors),
+            }
+        }
+    }
+
+    #[test]
+    fn command_line_messages_pick_the_plural() {
+        let english = Locale::new(Some("en_US.UTF-8"));
+        let imported = |locale: &Locale, count: usize| {
+            locale.text("cli-meetings-imported", &[("count", Arg::from(count))])
+        };
+        assert_eq!(imported(&english, 1), "1 upcoming meeting imported");
+        assert_eq!(imported(&english, 3), "3 upcoming meetings imported");
+        let german = Locale::new(Some("de-DE"));
+        assert_eq!(
+            imported(&german, 2),
+            "2 anstehende Besprechungen importiert"
+        );
+        let until = english.text("option-until", &[("value", Arg::from("\"noon\""))]);
+        assert_eq!(
+            until,
+            "--until: expected a time like 14:55, 2:55pm or tomorrow 09:00, not \"noon\""
+        );
+    }
+}
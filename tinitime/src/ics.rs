@@ -0,0 +1,995 @@
+use std::{collections::HashMap, ops::Range, time::SystemTime};
+
+#[cfg(not(windows))]
+use crate::localtime;
+use crate::{
+    helpers::unix_secs,
+    history::Session,
+    i18n::tr,
+    localtime::{DateTime, Daylight, FixedZone, RuleDay, RuleZone, Zone, SECS_PER_DAY},
+};
+
+const PRODID: &str = "-//rxcle//tinitime//EN";
+/// Content lines are folded after this many octets, not counting the CRLF.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// An event read from a calendar.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    /// Unix seconds.
+    pub start: i64,
+    pub summary: String,
+}
+
+/// Writes one VEVENT per session, with the label as summary.
+pub fn export(sessions: &[Session], now: SystemTime) -> String {
+    let stamp = format_utc(unix_secs(now));
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODID),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    for session in sessions {
+        let start = unix_secs(session.start);
+        let end = start + session.elapsed.as_secs() as i64;
+        let summary = session
+            .label
+            .clone()
+            .unwrap_or_else(|| tr!("export-summary"));
+        let mut description = tr!(
+            "export-description",
+            elapsed = session.elapsed.as_secs() / 60,
+            planned = session.planned.as_secs() / 60,
+            outcome = session.outcome.as_str(),
+        );
+        if let Some(count) = session.interruptions {
+            description.push_str(&format!(", {}", tr!("export-interruptions", count = count)));
+        }
+        if let Some(note) = &session.note {
+            description.push_str(&format!("\n{}", note));
+        }
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}-{}@tinitime", start, session.planned.as_secs()),
+            format!("DTSTAMP:{}", stamp),
+            format!("DTSTART:{}", format_utc(start)),
+            format!("DTEND:{}", format_utc(end)),
+            format!("SUMMARY:{}", escape_text(&summary)),
+            format!("DESCRIPTION:{}", escape_text(&description)),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+/// The events of a calendar, and what in it could not be read as meant.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Calendar {
+    pub events: Vec<Event>,
+    pub warnings: Vec<String>,
+}
+
+/// Reads the timed events of a calendar. All-day and cancelled events are
+/// left out. Daily and weekly recurrence rules are expanded within `range`,
+/// without their EXDATEs and the occurrences moved or cancelled on their
+/// own; other rules give the first occurrence only. Times are resolved in
+/// the calendar's VTIMEZONE blocks, then the system's zones; times in any
+/// other zone, or none at all, are taken as local time in `zone`.
+pub fn parse(source: &str, zone: &impl Zone, range: Range<i64>) -> Calendar {
+    let lines = unfold(source);
+    let mut calendar = Calendar::default();
+    let zones = Zones::read(&lines, zone, &mut calendar.warnings);
+    let mut events = vec![];
+    let mut event: Option<PendingEvent> = None;
+    for line in &lines {
+        let Some(line) = ContentLine::split(line) else {
+            continue;
+        };
+        let name = line.name.to_ascii_uppercase();
+        let Some(pending) = &mut event else {
+            if name == "BEGIN" && line.value.eq_ignore_ascii_case("VEVENT") {
+                event = Some(PendingEvent::default());
+            }
+            continue;
+        };
+        match name.as_str() {
+            "BEGIN" => pending.depth += 1,
+            "END" if pending.depth > 0 => pending.depth -= 1,
+            "END" => events.extend(event.take()),
+            _ if pending.depth > 0 => {}
+            "DTSTART" => pending.start = zones.time(&line, line.value),
+            "SUMMARY" => pending.summary = Some(unescape_text(line.value)),
+            "STATUS" => pending.cancelled = line.value.eq_ignore_ascii_case("CANCELLED"),
+            "UID" => pending.uid = Some(line.value.to_string()),
+            "RECURRENCE-ID" => {
+                pending.recurrence_id = zones.time(&line, line.value).map(|time| time.utc())
+            }
+            "RRULE" => pending.rule = Some(line.value.to_string()),
+            "EXDATE" => pending.exceptions.extend(
+                line.value
+                    .split(',')
+                    .filter_map(|value| Some(zones.time(&line, value)?.utc())),
+            ),
+            _ => {}
+        }
+    }
+    // Occurrences moved or cancelled on their own are left out of the rule.
+    let replaced: Vec<(&str, i64)> = events
+        .iter()
+        .filter_map(|event| Some((event.uid.as_deref()?, event.recurrence_id?)))
+        .collect();
+    for event in &events {
+        let (Some(start), false) = (event.start, event.cancelled) else {
+            continue;
+        };
+        let summary = event.summary.clone().unwrap_or_default();
+        let starts = match event.rule.as_deref().map(Recurrence::parse) {
+            None => vec![start.utc()],
+            Some(Some(rule)) => rule.starts(start, &range),
+            Some(None) => {
+                calendar.warnings.push(format!(
+                    "{}: only the first occurrence is imported, {:?} is not supported",
+                    summary,
+                    event.rule.as_deref().unwrap_or_default()
+                ));
+                vec![start.utc()]
+            }
+        };
+        for start in starts {
+            let moved = event.recurrence_id.is_none()
+                && event
+                    .uid
+                    .as_deref()
+                    .is_some_and(|uid| replaced.contains(&(uid, start)));
+            if !moved && !event.exceptions.contains(&start) {
+                calendar.events.push(Event {
+                    start,
+                    summary: summary.clone(),
+                });
+            }
+        }
+    }
+    calendar
+}
+
+#[derive(Default)]
+struct PendingEvent<'a> {
+    start: Option<Time<'a>>,
+    summary: Option<String>,
+    cancelled: bool,
+    uid: Option<String>,
+    /// The occurrence of a recurring event this one replaces.
+    recurrence_id: Option<i64>,
+    rule: Option<String>,
+    exceptions: Vec<i64>,
+    /// Components nested in the event, such as VALARM, whose properties
+    /// are not the event's.
+    depth: usize,
+}
+
+static UTC: FixedZone = FixedZone(0);
+
+/// A DATE-TIME value as written, with the zone it is written in.
+#[derive(Clone, Copy)]
+struct Time<'a> {
+    local: i64,
+    zone: &'a dyn Zone,
+}
+
+impl Time<'_> {
+    fn utc(&self) -> i64 {
+        self.zone.to_utc(self.local)
+    }
+}
+
+/// The zones a calendar refers to by TZID.
+struct Zones<'a> {
+    by_id: HashMap<String, Box<dyn Zone>>,
+    local: &'a dyn Zone,
+}
+
+impl<'a> Zones<'a> {
+    /// Looks up every TZID of a calendar once, warning about those that
+    /// fall back to local time.
+    fn read(lines: &[String], local: &'a dyn Zone, warnings: &mut Vec<String>) -> Self {
+        let mut by_id = time_zones(lines, warnings);
+        for line in lines.iter().filter_map(|line| ContentLine::split(line)) {
+            let Some(tzid) = line.param("TZID") else {
+                continue;
+            };
+            if by_id.contains_key(tzid) {
+                continue;
+            }
+            match well_known_zone(tzid) {
+                Some(zone) => {
+                    by_id.insert(tzid.to_string(), zone);
+                }
+                None => {
+                    let warning = format!("unknown time zone {:?}, using local time", tzid);
+                    if !warnings.contains(&warning) {
+                        warnings.push(warning);
+                    }
+                }
+            }
+        }
+        Self { by_id, local }
+    }
+
+    /// Reads a DATE-TIME value of `line`, in the zone its TZID names.
+    fn time(&self, line: &ContentLine, value: &str) -> Option<Time<'_>> {
+        if line
+            .param("VALUE")
+            .is_some_and(|value| value.eq_ignore_ascii_case("DATE"))
+        {
+            return None;
+        }
+        let (local, utc) = parse_date_time(value)?;
+        let zone = match line.param("TZID").and_then(|tzid| self.by_id.get(tzid)) {
+            _ if utc => &UTC,
+            Some(zone) => zone.as_ref(),
+            None => self.local,
+        };
+        Some(Time { local, zone })
+    }
+}
+
+/// UTC by any of its names, or a zone from the system's time zone database.
+fn well_known_zone(tzid: &str) -> Option<Box<dyn Zone>> {
+    let name = tzid.trim_start_matches('/');
+    if ["UTC", "GMT", "Z", "Etc/UTC", "Etc/GMT"]
+        .iter()
+        .any(|utc| utc.eq_ignore_ascii_case(name))
+    {
+        return Some(Box::new(FixedZone(0)));
+    }
+    #[cfg(not(windows))]
+    if let Some(zone) = localtime::named_zone(name) {
+        return Some(Box::new(zone));
+    }
+    None
+}
+
+/// Reads the VTIMEZONE blocks of a calendar, each by the rules of its
+/// latest STANDARD and DAYLIGHT observances.
+fn time_zones(lines: &[String], warnings: &mut Vec<String>) -> HashMap<String, Box<dyn Zone>> {
+    let mut zones: HashMap<String, Box<dyn Zone>> = HashMap::new();
+    let mut tzid: Option<String> = None;
+    let mut observances: Vec<Observance> = vec![];
+    let mut observance: Option<Observance> = None;
+    let mut in_zone = false;
+    for line in lines.iter().filter_map(|line| ContentLine::split(line)) {
+        let name = line.name.to_ascii_uppercase();
+        let value = line.value.to_ascii_uppercase();
+        match (name.as_str(), value.as_str()) {
+            ("BEGIN", "VTIMEZONE") => {
+                (in_zone, tzid) = (true, None);
+                observances.clear();
+            }
+            _ if !in_zone => {}
+            ("BEGIN", "STANDARD" | "DAYLIGHT") => {
+                observance = Some(Observance {
+                    daylight: value == "DAYLIGHT",
+                    ..Observance::default()
+                })
+            }
+            ("END", "STANDARD" | "DAYLIGHT") => observances.extend(observance.take()),
+            ("END", "VTIMEZONE") => {
+                in_zone = false;
+                let Some(tzid) = tzid.take() else {
+                    continue;
+                };
+                match rule_zone(&observances) {
+                    Some(zone) => {
+                        zones.insert(tzid, Box::new(zone));
+                    }
+                    None => warnings.push(format!(
+                        "the rules of time zone {:?} are not supported",
+                        tzid
+                    )),
+                }
+            }
+            ("TZID", _) if observance.is_none() => tzid = Some(line.value.to_string()),
+            _ => {
+                let Some(observance) = &mut observance else {
+                    continue;
+                };
+                match name.as_str() {
+                    "DTSTART" => {
+                        observance.start = parse_date_time(line.value).map(|(local, _)| local)
+                    }
+                    "TZOFFSETTO" => observance.offset = parse_utc_offset(line.value),
+                    "RRULE" => observance.rule = Some(parse_yearly_rule(line.value)),
+                    _ => {}
+                }
+            }
+        }
+    }
+    zones
+}
+
+/// A STANDARD or DAYLIGHT block of a VTIMEZONE.
+#[derive(Default)]
+struct Observance {
+    daylight: bool,
+    /// Local seconds in the offset before.
+    start: Option<i64>,
+    offset: Option<i64>,
+    /// `Some(None)` for a rule that is not understood.
+    rule: Option<Option<RuleDay>>,
+}
+
+/// Standard time from the latest STANDARD block and daylight saving time
+/// from the latest DAYLIGHT block, if both still recur every year. Without
+/// that, whichever block started last holds.
+fn rule_zone(observances: &[Observance]) -> Option<RuleZone> {
+    let latest = |daylight| {
+        observances
+            .iter()
+            .filter(|observance| observance.daylight == daylight)
+            .max_by_key(|observance| observance.start)
+    };
+    if let (Some(standard), Some(daylight)) = (latest(false), latest(true)) {
+        if let (Some(standard_day), Some(daylight_day)) = (standard.rule, daylight.rule) {
+            let time_of_day = |start: Option<i64>| Some(start?.rem_euclid(SECS_PER_DAY));
+            return Some(RuleZone {
+                standard: standard.offset?,
+                daylight: Some(Daylight {
+                    offset: daylight.offset?,
+                    start: (daylight_day?, time_of_day(daylight.start)?),
+                    end: (standard_day?, time_of_day(standard.start)?),
+                }),
+            });
+        }
+    }
+    let last = observances
+        .iter()
+        .max_by_key(|observance| observance.start)?;
+    if last.rule == Some(None) {
+        return None;
+    }
+    Some(RuleZone {
+        standard: last.offset?,
+        daylight: None,
+    })
+}
+
+/// Parses the yearly rules VTIMEZONE blocks change by, such as
+/// `FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU`. Rules that end are not understood.
+fn parse_yearly_rule(rule: &str) -> Option<RuleDay> {
+    let mut month = None;
+    let mut day = None;
+    for part in rule.split(';') {
+        match part.split_once('=')? {
+            ("FREQ", "YEARLY") => {}
+            ("BYMONTH", value) => {
+                month = value.parse().ok().filter(|month| (1..=12).contains(month))
+            }
+            ("BYDAY", value) => {
+                let (week, weekday) = value.split_at_checked(value.len().checked_sub(2)?)?;
+                let week = match week {
+                    "-1" => 5,
+                    week => week
+                        .trim_start_matches('+')
+                        .parse()
+                        .ok()
+                        .filter(|week| (1..=4).contains(week))?,
+                };
+                day = Some((week, parse_weekday(weekday)?));
+            }
+            _ => return None,
+        }
+    }
+    let (week, weekday) = day?;
+    Some(RuleDay {
+        month: month?,
+        week,
+        weekday,
+    })
+}
+
+/// A daily or weekly recurrence rule.
+#[derive(Debug, PartialEq, Eq)]
+struct Recurrence {
+    weekly: bool,
+    interval: i64,
+    count: Option<usize>,
+    until: Option<String>,
+    /// Sunday being 0. Weekly rules default to the weekday of the start.
+    weekdays: Vec<u32>,
+}
+
+impl Recurrence {
+    /// Parses `FREQ=DAILY` or `FREQ=WEEKLY` rules with INTERVAL, COUNT,
+    /// UNTIL and plain BYDAY parts.
+    fn parse(rule: &str) -> Option<Self> {
+        let mut recurrence = Self {
+            weekly: false,
+            interval: 1,
+            count: None,
+            until: None,
+            weekdays: vec![],
+        };
+        let mut frequency = None;
+        for part in rule.split(';') {
+            match part.split_once('=')? {
+                ("FREQ", "DAILY") => frequency = Some(false),
+                ("FREQ", "WEEKLY") => frequency = Some(true),
+                ("INTERVAL", value) => {
+                    recurrence.interval = value.parse::<u16>().ok().filter(|n| *n > 0)?.into()
+                }
+                ("COUNT", value) => recurrence.count = Some(value.parse().ok()?),
+                ("UNTIL", value) => recurrence.until = Some(value.to_string()),
+                ("BYDAY", value) => {
+                    recurrence.weekdays =
+                        value.split(',').map(parse_weekday).collect::<Option<_>>()?
+                }
+                ("WKST", "MO") => {}
+                _ => return None,
+            }
+        }
+        recurrence.weekly = frequency?;
+        Some(recurrence)
+    }
+
+    /// The starts in `range`, in UTC. Occurrences keep their local time of
+    /// day across changes to daylight saving time.
+    fn starts(&self, start: Time, range: &Range<i64>) -> Vec<i64> {
+        let first_day = start.local.div_euclid(SECS_PER_DAY);
+        let time_of_day = start.local.rem_euclid(SECS_PER_DAY);
+        let until = self.until.as_deref().and_then(|until| match until.len() {
+            // A plain date includes the whole day.
+            8 => parse_date_time(&format!("{}T235959", until))
+                .map(|(local, _)| start.zone.to_utc(local)),
+            _ => parse_date_time(until).map(|(secs, utc)| match utc {
+                true => secs,
+                false => start.zone.to_utc(secs),
+            }),
+        });
+        let mut weekdays = match (self.weekly, self.weekdays.is_empty()) {
+            (true, true) => vec![weekday(first_day)],
+            _ => self.weekdays.clone(),
+        };
+        // Weeks start on Monday.
+        weekdays.sort_by_key(|weekday| (weekday + 6) % 7);
+        let mut starts = vec![];
+        let mut count = 0;
+        for period in 0.. {
+            let period_start = first_day + period * self.interval * if self.weekly { 7 } else { 1 };
+            // Rules whose BYDAY never matches end here too.
+            if (period_start - 7) * SECS_PER_DAY > range.end {
+                break;
+            }
+            let days = match self.weekly {
+                true => {
+                    let monday = first_day - (weekday(first_day) as i64 + 6) % 7
+                        + period * 7 * self.interval;
+                    weekdays
+                        .iter()
+                        .map(|weekday| monday + (*weekday as i64 + 6) % 7)
+                        .collect()
+                }
+                false => {
+                    let day = first_day + period * self.interval;
+                    match weekdays.is_empty() || weekdays.contains(&weekday(day)) {
+                        true => vec![day],
+                        false => vec![],
+                    }
+                }
+            };
+            for day in days.into_iter().filter(|day| *day >= first_day) {
+                let utc = start.zone.to_utc(day * SECS_PER_DAY + time_of_day);
+                if utc >= range.end
+                    || until.is_some_and(|until| utc > until)
+                    || self.count.is_some_and(|max| count >= max)
+                {
+                    return starts;
+                }
+                count += 1;
+                if utc >= range.start {
+                    starts.push(utc);
+                }
+            }
+        }
+        starts
+    }
+}
+
+/// Sunday being 0.
+fn weekday(days: i64) -> u32 {
+    (days + 4).rem_euclid(7) as u32
+}
+
+fn parse_weekday(name: &str) -> Option<u32> {
+    ["SU", "MO", "TU", "WE", "TH", "FR", "SA"]
+        .iter()
+        .position(|weekday| *weekday == name)
+        .map(|weekday| weekday as u32)
+}
+
+/// Parses a UTC-OFFSET value, `+HHMM` or `-HHMMSS`.
+fn parse_utc_offset(value: &str) -> Option<i64> {
+    let (sign, digits) = match value.split_at_checked(1)? {
+        ("+", digits) => (1, digits),
+        ("-", digits) => (-1, digits),
+        _ => return None,
+    };
+    if !matches!(digits.len(), 4 | 6) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let part = |range: Range<usize>| {
+        digits
+            .get(range)
+            .map_or(0, |part| part.parse::<i64>().unwrap_or(0))
+    };
+    Some(sign * (part(0..2) * 3600 + part(2..4) * 60 + part(4..6)))
+}
+
+/// Escapes a TEXT value (RFC 5545, section 3.3.11).
+pub fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub fn unescape_text(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => unescaped.push('\n'),
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Folds a content line into CRLF-terminated lines of at most 75 octets,
+/// continuation lines starting with a space. UTF-8 sequences are never split.
+pub fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// Joins folded lines back together. Bare LF line ends are accepted too.
+pub fn unfold(source: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in source.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continued), Some(last)) => last.push_str(continued),
+            _ if line.is_empty() => {}
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+struct ContentLine<'a> {
+    name: &'a str,
+    params: Vec<(&'a str, &'a str)>,
+    value: &'a str,
+}
+
+impl<'a> ContentLine<'a> {
+    /// Splits `NAME;PARAM=VALUE;...:VALUE` into its parts. Colons and
+    /// semicolons inside quoted parameter values do not count.
+    fn split(line: &'a str) -> Option<Self> {
+        let mut quoted = false;
+        let mut boundaries = vec![];
+        let mut colon = None;
+        for (index, c) in line.char_indices() {
+            match c {
+                '"' => quoted = !quoted,
+                ';' if !quoted => boundaries.push(index),
+                ':' if !quoted => {
+                    colon = Some(index);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let colon = colon?;
+        let mut parts = boundaries
+            .iter()
+            .chain(Some(&colon))
+            .scan(0, |start, &end| {
+                let part = &line[*start..end];
+                *start = end + 1;
+                Some(part)
+            });
+        let name = parts.next()?;
+        let params = parts
+            .filter_map(|param| param.split_once('='))
+            .map(|(key, value)| (key, value.trim_matches('"')))
+            .collect();
+        Some(Self {
+            name,
+            params,
+            value: &line[colon + 1..],
+        })
+    }
+
+    fn param(&self, key: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| *value)
+    }
+}
+
+/// Parses a DATE-TIME value, `YYYYMMDDTHHMMSS` with an optional `Z` for
+/// UTC, into seconds and whether they are UTC.
+fn parse_date_time(value: &str) -> Option<(i64, bool)> {
+    let (local, utc) = match value.strip_suffix('Z') {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    let (date, time) = local.split_once('T')?;
+    if date.len() != 8
+        || time.len() != 6
+        || !date.bytes().chain(time.bytes()).all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let number = |text: &str, range: Range<usize>| text[range].parse::<u32>().ok();
+    let date_time = DateTime {
+        year: number(date, 0..4)? as i32,
+        month: number(date, 4..6).filter(|month| (1..=12).contains(month))?,
+        day: number(date, 6..8).filter(|day| (1..=31).contains(day))?,
+        hour: number(time, 0..2).filter(|hour| *hour < 24)?,
+        minute: number(time, 2..4).filter(|minute| *minute < 60)?,
+        second: number(time, 4..6).filter(|second| *second <= 60)?,
+    };
+    Some((date_time.to_secs(), utc))
+}
+
+pub fn format_utc(secs: i64) -> String {
+    let time = DateTime::from_secs(secs);
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        time.year, time.month, time.day, time.hour, time.minute, time.second
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::*;
+
+    const HOUR: i64 = 3600;
+    /// 2024-07-15 00:00 UTC, a Monday.
+    const JULY_15: i64 = 1_721_001_600;
+    const ALL_TIME: Range<i64> = i64::MIN..i64::MAX;
+
+    const BERLIN: &str = "BEGIN:VTIMEZONE
+TZID:Europe/Berlin
+BEGIN:DAYLIGHT
+TZOFFSETFROM:+0100
+TZOFFSETTO:+0200
+DTSTART:19810329T020000
+RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU
+END:DAYLIGHT
+BEGIN:STANDARD
+TZOFFSETFROM:+0200
+TZOFFSETTO:+0100
+DTSTART:19961027T030000
+RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU
+END:STANDARD
+END:VTIMEZONE
+";
+
+    /// A calendar with CRLF line ends around `body`.
+    fn wrap(body: &str) -> String {
+        format!("BEGIN:VCALENDAR\nVERSION:2.0\n{}END:VCALENDAR\n", body).replace('\n', "\r\n")
+    }
+
+    fn event(properties: &str) -> String {
+        format!("BEGIN:VEVENT\n{}END:VEVENT\n", properties)
+    }
+
+    fn starts(calendar: &Calendar) -> Vec<i64> {
+        calendar.events.iter().map(|event| event.start).collect()
+    }
+
+    #[test]
+    fn folds_lines_at_75_octets() {
+        let line = format!("DESCRIPTION:{}", "x".repeat(200));
+        let folded = fold(&line);
+        let physical: Vec<&str> = folded.strip_suffix("\r\n").unwrap().split("\r\n").collect();
+        assert_eq!(physical.len(), 3);
+        assert!(physical.iter().all(|part| part.len() <= MAX_LINE_OCTETS));
+        assert_eq!(physical[0].len(), MAX_LINE_OCTETS);
+        assert!(physical[1..].iter().all(|part| part.starts_with(' ')));
+        assert_eq!(unfold(&folded), [line]);
+        assert_eq!(fold("SHORT:line"), "SHORT:line\r\n");
+    }
+
+    #[test]
+    fn never_folds_inside_a_utf8_sequence() {
+        // 74 octets, then a three-octet euro sign that does not fit.
+        let line = format!("SUMMARY:{}€€", "a".repeat(66));
+        let folded = fold(&line);
+        let physical: Vec<&str> = folded.split("\r\n").collect();
+        assert_eq!(physical[0], format!("SUMMARY:{}", "a".repeat(66)));
+        assert_eq!(physical[1], " €€");
+        assert_eq!(unfold(&folded), [line]);
+
+        let umlauts = format!("SUMMARY:{}", "ü".repeat(100));
+        let folded = fold(&umlauts);
+        assert!(folded
+            .split("\r\n")
+            .all(|part| part.len() <= MAX_LINE_OCTETS));
+        assert_eq!(unfold(&folded), [umlauts]);
+    }
+
+    #[test]
+    fn unfolds_tabs_and_bare_line_feeds() {
+        let source = "SUMMARY:one\n two\r\n\tthree\n\nDTSTART:x\r\n";
+        assert_eq!(unfold(source), ["SUMMARY:onetwothree", "DTSTART:x"]);
+    }
+
+    #[test]
+    fn escapes_round_trip() {
+        let text = "a;b,c\\d\nnext line: done";
+        let escaped = escape_text(text);
+        assert_eq!(escaped, r"a\;b\,c\\d\nnext line: done");
+        assert_eq!(unescape_text(&escaped), text);
+        assert_eq!(escape_text("crlf\r\nend"), "crlf\\nend");
+        assert_eq!(unescape_text("upper\\Ncase\\"), "upper\ncase\\");
+    }
+
+    #[test]
+    fn exported_sessions_read_back() {
+        let mut session = Session::new(
+            UNIX_EPOCH + Duration::from_secs(JULY_15 as u64),
+            Duration::from_secs(1500),
+        );
+        session.label = Some("Write, then; review".to_string());
+        session.note = Some("a long note that goes on and on ".repeat(5));
+        let source = export(&[session], UNIX_EPOCH);
+        assert!(source
+            .split("\r\n")
+            .all(|line| line.len() <= MAX_LINE_OCTETS));
+        let calendar = parse(&source, &FixedZone(HOUR), ALL_TIME);
+        assert_eq!(
+            calendar.events,
+            [Event {
+                start: JULY_15,
+                summary: "Write, then; review".to_string(),
+            }]
+        );
+        assert!(calendar.warnings.is_empty());
+    }
+
+    #[test]
+    fn ignores_properties_of_alarms() {
+        let source = wrap(&event(
+            "BEGIN:VALARM
+ACTION:DISPLAY
+DTSTART:20240715T080000Z
+SUMMARY:Reminder
+BEGIN:X-NESTED
+SUMMARY:Deeper
+END:X-NESTED
+END:VALARM
+DTSTART:20240715T090000Z
+SUMMARY:Standup
+",
+        ));
+        let calendar = parse(&source, &UTC, ALL_TIME);
+        assert_eq!(
+            calendar.events,
+            [Event {
+                start: JULY_15 + 9 * HOUR,
+                summary: "Standup".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn leaves_out_cancelled_and_all_day_events() {
+        let source = wrap(
+            &[
+                event("DTSTART:20240715T090000Z\nSUMMARY:Cancelled\nSTATUS:CANCELLED\n"),
+                event("DTSTART;VALUE=DATE:20240715\nSUMMARY:Holiday\n"),
+                event("SUMMARY:No start\n"),
+                event("DTSTART:20240715T100000Z\nSUMMARY:Kept\nSTATUS:CONFIRMED\n"),
+            ]
+            .concat(),
+        );
+        let calendar = parse(&source, &UTC, ALL_TIME);
+        assert_eq!(starts(&calendar), [JULY_15 + 10 * HOUR]);
+    }
+
+    #[test]
+    fn resolves_time_zones() {
+        let source = wrap(
+            &[
+                BERLIN,
+                &event("DTSTART;TZID=Europe/Berlin:20240715T090000\nSUMMARY:Summer\n"),
+                &event("DTSTART;TZID=\"Europe/Berlin\":20240115T090000\nSUMMARY:Winter\n"),
+                &event("DTSTART;TZID=UTC:20240715T090000\nSUMMARY:UTC\n"),
+                &event("DTSTART;TZID=Europe/Berlin:20240715T090000Z\nSUMMARY:Z wins\n"),
+                &event("DTSTART:20240715T090000\nSUMMARY:Floating\n"),
+            ]
+            .concat(),
+        );
+        let calendar = parse(&source, &FixedZone(-5 * HOUR), ALL_TIME);
+        let january_15 = JULY_15 - 182 * 86_400;
+        assert_eq!(
+            starts(&calendar),
+            [
+                JULY_15 + 7 * HOUR,
+                january_15 + 8 * HOUR,
+                JULY_15 + 9 * HOUR,
+                JULY_15 + 9 * HOUR,
+                JULY_15 + 14 * HOUR,
+            ]
+        );
+        assert!(calendar.warnings.is_empty());
+    }
+
+    #[test]
+    fn warns_about_unknown_time_zones_once() {
+        let source = wrap(
+            &[
+                event("DTSTART;TZID=Mars/Olympus:20240715T090000\nSUMMARY:One\n"),
+                event("DTSTART;TZID=Mars/Olympus:20240716T090000\nSUMMARY:Two\n"),
+            ]
+            .concat(),
+        );
+        let calendar = parse(&source, &FixedZone(HOUR), ALL_TIME);
+        assert_eq!(
+            starts(&calendar),
+            [JULY_15 + 8 * HOUR, JULY_15 + 86_400 + 8 * HOUR]
+        );
+        assert_eq!(
+            calendar.warnings,
+            ["unknown time zone \"Mars/Olympus\", using local time"]
+        );
+    }
+
+    #[test]
+    fn warns_about_time_zones_with_rules_it_cannot_follow() {
+        let source = wrap(&[
+            "BEGIN:VTIMEZONE\nTZID:Odd\nBEGIN:STANDARD\nDTSTART:19700101T000000\nTZOFFSETTO:+0100\nRRULE:FREQ=YEARLY;BYMONTHDAY=1\nEND:STANDARD\nEND:VTIMEZONE\n",
+            &event("DTSTART;TZID=Odd:20240715T090000\nSUMMARY:Odd\n"),
+        ]
+        .concat());
+        let calendar = parse(&source, &UTC, ALL_TIME);
+        assert_eq!(starts(&calendar), [JULY_15 + 9 * HOUR]);
+        assert_eq!(
+            calendar.warnings,
+            [
+                "the rules of time zone \"Odd\" are not supported",
+                "unknown time zone \"Odd\", using local time",
+            ]
+        );
+    }
+
+    #[test]
+    fn expands_daily_rules() {
+        let source = wrap(&[
+            event("DTSTART:20240715T090000Z\nRRULE:FREQ=DAILY;COUNT=3\nSUMMARY:Three days\n"),
+            event("DTSTART:20240715T100000Z\nRRULE:FREQ=DAILY;INTERVAL=2;UNTIL=20240719\nSUMMARY:Every other day\n"),
+            event("DTSTART:20240719T110000Z\nRRULE:FREQ=DAILY;BYDAY=MO,FR;COUNT=3\nSUMMARY:Weekdays\n"),
+        ]
+        .concat());
+        let calendar = parse(&source, &UTC, ALL_TIME);
+        let day = 86_400;
+        assert_eq!(
+            starts(&calendar),
+            [
+                JULY_15 + 9 * HOUR,
+                JULY_15 + day + 9 * HOUR,
+                JULY_15 + 2 * day + 9 * HOUR,
+                JULY_15 + 10 * HOUR,
+                JULY_15 + 2 * day + 10 * HOUR,
+                JULY_15 + 4 * day + 10 * HOUR,
+                JULY_15 + 4 * day + 11 * HOUR,
+                JULY_15 + 7 * day + 11 * HOUR,
+                JULY_15 + 11 * day + 11 * HOUR,
+            ]
+        );
+    }
+
+    #[test]
+    fn expands_weekly_rules_in_local_time() {
+        // Fortnightly on Mondays and Wednesdays, across the change to
+        // winter time on 2024-10-27.
+        let source = wrap(&[
+            BERLIN,
+            &event("DTSTART;TZID=Europe/Berlin:20241016T090000\nRRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;UNTIL=20241030T080000Z\nSUMMARY:Planning\n"),
+        ]
+        .concat());
+        let calendar = parse(&source, &UTC, ALL_TIME);
+        let october_14 = JULY_15 + 91 * 86_400;
+        assert_eq!(
+            starts(&calendar),
+            [
+                october_14 + 2 * 86_400 + 7 * HOUR,
+                october_14 + 14 * 86_400 + 8 * HOUR,
+                october_14 + 16 * 86_400 + 8 * HOUR,
+            ]
+        );
+    }
+
+    #[test]
+    fn expands_only_within_the_range() {
+        let source = wrap(&event(
+            "DTSTART:20240101T090000Z\nRRULE:FREQ=WEEKLY\nSUMMARY:Forever\n",
+        ));
+        let range = JULY_15..JULY_15 + 14 * 86_400;
+        let calendar = parse(&source, &UTC, range);
+        assert_eq!(
+            starts(&calendar),
+            [JULY_15 + 9 * HOUR, JULY_15 + 7 * 86_400 + 9 * HOUR]
+        );
+        // COUNT still counts the occurrences before the range.
+        let source = wrap(&event(
+            "DTSTART:20240701T090000Z\nRRULE:FREQ=WEEKLY;COUNT=3\nSUMMARY:Three\n",
+        ));
+        let calendar = parse(&source, &UTC, JULY_15..i64::MAX);
+        assert_eq!(starts(&calendar), [JULY_15 + 9 * HOUR]);
+    }
+
+    #[test]
+    fn leaves_out_excluded_and_moved_occurrences() {
+        let source = wrap(&[
+            event("UID:standup\nDTSTART:20240715T090000Z\nRRULE:FREQ=DAILY;COUNT=4\nEXDATE:20240716T090000Z,20240717T090000Z\nSUMMARY:Standup\n"),
+            event("UID:standup\nRECURRENCE-ID:20240718T090000Z\nDTSTART:20240718T150000Z\nSUMMARY:Standup\n"),
+            event("UID:review\nDTSTART:20240715T120000Z\nRRULE:FREQ=DAILY;COUNT=2\nSUMMARY:Review\n"),
+            event("UID:review\nRECURRENCE-ID:20240716T120000Z\nDTSTART:20240716T120000Z\nSTATUS:CANCELLED\n"),
+        ]
+        .concat());
+        let calendar = parse(&source, &UTC, ALL_TIME);
+        assert_eq!(
+            starts(&calendar),
+            [
+                JULY_15 + 9 * HOUR,
+                JULY_15 + 3 * 86_400 + 15 * HOUR,
+                JULY_15 + 12 * HOUR,
+            ]
+        );
+    }
+
+    #[test]
+    fn imports_the_first_occurrence_of_other_rules() {
+        let source = wrap(&event(
+            "DTSTART:20240715T090000Z\nRRULE:FREQ=MONTHLY;BYDAY=3MO\nSUMMARY:Monthly\n",
+        ));
+        let calendar = parse(&source, &UTC, ALL_TIME);
+        assert_eq!(starts(&calendar), [JULY_15 + 9 * HOUR]);
+        assert_eq!(
+            calendar.warnings,
+            ["Monthly: only the first occurrence is imported, \"FREQ=MONTHLY;BYDAY=3MO\" is not supported"]
+        );
+    }
+}
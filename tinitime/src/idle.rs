@@ -0,0 +1,227 @@
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IdleAction {
+    #[default]
+    Pause,
+    Mark,
+}
+
+impl IdleAction {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pause" => Some(IdleAction::Pause),
+            "mark" => Some(IdleAction::Mark),
+            _ => None,
+        }
+    }
+}
+
+pub trait IdleSource {
+    fn idle_time(&self) -> Duration;
+    fn is_locked(&self) -> bool;
+    /// Only the window hears of the session being locked, so it passes that
+    /// on.
+    fn set_locked(&mut self, locked: bool);
+    fn is_screensaver_running(&self) -> bool;
+}
+
+impl<S: IdleSource + ?Sized> IdleSource for Box<S> {
+    fn idle_time(&self) -> Duration {
+        (**self).idle_time()
+    }
+
+    fn is_locked(&self) -> bool {
+        (**self).is_locked()
+    }
+
+    fn set_locked(&mut self, locked: bool) {
+        (**self).set_locked(locked);
+    }
+
+    fn is_screensaver_running(&self) -> bool {
+        (**self).is_screensaver_running()
+    }
+}
+
+#[cfg(windows)]
+pub use system::SystemIdleSource;
+
+#[cfg(windows)]
+mod system {
+    use std::{ffi::c_void, mem::size_of, time::Duration};
+
+    use windows::Win32::{
+        System::SystemInformation::GetTickCount,
+        UI::{
+            Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO},
+            WindowsAndMessaging::{
+                SystemParametersInfoW, SPI_GETSCREENSAVERRUNNING,
+                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+            },
+        },
+    };
+
+    use super::IdleSource;
+
+    #[derive(Default)]
+    pub struct SystemIdleSource {
+        locked: bool,
+    }
+
+    impl IdleSource for SystemIdleSource {
+        fn idle_time(&self) -> Duration {
+            let mut info = LASTINPUTINFO {
+                cbSize: size_of::<LASTINPUTINFO>() as u32,
+                ..Default::default()
+            };
+            unsafe {
+                if !GetLastInputInfo(&mut info).as_bool() {
+                    return Duration::ZERO;
+                }
+                Duration::from_millis(GetTickCount().wrapping_sub(info.dwTime) as u64)
+            }
+        }
+
+        fn is_locked(&self) -> bool {
+            self.locked
+        }
+
+        fn set_locked(&mut self, locked: bool) {
+            self.locked = locked;
+        }
+
+        fn is_screensaver_running(&self) -> bool {
+            let mut running = 0i32;
+            unsafe {
+                SystemParametersInfoW(
+                    SPI_GETSCREENSAVERRUNNING,
+                    0,
+                    Some(&mut running as *mut _ as *mut c_void),
+                    SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+                )
+                .is_ok()
+                    && running != 0
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdleChange {
+    /// The user went away; carries how long ago the last input was.
+    Away(Duration),
+    Back,
+}
+
+pub struct IdleMonitor<S: IdleSource> {
+    source: S,
+    threshold: Duration,
+    away: bool,
+}
+
+impl<S: IdleSource> IdleMonitor<S> {
+    pub fn new(source: S, threshold: Duration) -> Self {
+        Self {
+            source,
+            threshold,
+            away: false,
+        }
+    }
+
+    pub fn source_mut(&mut self) -> &mut S {
+        &mut self.source
+    }
+
+    pub fn poll(&mut self) -> Option<IdleChange> {
+        let idle_time = self.source.idle_time();
+        let away = idle_time >= self.threshold
+            || self.source.is_locked()
+            || self.source.is_screensaver_running();
+        if away == self.away {
+            return None;
+        }
+        self.away = away;
+        Some(if away {
+            IdleChange::Away(idle_time)
+        } else {
+            IdleChange::Back
+        })
+    }
+}
+
+/// Reports whatever the test sets.
+#[cfg(test)]
+#[derive(Default)]
+pub struct FakeIdleSource {
+    pub idle_time: Duration,
+    pub locked: bool,
+    pub screensaver: bool,
+}
+
+#[cfg(test)]
+impl IdleSource for FakeIdleSource {
+    fn idle_time(&self) -> Duration {
+        self.idle_time
+    }
+
+    fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    fn set_locked(&mut self, locked: bool) {
+        self.locked = locked;
+    }
+
+    fn is_screensaver_running(&self) -> bool {
+        self.screensaver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLD: Duration = Duration::from_secs(300);
+
+    fn monitor() -> IdleMonitor<FakeIdleSource> {
+        IdleMonitor::new(FakeIdleSource::default(), THRESHOLD)
+    }
+
+    #[test]
+    fn away_once_idle_past_threshold() {
+        let mut monitor = monitor();
+        monitor.source_mut().idle_time = THRESHOLD - Duration::from_secs(1);
+        assert_eq!(monitor.poll(), None);
+        monitor.source_mut().idle_time = THRESHOLD;
+        assert_eq!(monitor.poll(), Some(IdleChange::Away(THRESHOLD)));
+        monitor.source_mut().idle_time = THRESHOLD * 2;
+        assert_eq!(monitor.poll(), None);
+        monitor.source_mut().idle_time = Duration::ZERO;
+        assert_eq!(monitor.poll(), Some(IdleChange::Back));
+        assert_eq!(monitor.poll(), None);
+    }
+
+    #[test]
+    fn away_while_locked() {
+        let mut monitor = monitor();
+        monitor.source_mut().locked = true;
+        assert_eq!(monitor.poll(), Some(IdleChange::Away(Duration::ZERO)));
+        monitor.source_mut().locked = false;
+        assert_eq!(monitor.poll(), Some(IdleChange::Back));
+    }
+
+    #[test]
+    fn away_while_screensaver_runs() {
+        let mut monitor = monitor();
+        monitor.source_mut().idle_time = Duration::from_secs(60);
+        monitor.source_mut().screensaver = true;
+        assert_eq!(
+            monitor.poll(),
+            Some(IdleChange::Away(Duration::from_secs(60)))
+        );
+        monitor.source_mut().screensaver = false;
+        monitor.source_mut().idle_time = Duration::ZERO;
+        assert_eq!(monitor.poll(), Some(IdleChange::Back));
+    }
+}
@@ -0,0 +1,708 @@
+pub const SECS_PER_DAY: i64 = 86_400;
+
+/// Civil date and time of day, without any time zone attached.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl DateTime {
+    pub fn from_secs(secs: i64) -> Self {
+        let days = secs.div_euclid(SECS_PER_DAY);
+        let time = secs.rem_euclid(SECS_PER_DAY) as u32;
+        let (year, month, day) = civil_from_days(days);
+        Self {
+            year,
+            month,
+            day,
+            hour: time / 3600,
+            minute: time / 60 % 60,
+            second: time % 60,
+        }
+    }
+
+    pub fn to_secs(self) -> i64 {
+        days_from_civil(self.year, self.month, self.day) * SECS_PER_DAY
+            + (self.hour * 3600 + self.minute * 60 + self.second) as i64
+    }
+}
+
+/// Offset of local time from UTC in seconds at a given UTC instant.
+pub trait Zone {
+    fn offset_at(&self, utc: i64) -> i64;
+
+    fn to_local(&self, utc: i64) -> i64 {
+        utc + self.offset_at(utc)
+    }
+
+    /// Maps local seconds back to UTC. Ambiguous times (when clocks go back)
+    /// resolve to the earlier instant; times skipped when clocks go forward
+    /// are moved forward by the size of the gap.
+    fn to_utc(&self, local: i64) -> i64 {
+        let before = self.offset_at(local - SECS_PER_DAY / 2);
+        let after = self.offset_at(local + SECS_PER_DAY / 2);
+        let candidates = [local - before, local - after];
+        candidates
+            .into_iter()
+            .filter(|utc| self.to_local(*utc) == local)
+            .min()
+            .unwrap_or(local - before)
+    }
+}
+
+#[cfg(not(windows))]
+pub use system::named_zone;
+pub use system::SystemZone;
+
+/// The same offset all year, e.g. UTC.
+pub struct FixedZone(pub i64);
+
+impl Zone for FixedZone {
+    fn offset_at(&self, _utc: i64) -> i64 {
+        self.0
+    }
+}
+
+/// A day of the year as time zone rules give it: the `week`th `weekday`
+/// (Sunday being 0) of `month`, where week 5 means the last one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RuleDay {
+    pub month: u32,
+    pub week: u32,
+    pub weekday: u32,
+}
+
+impl RuleDay {
+    /// Days since the epoch of this day in `year`.
+    fn in_year(self, year: i32) -> i64 {
+        let first = days_from_civil(year, self.month, 1);
+        let first_weekday = (first + 4).rem_euclid(7);
+        let day = first
+            + (self.weekday as i64 - first_weekday).rem_euclid(7)
+            + 7 * (self.week as i64 - 1);
+        let next_month = match self.month {
+            12 => days_from_civil(year + 1, 1, 1),
+            month => days_from_civil(year, month + 1, 1),
+        };
+        if day >= next_month {
+            day - 7
+        } else {
+            day
+        }
+    }
+}
+
+/// Daylight saving time: its offset, and the days and local times of day in
+/// seconds on which it starts and ends each year.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Daylight {
+    pub offset: i64,
+    pub start: (RuleDay, i64),
+    pub end: (RuleDay, i64),
+}
+
+/// Standard time with an optional daylight saving time, the way POSIX `TZ`
+/// strings and iCalendar `VTIMEZONE` blocks describe a zone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RuleZone {
+    pub standard: i64,
+    pub daylight: Option<Daylight>,
+}
+
+impl Zone for RuleZone {
+    fn offset_at(&self, utc: i64) -> i64 {
+        let Some(daylight) = self.daylight else {
+            return self.standard;
+        };
+        let year = DateTime::from_secs(utc + self.standard).year;
+        let (start_day, start_time) = daylight.start;
+        let (end_day, end_time) = daylight.end;
+        let start = start_day.in_year(year) * SECS_PER_DAY + start_time - self.standard;
+        let end = end_day.in_year(year) * SECS_PER_DAY + end_time - daylight.offset;
+        // South of the equator daylight saving time spans the new year.
+        let in_daylight = if start < end {
+            (start..end).contains(&utc)
+        } else {
+            !(end..start).contains(&utc)
+        };
+        if in_daylight {
+            daylight.offset
+        } else {
+            self.standard
+        }
+    }
+}
+
+#[cfg(windows)]
+mod system {
+    use windows::Win32::{Foundation::SYSTEMTIME, System::Time::SystemTimeToTzSpecificLocalTime};
+
+    use super::{DateTime, Zone};
+
+    pub struct SystemZone;
+
+    impl Zone for SystemZone {
+        fn offset_at(&self, utc: i64) -> i64 {
+            let utc_time = to_system_time(DateTime::from_secs(utc));
+            let mut local_time = SYSTEMTIME::default();
+            match unsafe { SystemTimeToTzSpecificLocalTime(None, &utc_time, &mut local_time) } {
+                Ok(()) => from_system_time(&local_time).to_secs() - utc,
+                Err(_) => 0,
+            }
+        }
+    }
+
+    fn to_system_time(date_time: DateTime) -> SYSTEMTIME {
+        SYSTEMTIME {
+            wYear: date_time.year as u16,
+            wMonth: date_time.month as u16,
+            wDay: date_time.day as u16,
+            wHour: date_time.hour as u16,
+            wMinute: date_time.minute as u16,
+            wSecond: date_time.second as u16,
+            ..Default::default()
+        }
+    }
+
+    fn from_system_time(system_time: &SYSTEMTIME) -> DateTime {
+        DateTime {
+            year: system_time.wYear as i32,
+            month: system_time.wMonth as u32,
+            day: system_time.wDay as u32,
+            hour: system_time.wHour as u32,
+            minute: system_time.wMinute as u32,
+            second: system_time.wSecond as u32,
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod system {
+    use std::{
+        collections::HashMap,
+        env, fs,
+        path::Path,
+        process::Command,
+        sync::{Mutex, OnceLock},
+    };
+
+    use super::{Daylight, RuleDay, RuleZone, Zone};
+
+    /// Reads the zone from `TZ` or `/etc/localtime` once, as std knows
+    /// nothing about time zones. When neither can be read, asks `date`
+    /// instead and remembers its answers.
+    pub struct SystemZone;
+
+    impl Zone for SystemZone {
+        fn offset_at(&self, utc: i64) -> i64 {
+            static ZONE: OnceLock<Option<TzData>> = OnceLock::new();
+            match ZONE.get_or_init(load) {
+                Some(zone) => zone.offset_at(utc),
+                None => ask_date(utc),
+            }
+        }
+    }
+
+    const ZONEINFO: &str = "/usr/share/zoneinfo";
+
+    /// A zone from the system's database by its IANA name, e.g.
+    /// `Europe/Berlin`.
+    pub fn named_zone(name: &str) -> Option<TzData> {
+        if name
+            .split('/')
+            .any(|part| part.is_empty() || part.starts_with('.'))
+        {
+            return None;
+        }
+        let data = fs::read(Path::new(ZONEINFO).join(name)).ok()?;
+        parse_tzif(&data)
+    }
+
+    fn load() -> Option<TzData> {
+        let Ok(tz) = env::var("TZ") else {
+            return fs::read("/etc/localtime")
+                .ok()
+                .and_then(|data| parse_tzif(&data));
+        };
+        let tz = tz.strip_prefix(':').unwrap_or(&tz);
+        if tz.is_empty() {
+            return Some(TzData::from(RuleZone {
+                standard: 0,
+                daylight: None,
+            }));
+        }
+        named_zone(tz)
+            .or_else(|| {
+                let data = fs::read(tz).ok().filter(|_| tz.starts_with('/'))?;
+                parse_tzif(&data)
+            })
+            .or_else(|| parse_posix(tz).map(TzData::from))
+    }
+
+    /// Offsets change on quarter hours, so one answer from `date` holds for
+    /// the whole quarter hour. Tries GNU syntax first, then BSD.
+    fn ask_date(utc: i64) -> i64 {
+        static OFFSETS: Mutex<Option<HashMap<i64, i64>>> = Mutex::new(None);
+        let quarter = utc.div_euclid(900) * 900;
+        let mut offsets = OFFSETS.lock().unwrap_or_else(|error| error.into_inner());
+        *offsets
+            .get_or_insert_with(HashMap::new)
+            .entry(quarter)
+            .or_insert_with(|| {
+                let gnu = ["-d".to_string(), format!("@{}", quarter), "+%z".to_string()];
+                let bsd = ["-r".to_string(), quarter.to_string(), "+%z".to_string()];
+                [gnu, bsd]
+                    .iter()
+                    .find_map(|args| {
+                        let output = Command::new("date").args(args).output().ok()?;
+                        let offset = String::from_utf8(output.stdout).ok()?;
+                        output
+                            .status
+                            .success()
+                            .then(|| parse_offset(offset.trim()))?
+                    })
+                    .unwrap_or(0)
+            })
+    }
+
+    /// Parses `+HHMM` or `-HHMM`.
+    fn parse_offset(offset: &str) -> Option<i64> {
+        let (sign, digits) = match offset.split_at_checked(1)? {
+            ("+", digits) => (1, digits),
+            ("-", digits) => (-1, digits),
+            _ => return None,
+        };
+        let hours: i64 = digits.get(..2)?.parse().ok()?;
+        let minutes: i64 = digits.get(2..4)?.parse().ok()?;
+        Some(sign * (hours * 3600 + minutes * 60))
+    }
+
+    /// The offsets of a zone from a TZif file: every transition it lists,
+    /// then its rule for the years after.
+    #[derive(Debug, PartialEq, Eq)]
+    pub struct TzData {
+        transitions: Vec<(i64, i64)>,
+        initial: i64,
+        rule: Option<RuleZone>,
+    }
+
+    impl From<RuleZone> for TzData {
+        fn from(rule: RuleZone) -> Self {
+            Self {
+                transitions: Vec::new(),
+                initial: rule.standard,
+                rule: Some(rule),
+            }
+        }
+    }
+
+    impl Zone for TzData {
+        fn offset_at(&self, utc: i64) -> i64 {
+            let passed = self.transitions.partition_point(|(time, _)| *time <= utc);
+            match (passed, self.rule) {
+                (passed, Some(rule)) if passed == self.transitions.len() => rule.offset_at(utc),
+                (0, _) => self.initial,
+                (passed, _) => self.transitions[passed - 1].1,
+            }
+        }
+    }
+
+    /// Reads a TZif file as described in RFC 8536, preferring the 64-bit
+    /// data of version 2 and later.
+    pub(super) fn parse_tzif(data: &[u8]) -> Option<TzData> {
+        let (header, rest) = data.split_first_chunk::<44>()?;
+        if &header[..4] != b"TZif" {
+            return None;
+        }
+        let v1_counts = counts(header);
+        if header[4] == 0 {
+            return parse_block(rest.get(..block_len(v1_counts, 4))?, v1_counts, 4, None);
+        }
+        let (header, rest) = rest
+            .get(block_len(v1_counts, 4)..)?
+            .split_first_chunk::<44>()?;
+        let counts = counts(header);
+        let len = block_len(counts, 8);
+        let footer = std::str::from_utf8(rest.get(len..)?).ok()?;
+        let rule = parse_posix(footer.trim_matches('\n'));
+        parse_block(rest.get(..len)?, counts, 8, rule)
+    }
+
+    /// The counts of UT indicators, standard indicators, leap seconds,
+    /// transitions, types and characters.
+    fn counts(header: &[u8; 44]) -> [usize; 6] {
+        std::array::from_fn(|index| {
+            let start = 20 + 4 * index;
+            u32::from_be_bytes([
+                header[start],
+                header[start + 1],
+                header[start + 2],
+                header[start + 3],
+            ]) as usize
+        })
+    }
+
+    fn block_len(counts: [usize; 6], time_size: usize) -> usize {
+        let [ut, standard, leaps, times, types, chars] = counts;
+        times * (time_size + 1) + types * 6 + chars + leaps * (time_size + 4) + standard + ut
+    }
+
+    fn parse_block(
+        block: &[u8],
+        counts: [usize; 6],
+        time_size: usize,
+        rule: Option<RuleZone>,
+    ) -> Option<TzData> {
+        let [_, _, _, times, types, _] = counts;
+        let (time_bytes, rest) = block.split_at(times * time_size);
+        let (indices, rest) = rest.split_at(times);
+        let offsets: Vec<i64> = rest[..types * 6]
+            .chunks_exact(6)
+            .map(|info| i32::from_be_bytes([info[0], info[1], info[2], info[3]]) as i64)
+            .collect();
+        let transitions = time_bytes
+            .chunks_exact(time_size)
+            .zip(indices)
+            .map(|(time, index)| {
+                let time = match *time {
+                    [a, b, c, d] => i32::from_be_bytes([a, b, c, d]) as i64,
+                    _ => i64::from_be_bytes(time.try_into().ok()?),
+                };
+                Some((time, *offsets.get(*index as usize)?))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(TzData {
+            transitions,
+            initial: *offsets.first()?,
+            rule,
+        })
+    }
+
+    /// Parses a POSIX `TZ` string such as `CET-1CEST,M3.5.0,M10.5.0/3`.
+    /// Only `M` rules are supported, which is what every zone uses today.
+    pub(super) fn parse_posix(tz: &str) -> Option<RuleZone> {
+        let mut rest = tz;
+        skip_name(&mut rest)?;
+        // POSIX offsets count west of Greenwich.
+        let standard = -parse_time(&mut rest)?;
+        if rest.is_empty() {
+            return Some(RuleZone {
+                standard,
+                daylight: None,
+            });
+        }
+        skip_name(&mut rest)?;
+        let offset = match rest.starts_with(',') {
+            true => standard + 3600,
+            false => -parse_time(&mut rest)?,
+        };
+        let (start, end) = rest.strip_prefix(',')?.split_once(',')?;
+        Some(RuleZone {
+            standard,
+            daylight: Some(Daylight {
+                offset,
+                start: parse_rule(start)?,
+                end: parse_rule(end)?,
+            }),
+        })
+    }
+
+    fn skip_name(rest: &mut &str) -> Option<()> {
+        let len = match rest.strip_prefix('<') {
+            Some(quoted) => quoted.find('>')? + 2,
+            None => rest
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(rest.len()),
+        };
+        (len >= 3).then(|| *rest = &rest[len..])
+    }
+
+    /// Parses `[+-]hh[:mm[:ss]]` into seconds.
+    fn parse_time(rest: &mut &str) -> Option<i64> {
+        let end = rest
+            .find(|c: char| !matches!(c, '0'..='9' | ':' | '+' | '-'))
+            .unwrap_or(rest.len());
+        let (time, tail) = rest.split_at(end);
+        *rest = tail;
+        let (sign, time) = match time.strip_prefix('-') {
+            Some(time) => (-1, time),
+            None => (1, time.strip_prefix('+').unwrap_or(time)),
+        };
+        if time.split(':').count() > 3 {
+            return None;
+        }
+        time.split(':')
+            .zip([3600, 60, 1])
+            .try_fold(0, |secs, (part, scale)| {
+                Some(secs + part.parse::<i64>().ok()? * scale)
+            })
+            .map(|secs| sign * secs)
+    }
+
+    /// Parses `Mm.w.d[/time]`, where the time defaults to 02:00.
+    fn parse_rule(rule: &str) -> Option<(RuleDay, i64)> {
+        let (day, time) = match rule.split_once('/') {
+            Some((day, time)) => (day, Some(time)),
+            None => (rule, None),
+        };
+        let fields: Vec<u32> = day
+            .strip_prefix('M')?
+            .split('.')
+            .map(|field| field.parse().ok())
+            .collect::<Option<_>>()?;
+        let [month, week, weekday] = fields[..] else {
+            return None;
+        };
+        if !(1..=12).contains(&month) || !(1..=5).contains(&week) || weekday > 6 {
+            return None;
+        }
+        let time = match time {
+            Some(mut time) => Some(parse_time(&mut time)?).filter(|_| time.is_empty())?,
+            None => 7200,
+        };
+        Some((
+            RuleDay {
+                month,
+                week,
+                weekday,
+            },
+            time,
+        ))
+    }
+}
+
+// Howard Hinnant's days_from_civil / civil_from_days.
+pub fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let year = year as i64 - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let month = month as i64;
+    let doy = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+pub fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+    (year, month, day)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    const HOUR: i64 = 3600;
+
+    /// Central European time around the transitions of 2024.
+    pub struct EuropeZone;
+
+    impl EuropeZone {
+        /// 2024-03-31 01:00 UTC, when 02:00 CET becomes 03:00 CEST.
+        pub const SUMMER: i64 = 1_711_846_800;
+        /// 2024-10-27 01:00 UTC, when 03:00 CEST becomes 02:00 CET.
+        pub const WINTER: i64 = 1_729_990_800;
+    }
+
+    impl Zone for EuropeZone {
+        fn offset_at(&self, utc: i64) -> i64 {
+            if (Self::SUMMER..Self::WINTER).contains(&utc) {
+                2 * HOUR
+            } else {
+                HOUR
+            }
+        }
+    }
+
+    #[test]
+    fn civil_dates_round_trip() {
+        for days in [-719_468, -1, 0, 1, 10_957, 19_813, 2_932_896] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+        assert_eq!(civil_from_days(19_813), (2024, 3, 31));
+        let time = DateTime::from_secs(EuropeZone::SUMMER);
+        assert_eq!(
+            (time.year, time.month, time.day, time.hour),
+            (2024, 3, 31, 1)
+        );
+        assert_eq!(time.to_secs(), EuropeZone::SUMMER);
+    }
+
+    #[test]
+    fn fixed_offset_round_trips() {
+        let zone = FixedZone(-5 * HOUR);
+        assert_eq!(zone.to_local(10 * HOUR), 5 * HOUR);
+        assert_eq!(zone.to_utc(5 * HOUR), 10 * HOUR);
+    }
+
+    #[test]
+    fn to_utc_outside_transitions() {
+        let zone = EuropeZone;
+        let winter = EuropeZone::SUMMER - 10 * HOUR;
+        let summer = EuropeZone::SUMMER + 10 * HOUR;
+        assert_eq!(zone.to_utc(zone.to_local(winter)), winter);
+        assert_eq!(zone.to_utc(zone.to_local(summer)), summer);
+    }
+
+    #[test]
+    fn to_utc_moves_skipped_times_forward() {
+        let zone = EuropeZone;
+        // 02:30 local does not exist on 2024-03-31.
+        let local = EuropeZone::SUMMER + HOUR + 30 * 60;
+        let utc = zone.to_utc(local);
+        assert_eq!(utc, EuropeZone::SUMMER + 30 * 60);
+        assert_eq!(zone.to_local(utc), local + HOUR);
+    }
+
+    #[test]
+    fn to_utc_takes_the_earlier_of_repeated_times() {
+        let zone = EuropeZone;
+        // 02:30 local happens twice on 2024-10-27.
+        let local = EuropeZone::WINTER + HOUR + 30 * 60;
+        assert_eq!(zone.to_utc(local), EuropeZone::WINTER - 30 * 60);
+        assert_eq!(zone.to_local(EuropeZone::WINTER + 30 * 60), local);
+    }
+
+    fn central_europe() -> RuleZone {
+        let last_sunday = |month| RuleDay {
+            month,
+            week: 5,
+            weekday: 0,
+        };
+        RuleZone {
+            standard: HOUR,
+            daylight: Some(Daylight {
+                offset: 2 * HOUR,
+                start: (last_sunday(3), 2 * HOUR),
+                end: (last_sunday(10), 3 * HOUR),
+            }),
+        }
+    }
+
+    #[test]
+    fn rule_zones_change_on_the_right_days() {
+        let zone = central_europe();
+        for utc in [
+            EuropeZone::SUMMER - 1,
+            EuropeZone::SUMMER,
+            EuropeZone::WINTER - 1,
+            EuropeZone::WINTER,
+            EuropeZone::SUMMER - 100 * SECS_PER_DAY,
+            EuropeZone::WINTER + 100 * SECS_PER_DAY,
+        ] {
+            assert_eq!(zone.offset_at(utc), EuropeZone.offset_at(utc), "{}", utc);
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn parses_posix_tz_strings() {
+        use system::parse_posix;
+
+        assert_eq!(
+            parse_posix("CET-1CEST,M3.5.0,M10.5.0/3"),
+            Some(central_europe())
+        );
+        assert_eq!(
+            parse_posix("<+0330>-3:30"),
+            Some(RuleZone {
+                standard: 3 * HOUR + 30 * 60,
+                daylight: None,
+            })
+        );
+        // Sydney: daylight saving time from October to April.
+        let sydney = parse_posix("AEST-10AEDT,M10.1.0,M4.1.0/3").unwrap();
+        let (january, july) = (1_704_067_200, 1_719_792_000);
+        assert_eq!(sydney.offset_at(january), 11 * HOUR);
+        assert_eq!(sydney.offset_at(july), 10 * HOUR);
+        for tz in [
+            "",
+            "CET",
+            "EST5EDT",
+            "CET-1CEST,M13.5.0,M10.5.0",
+            "CET-1CEST,J60,J300",
+        ] {
+            assert_eq!(parse_posix(tz), None, "{:?}", tz);
+        }
+    }
+
+    /// A TZif file with the given transitions, type offsets and footer.
+    #[cfg(not(windows))]
+    fn tzif(version: u8, transitions: &[(i64, u8)], offsets: &[i32], footer: &str) -> Vec<u8> {
+        let header = |times: usize, types: usize| {
+            let mut header = b"TZif".to_vec();
+            header.push(version);
+            header.extend([0; 15]);
+            for count in [0, 0, 0, times, types, 0] {
+                header.extend((count as u32).to_be_bytes());
+            }
+            header
+        };
+        let block = |wide: bool| {
+            let mut block = Vec::new();
+            for (time, _) in transitions {
+                match wide {
+                    true => block.extend(time.to_be_bytes()),
+                    false => block.extend((*time as i32).to_be_bytes()),
+                }
+            }
+            block.extend(transitions.iter().map(|(_, index)| index));
+            for offset in offsets {
+                block.extend(offset.to_be_bytes());
+                block.extend([0, 0]);
+            }
+            block
+        };
+        let mut data = header(transitions.len(), offsets.len());
+        data.extend(block(false));
+        if version != 0 {
+            data.extend(header(transitions.len(), offsets.len()));
+            data.extend(block(true));
+            data.extend(format!("\n{}\n", footer).bytes());
+        }
+        data
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn reads_tzif_files() {
+        use system::parse_tzif;
+
+        let transitions = [(EuropeZone::SUMMER, 1), (EuropeZone::WINTER, 0)];
+        let offsets = [HOUR as i32, 2 * HOUR as i32];
+        let later_summer = EuropeZone::SUMMER + 365 * SECS_PER_DAY;
+        for version in [0, b'2'] {
+            let zone = parse_tzif(&tzif(
+                version,
+                &transitions,
+                &offsets,
+                "CET-1CEST,M3.5.0,M10.5.0/3",
+            ))
+            .unwrap();
+            assert_eq!(zone.offset_at(EuropeZone::SUMMER - 1), HOUR);
+            assert_eq!(zone.offset_at(EuropeZone::SUMMER), 2 * HOUR);
+            assert_eq!(zone.offset_at(EuropeZone::WINTER), HOUR);
+            let later = if version == 0 { HOUR } else { 2 * HOUR };
+            assert_eq!(zone.offset_at(later_summer), later);
+        }
+        let data = tzif(b'2', &transitions, &offsets, "");
+        assert_eq!(parse_tzif(&data[..data.len() - 10]), None);
+        assert_eq!(
+            parse_tzif(b"not a tzif file at all, not even close to one"),
+            None
+        );
+    }
+}
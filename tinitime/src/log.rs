@@ -0,0 +1,152 @@
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::SystemTime,
+};
+
+use crate::{
+    helpers::unix_secs,
+    history::data_dir,
+    localtime::{DateTime, SystemZone, Zone},
+};
+
+const LOG_FILE: &str = "tinitime.log";
+/// The log is rotated once it grows past this size.
+const MAX_LOG_SIZE: u64 = 256 * 1024;
+/// How many rotated logs are kept next to the current one, e.g.
+/// `tinitime.log.1` and `tinitime.log.2`.
+const MAX_OLD_LOGS: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl Level {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+        }
+    }
+}
+
+struct Logger {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    level: Level,
+    /// Offset of local time from UTC, looked up once since that can take a
+    /// process spawn.
+    offset: i64,
+}
+
+static LOGGER: Mutex<Option<Logger>> = Mutex::new(None);
+
+pub fn default_path() -> Option<PathBuf> {
+    Some(data_dir()?.join(LOG_FILE))
+}
+
+/// Starts writing messages up to `level` to `path`. Until then, and if the
+/// file cannot be opened, messages are dropped.
+pub fn init(path: &Path, level: Level) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let size = file.metadata()?.len();
+    *LOGGER.lock().unwrap() = Some(Logger {
+        path: path.to_path_buf(),
+        file,
+        size,
+        level,
+        offset: SystemZone.offset_at(unix_secs(SystemTime::now())),
+    });
+    Ok(())
+}
+
+pub fn write(level: Level, args: fmt::Arguments) {
+    let mut logger = LOGGER.lock().unwrap();
+    let Some(logger) = logger.as_mut() else {
+        return;
+    };
+    if level > logger.level {
+        return;
+    }
+    if logger.size >= MAX_LOG_SIZE {
+        if let Ok(file) = rotate(&logger.path) {
+            logger.file = file;
+            logger.size = 0;
+        }
+    }
+    let line = format!(
+        "{} {:<5} {}\n",
+        timestamp(logger.offset),
+        level.as_str(),
+        args
+    );
+    if logger.file.write_all(line.as_bytes()).is_ok() {
+        logger.size += line.len() as u64;
+    }
+}
+
+/// Shifts `tinitime.log` to `tinitime.log.1` and so on, dropping the oldest,
+/// and opens a fresh log.
+fn rotate(path: &Path) -> io::Result<File> {
+    let old = |index: usize| {
+        let mut old = path.as_os_str().to_owned();
+        old.push(format!(".{}", index));
+        PathBuf::from(old)
+    };
+    for index in (1..MAX_OLD_LOGS).rev() {
+        let _ = fs::rename(old(index), old(index + 1));
+    }
+    fs::rename(path, old(1))?;
+    File::create(path)
+}
+
+fn timestamp(offset: i64) -> String {
+    let time = DateTime::from_secs(unix_secs(SystemTime::now()) + offset);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        time.year, time.month, time.day, time.hour, time.minute, time.second
+    )
+}
+
+// Only fatal errors are logged as such, and the terminal front-end has none.
+#[cfg_attr(not(windows), allow(unused_macros))]
+macro_rules! error {
+    ($($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Error, format_args!($($arg)*))
+    };
+}
+
+macro_rules! warning {
+    ($($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Warn, format_args!($($arg)*))
+    };
+}
+
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Info, format_args!($($arg)*))
+    };
+}
+
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        $crate::log::write($crate::log::Level::Debug, format_args!($($arg)*))
+    };
+}
+
+#[cfg(windows)]
+pub(crate) use error;
+pub(crate) use {debug, info, warning};
@@ -1,18 +1,410 @@
 #![windows_subsystem = "windows"]
+// Most options only apply to the window, which the terminal front-end
+// elsewhere does not have.
+#![cfg_attr(not(windows), allow(dead_code))]
 
+mod api;
+mod clock;
+mod config;
+mod controller;
+mod ctl;
+mod error;
+mod estimate;
+mod export;
+mod foreground;
+mod goal;
+mod helpers;
+mod history;
+mod i18n;
+mod ics;
+mod idle;
+mod localtime;
+mod log;
+mod meeting;
+mod named;
+#[cfg(windows)]
+mod notify;
+#[cfg(windows)]
+mod overlay;
+mod pomodoro;
+mod program;
+#[cfg(windows)]
+mod reflection;
+mod reminder;
+mod room;
+mod schedule;
+#[cfg(windows)]
+mod stacked;
+mod stats;
+mod textfile;
+mod timer;
+#[cfg(not(windows))]
+mod tui;
+mod undo;
+mod until;
+mod webhook;
+#[cfg(windows)]
 mod window;
 
+use std::env;
+use std::fs;
+#[cfg(windows)]
 use std::mem;
+use std::path::Path;
+use std::process;
+#[cfg(windows)]
 use std::ptr::null_mut;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use config::Config;
+#[cfg(windows)]
+use ctl::Command;
+#[cfg(windows)]
+use error::Error;
+use export::{Format, Rounding};
+use helpers::{parse_duration, unix_secs};
+use history::{clean_label, History};
+use i18n::tr;
+use localtime::SystemZone;
+use meeting::Meeting;
+#[cfg(windows)]
 use window::Window;
-use windows::core::Result;
+#[cfg(windows)]
+use windows::core::{Result, HSTRING};
+#[cfg(windows)]
 use windows::Win32::Graphics::GdiPlus;
+#[cfg(windows)]
 use windows::Win32::Graphics::GdiPlus::GdiplusShutdown;
+#[cfg(windows)]
 use windows::Win32::Graphics::GdiPlus::GdiplusStartup;
+#[cfg(windows)]
 use windows::Win32::Graphics::GdiPlus::GdiplusStartupInput;
+#[cfg(windows)]
+use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+#[cfg(windows)]
+use windows::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_ICONERROR, MB_OK};
+
+const DEF_MEETING_BEFORE: Duration = Duration::from_secs(300);
+/// Recurring meetings are imported this far ahead.
+const IMPORT_AHEAD: Duration = Duration::from_secs(8 * 7 * 24 * 3600);
 
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        #[cfg(windows)]
+        Some("ctl") => ctl(&args[1..]),
+        Some("stats") => print_stats(),
+        Some("export") => export(&args[1..]),
+        Some("import") => import(&args[1..]),
+        Some("check") => check_program(&args[1..]),
+        Some("run") => run_program(&args[1..]),
+        _ => start(parse_config(args.into_iter())),
+    }
+}
+
+#[cfg(windows)]
+fn start(config: Config) {
+    // The Windows build is a GUI program, which the console does not wait
+    // for, so it cannot take keys in the terminal it was started from.
+    if config.tui {
+        attach_console();
+        print_usage(&["tinitime [OPTIONS]"]);
+        eprintln!("{}", tr!("cli-tui-windows"));
+        process::exit(2);
+    }
+    init_log(&config);
+    i18n::init(config.lang.as_deref());
+    if let Err(error) = gui(config) {
+        fail(&error);
+    }
+    log::info!("tinitime stopped");
+}
+
+#[cfg(not(windows))]
+fn start(mut config: Config) {
+    init_log(&config);
+    i18n::init(config.lang.as_deref());
+    if let Err(errors) = load_files(&mut config) {
+        config_failed(errors);
+    }
+    if let Err(errors) = tui::run(config) {
+        config_failed(errors);
+    }
+    log::info!("tinitime stopped");
+}
+
+/// Stops on options that cannot be used, in a dialog on Windows like other
+/// startup errors.
+fn parse_config(args: impl Iterator<Item = String>) -> Config {
+    Config::from_args(args).unwrap_or_else(|errors| config_failed(errors))
+}
+
+fn config_failed(errors: Vec<String>) -> ! {
+    #[cfg(windows)]
+    fail(&Error::Config(errors));
+    #[cfg(not(windows))]
+    {
+        for error in errors {
+            eprintln!("{}", error);
+        }
+        process::exit(1);
+    }
+}
+
+/// Reads the files that options point to.
+fn load_files(config: &mut Config) -> std::result::Result<(), Vec<String>> {
+    if let Some(path) = config.schedule_file.clone() {
+        config.schedule = schedule::load(&path)?;
+    }
+    if let Some(path) = config.webhook_file.clone() {
+        config.webhooks = webhook::load(&path)?;
+    }
+    Ok(())
+}
+
+/// Logs to `--log-file`, or `tinitime.log` in the data directory. Without a
+/// log tinitime still runs.
+fn init_log(config: &Config) {
+    let Some(path) = config.log_file.clone().or_else(log::default_path) else {
+        return;
+    };
+    let level = if config.verbose {
+        log::Level::Debug
+    } else {
+        log::Level::Info
+    };
+    if let Err(error) = log::init(&path, level) {
+        attach_console();
+        eprintln!("{}: {}", path.display(), error);
+        return;
+    }
+    log::info!("tinitime {} started", env!("CARGO_PKG_VERSION"));
+}
+
+/// Reports why tinitime cannot run, in a dialog since there is usually no
+/// console to print to.
+#[cfg(windows)]
+fn fail(error: &Error) -> ! {
+    log::error!("{}", error);
+    attach_console();
+    eprintln!("{}", error);
+    unsafe {
+        MessageBoxW(
+            None,
+            &HSTRING::from(error.to_string()),
+            &HSTRING::from("tinitime"),
+            MB_OK | MB_ICONERROR,
+        );
+    }
+    process::exit(1);
+}
+
+#[cfg(windows)]
+fn ctl(args: &[String]) {
+    attach_console();
+    match Command::parse(args) {
+        Some(command) => {
+            if command.send().is_err() {
+                eprintln!("{}", tr!("cli-not-running"));
+            }
+        }
+        None => print_usage(&[
+            "tinitime ctl start|stop|toggle|pause|extend|snooze|undo|label [NAME]",
+            "tinitime ctl estimate SESSIONS",
+            "tinitime ctl timer add NAME DURATION | timer toggle|reset|remove NAME",
+        ]),
+    }
+}
+
+fn check_program(args: &[String]) {
+    attach_console();
+    let Some(path) = args.first() else {
+        print_usage(&["tinitime check PROGRAM"]);
+        process::exit(2);
+    };
+    match program::load(Path::new(path)) {
+        Ok(steps) => println!(
+            "{}",
+            tr!("cli-program-steps", path = path, count = steps.len())
+        ),
+        Err(errors) => {
+            for error in errors {
+                eprintln!("{}", error);
+            }
+            process::exit(1);
+        }
+    }
+}
+
+fn run_program(args: &[String]) {
+    let Some((path, args)) = args.split_first() else {
+        attach_console();
+        print_usage(&["tinitime run PROGRAM [OPTIONS]"]);
+        process::exit(2);
+    };
+    match program::load(Path::new(path)) {
+        Ok(steps) => {
+            let mut config = parse_config(args.iter().cloned());
+            config.program = steps;
+            start(config);
+        }
+        Err(errors) => {
+            attach_console();
+            for error in errors {
+                eprintln!("{}", error);
+            }
+            process::exit(1);
+        }
+    }
+}
+
+fn print_stats() {
+    attach_console();
+    let sessions = History::open_default()
+        .map(|history| history.load().unwrap_or_default())
+        .unwrap_or_default();
+    print!("{}", stats::report(&sessions, &SystemZone));
+}
+
+const EXPORT_USAGE: &[&str] = &[
+    "tinitime export --ics|--timew|--toggl|--csv [FILE] [--from=DATE] [--to=DATE]",
+    "[--round=DURATION|--round-up=DURATION|--round-down=DURATION] [--projects=FILE] \
+     [--email=ADDRESS]",
+];
+
+/// Writes the history for calendars and time trackers. Dates are
+/// `YYYY-MM-DD` in local time.
+fn export(args: &[String]) {
+    attach_console();
+    let Some(format) = args.first().and_then(|flag| Format::parse(flag)) else {
+        print_usage(EXPORT_USAGE);
+        process::exit(2);
+    };
+    let mut options = export::Options::default();
+    let mut path = None;
+    let mut email = String::new();
+    for arg in &args[1..] {
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (arg.as_str(), None),
+        };
+        let date = value.and_then(export::parse_date);
+        let unit = value.and_then(parse_duration);
+        match (name, value) {
+            ("--from", _) if date.is_some() => options.from = date,
+            ("--to", _) if date.is_some() => options.to = date,
+            ("--round", _) if unit.is_some() => options.rounding = unit.map(Rounding::Nearest),
+            ("--round-up", _) if unit.is_some() => options.rounding = unit.map(Rounding::Up),
+            ("--round-down", _) if unit.is_some() => options.rounding = unit.map(Rounding::Down),
+            ("--projects", Some(rules)) => match export::load_rules(Path::new(rules)) {
+                Ok(rules) => options.rules = rules,
+                Err(errors) => {
+                    for error in errors {
+                        eprintln!("{}", error);
+                    }
+                    process::exit(1);
+                }
+            },
+            ("--email", Some(address)) => email = address.to_string(),
+            (file, None) if !file.starts_with("--") && path.is_none() => path = Some(file),
+            _ => {
+                print_usage(EXPORT_USAGE);
+                process::exit(2);
+            }
+        }
+    }
+
+    let sessions = History::open_default()
+        .map(|history| history.load().unwrap_or_default())
+        .unwrap_or_default();
+    let entries = export::entries(&sessions, &options, &SystemZone);
+    let output = match format {
+        Format::Ics => ics::export(&export::sessions(&entries), SystemTime::now()),
+        Format::Timewarrior => export::timewarrior(&entries),
+        Format::Toggl => export::toggl(&entries, &email, &SystemZone),
+        Format::Csv => export::csv(&entries, &SystemZone),
+    };
+    match path {
+        Some(path) => {
+            if let Err(error) = fs::write(path, output) {
+                eprintln!("{}: {}", path, error);
+                process::exit(1);
+            }
+        }
+        None => print!("{}", output),
+    }
+}
+
+const IMPORT_USAGE: &[&str] = &["tinitime import --ics FILE [--before=DURATION]"];
+
+/// Reads upcoming meetings from a calendar; the timer counts down to each
+/// of them, starting `--before` ahead.
+fn import(args: &[String]) {
+    attach_console();
+    let [format, path, options @ ..] = args else {
+        print_usage(IMPORT_USAGE);
+        process::exit(2);
+    };
+    if format != "--ics" {
+        print_usage(IMPORT_USAGE);
+        process::exit(2);
+    }
+    let before = options
+        .iter()
+        .find_map(|option| option.strip_prefix("--before="))
+        .and_then(parse_duration)
+        .unwrap_or(DEF_MEETING_BEFORE);
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("{}: {}", path, error);
+            process::exit(1);
+        }
+    };
+    let now = SystemTime::now();
+    let range = unix_secs(now)..unix_secs(now + IMPORT_AHEAD);
+    let calendar = ics::parse(&source, &SystemZone, range);
+    for warning in &calendar.warnings {
+        eprintln!("{}: {}", path, warning);
+    }
+    let imported = calendar.events.into_iter().filter_map(|event| {
+        Some(Meeting {
+            start: UNIX_EPOCH + Duration::from_secs(u64::try_from(event.start).ok()?),
+            before,
+            name: clean_label(&event.summary).unwrap_or_else(|| tr!("meeting-default")),
+        })
+    });
+    let Some(meetings_path) = meeting::default_path() else {
+        eprintln!("{}", tr!("cli-no-data-dir"));
+        process::exit(1);
+    };
+    let mut meetings = meeting::load(&meetings_path);
+    let added = meeting::merge(&mut meetings, imported.collect(), now);
+    if let Err(error) = meeting::save(&meetings_path, &meetings) {
+        eprintln!("{}: {}", meetings_path.display(), error);
+        process::exit(1);
+    }
+    println!("{}", tr!("cli-meetings-imported", count = added));
+}
+
+/// Prints the ways to call a command, one per line, below each other.
+fn print_usage(forms: &[&str]) {
+    let usage = tr!("cli-usage");
+    let indent = format!("\n{:width$}", "", width = usage.chars().count() + 1);
+    eprintln!("{} {}", usage, forms.join(&indent));
+}
+
+fn attach_console() {
+    #[cfg(windows)]
+    unsafe {
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(windows)]
+fn gui(mut config: Config) -> std::result::Result<(), Error> {
+    load_files(&mut config).map_err(Error::Config)?;
+
     let mut gdiplus_token = 0;
 
     let status = unsafe {
@@ -26,20 +418,21 @@ fn main() {
         )
     };
 
-    assert_eq!(status, GdiPlus::Ok);
-
-    let result = run();
-    if let Err(error) = result {
-        error.code().unwrap();
+    if status != GdiPlus::Ok {
+        return Err(Error::GdiPlus(status.0));
     }
 
+    let result = run(config);
+
     unsafe {
         GdiplusShutdown(gdiplus_token);
     }
+    Ok(result?)
 }
 
-fn run() -> Result<()> {
-    let window = Window::new("tinitime")?;
+#[cfg(windows)]
+fn run(config: Config) -> Result<()> {
+    let window = Window::new("tinitime", config)?;
     Window::run_message_loop();
     mem::drop(window);
     Ok(())
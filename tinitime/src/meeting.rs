@@ -0,0 +1,156 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    helpers::unix_secs,
+    history::{clean_label, data_dir},
+};
+
+const MEETINGS_FILE: &str = "meetings.tsv";
+
+/// A meeting to count down to, typically imported from a calendar.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Meeting {
+    pub start: SystemTime,
+    /// How long before the start the countdown appears.
+    pub before: Duration,
+    pub name: String,
+}
+
+impl Meeting {
+    /// The countdown to start now, if it is due: the meeting's name and the
+    /// time left until it starts.
+    pub fn countdown(&self, now: SystemTime) -> Option<(&str, Duration)> {
+        let left = self.start.duration_since(now).ok()?;
+        (left <= self.before && !left.is_zero()).then_some((&self.name, left))
+    }
+
+    fn to_line(&self) -> String {
+        format!(
+            "start={}\tbefore={}\tname={}",
+            unix_secs(self.start),
+            self.before.as_secs(),
+            self.name
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        let (mut start, mut before, mut name) = (None, Duration::ZERO, None);
+        for field in line.split('\t') {
+            let (key, value) = field.split_once('=')?;
+            match key {
+                "start" => start = Some(UNIX_EPOCH + Duration::from_secs(value.parse().ok()?)),
+                "before" => before = Duration::from_secs(value.parse().ok()?),
+                "name" => name = clean_label(value),
+                _ => {}
+            }
+        }
+        Some(Self {
+            start: start?,
+            before,
+            name: name?,
+        })
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    Some(data_dir()?.join(MEETINGS_FILE))
+}
+
+pub fn load(path: &Path) -> Vec<Meeting> {
+    let Ok(source) = fs::read_to_string(path) else {
+        return vec![];
+    };
+    source.lines().filter_map(Meeting::from_line).collect()
+}
+
+pub fn save(path: &Path, meetings: &[Meeting]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let lines: String = meetings
+        .iter()
+        .map(|meeting| format!("{}\n", meeting.to_line()))
+        .collect();
+    fs::write(path, lines)
+}
+
+/// Adds `new` to `meetings`, skipping ones already there and dropping every
+/// meeting that has started by `now`. Returns how many were added.
+pub fn merge(meetings: &mut Vec<Meeting>, new: Vec<Meeting>, now: SystemTime) -> usize {
+    meetings.retain(|meeting| meeting.start > now);
+    let mut added = 0;
+    for meeting in new {
+        let known = meetings
+            .iter()
+            .any(|known| known.start == meeting.start && known.name == meeting.name);
+        if meeting.start > now && !known {
+            meetings.push(meeting);
+            added += 1;
+        }
+    }
+    meetings.sort_by_key(|meeting| meeting.start);
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_709_542_800;
+
+    fn mins(minutes: u64) -> Duration {
+        Duration::from_secs(minutes * 60)
+    }
+
+    fn meeting(name: &str, in_minutes: i64) -> Meeting {
+        let start = NOW.checked_add_signed(in_minutes * 60).unwrap();
+        Meeting {
+            start: UNIX_EPOCH + Duration::from_secs(start),
+            before: mins(5),
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn counts_down_shortly_before_the_start() {
+        let now = UNIX_EPOCH + Duration::from_secs(NOW);
+        assert_eq!(meeting("standup", 6).countdown(now), None);
+        assert_eq!(
+            meeting("standup", 5).countdown(now),
+            Some(("standup", mins(5)))
+        );
+        assert_eq!(
+            meeting("standup", 1).countdown(now),
+            Some(("standup", mins(1)))
+        );
+        assert_eq!(meeting("standup", 0).countdown(now), None);
+        assert_eq!(meeting("standup", -1).countdown(now), None);
+    }
+
+    #[test]
+    fn merges_new_meetings_in_order() {
+        let now = UNIX_EPOCH + Duration::from_secs(NOW);
+        let mut meetings = vec![meeting("retro", 60), meeting("standup", -10)];
+        let added = merge(
+            &mut meetings,
+            vec![
+                meeting("review", 30),
+                meeting("retro", 60),
+                meeting("lunch", 60),
+                meeting("standup", -10),
+                meeting("now", 0),
+            ],
+            now,
+        );
+        assert_eq!(added, 2);
+        let names: Vec<_> = meetings
+            .iter()
+            .map(|meeting| meeting.name.as_str())
+            .collect();
+        assert_eq!(names, ["review", "retro", "lunch"]);
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    helpers::unix_secs,
+    history::{clean_label, data_dir},
+    timer::{State, Timer},
+};
+
+const TIMERS_FILE: &str = "timers.tsv";
+
+/// A countdown that runs alongside the main timer, such as a tea timer or a
+/// meeting countdown.
+pub struct NamedTimer {
+    pub name: String,
+    pub timer: Timer,
+}
+
+impl NamedTimer {
+    pub fn new(name: String, duration: Duration) -> Self {
+        Self {
+            name,
+            timer: Timer::new(duration),
+        }
+    }
+
+    pub fn is_finished(&self, now: Duration) -> bool {
+        self.timer.is_stopped() && self.timer.remaining(now).is_zero()
+    }
+
+    /// Running timers are saved with their wall-clock end, so they keep
+    /// counting while tinitime is not running.
+    fn to_line(&self, now: Duration, wall: SystemTime) -> String {
+        let remaining = self.timer.remaining(now);
+        let mut line = format!(
+            "name={}\tduration={}\tstate={}",
+            self.name,
+            self.timer.duration().as_secs(),
+            self.timer.state().as_str()
+        );
+        match self.timer.state() {
+            State::Running => line.push_str(&format!("\tends={}", unix_secs(wall + remaining))),
+            State::Paused | State::Stopped => {
+                line.push_str(&format!("\tremaining={}", remaining.as_secs()))
+            }
+        }
+        line
+    }
+
+    fn from_line(line: &str, now: Duration, wall: SystemTime) -> Option<Self> {
+        let (mut name, mut duration, mut state) = (None, None, None);
+        let mut remaining = Duration::ZERO;
+        for field in line.split('\t') {
+            let (key, value) = field.split_once('=')?;
+            match key {
+                "name" => name = clean_label(value),
+                "duration" => duration = value.parse().ok().map(Duration::from_secs),
+                "state" => state = State::parse(value),
+                "ends" => {
+                    let ends: i64 = value.parse().ok()?;
+                    remaining = Duration::from_secs((ends - unix_secs(wall)).max(0) as u64);
+                }
+                "remaining" => remaining = Duration::from_secs(value.parse().ok()?),
+                _ => {}
+            }
+        }
+        let mut named = Self::new(name?, duration?);
+        named.timer.restore(now, state?, remaining);
+        Some(named)
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    Some(data_dir()?.join(TIMERS_FILE))
+}
+
+pub fn load(path: &Path, now: Duration, wall: SystemTime) -> Vec<NamedTimer> {
+    let Ok(source) = fs::read_to_string(path) else {
+        return vec![];
+    };
+    source
+        .lines()
+        .filter_map(|line| NamedTimer::from_line(line, now, wall))
+        .collect()
+}
+
+pub fn save<'a>(
+    path: &Path,
+    timers: impl Iterator<Item = &'a NamedTimer>,
+    now: Duration,
+    wall: SystemTime,
+) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let lines: String = timers
+        .map(|named| format!("{}\n", named.to_line(now, wall)))
+        .collect();
+    fs::write(path, lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+
+    const WALL: u64 = 1_709_542_800;
+
+    fn mins(minutes: u64) -> Duration {
+        Duration::from_secs(minutes * 60)
+    }
+
+    /// Saves a tea timer started a minute ago on a machine that has been up
+    /// for an hour.
+    fn saved_tea() -> String {
+        let clock = ManualClock::new(UNIX_EPOCH + Duration::from_secs(WALL));
+        clock.advance(mins(60));
+        let mut tea = NamedTimer::new("tea".to_string(), mins(5));
+        tea.timer.start(clock.now());
+        clock.advance(mins(1));
+        tea.to_line(clock.now(), clock.wall())
+    }
+
+    /// A fresh process, whose monotonic clock starts over, `minutes` after
+    /// the timer was saved.
+    fn restarted(minutes: u64) -> ManualClock {
+        ManualClock::new(UNIX_EPOCH + Duration::from_secs(WALL) + mins(61 + minutes))
+    }
+
+    #[test]
+    fn running_timer_keeps_counting_by_the_wall_clock() {
+        let clock = restarted(2);
+        let tea = NamedTimer::from_line(&saved_tea(), clock.now(), clock.wall()).unwrap();
+        assert_eq!(tea.name, "tea");
+        assert_eq!(tea.timer.duration(), mins(5));
+        assert_eq!(tea.timer.state(), State::Running);
+        assert_eq!(tea.timer.remaining(clock.now()), mins(2));
+
+        let mut paused = NamedTimer::new("pasta".to_string(), mins(10));
+        paused.timer.start(clock.now());
+        paused
+            .timer
+            .pause(clock.now() + mins(3), clock.now() + mins(3));
+        let line = paused.to_line(clock.now(), clock.wall());
+        let later = restarted(30);
+        let paused = NamedTimer::from_line(&line, later.now(), later.wall()).unwrap();
+        assert_eq!(paused.timer.state(), State::Paused);
+        assert_eq!(paused.timer.remaining(later.now()), mins(7));
+    }
+
+    #[test]
+    fn timer_that_ended_meanwhile_finishes_on_restore() {
+        let clock = restarted(10);
+        let mut tea = NamedTimer::from_line(&saved_tea(), clock.now(), clock.wall()).unwrap();
+        assert_eq!(tea.timer.remaining(clock.now()), Duration::ZERO);
+        assert!(!tea.is_finished(clock.now()));
+        assert!(tea.timer.tick(clock.now()));
+        assert!(tea.is_finished(clock.now()));
+    }
+}
@@ -0,0 +1,81 @@
+use std::mem::size_of;
+
+use windows::{
+    core::w,
+    Win32::{
+        Foundation::{HINSTANCE, HWND},
+        System::LibraryLoader::GetModuleHandleW,
+        UI::{
+            Shell::{
+                Shell_NotifyIconW, NIF_ICON, NIF_INFO, NIF_TIP, NIIF_INFO, NIM_ADD, NIM_DELETE,
+                NIM_MODIFY, NOTIFYICONDATAW,
+            },
+            WindowsAndMessaging::LoadIconW,
+        },
+    },
+};
+
+const NOTIFY_ICON_ID: u32 = 1;
+
+/// Shows balloon/toast notifications through a tray icon that is only added
+/// once the first notification is shown.
+pub struct Notifier {
+    window: HWND,
+    added: bool,
+}
+
+impl Notifier {
+    pub fn new(window: HWND) -> Self {
+        Self {
+            window,
+            added: false,
+        }
+    }
+
+    pub fn notify(&mut self, title: &str, text: &str) {
+        let mut data = self.icon_data();
+        data.uFlags |= NIF_INFO;
+        data.dwInfoFlags = NIIF_INFO;
+        copy_wide(&mut data.szInfoTitle, title);
+        copy_wide(&mut data.szInfo, text);
+        unsafe {
+            if !self.added {
+                self.added = Shell_NotifyIconW(NIM_ADD, &self.icon_data()).as_bool();
+            }
+            let _ = Shell_NotifyIconW(NIM_MODIFY, &data);
+        }
+    }
+
+    pub fn remove(&mut self) {
+        if self.added {
+            unsafe {
+                let _ = Shell_NotifyIconW(NIM_DELETE, &self.icon_data());
+            }
+            self.added = false;
+        }
+    }
+
+    fn icon_data(&self) -> NOTIFYICONDATAW {
+        let mut data = NOTIFYICONDATAW {
+            cbSize: size_of::<NOTIFYICONDATAW>() as u32,
+            hWnd: self.window,
+            uID: NOTIFY_ICON_ID,
+            uFlags: NIF_ICON | NIF_TIP,
+            ..Default::default()
+        };
+        unsafe {
+            if let Ok(instance) = GetModuleHandleW(None) {
+                data.hIcon = LoadIconW(Some(HINSTANCE(instance.0)), w!("main")).unwrap_or_default();
+            }
+        }
+        copy_wide(&mut data.szTip, "tinitime");
+        data
+    }
+}
+
+fn copy_wide(target: &mut [u16], text: &str) {
+    let len = target.len() - 1;
+    for (target, source) in target.iter_mut().zip(text.encode_utf16().take(len)) {
+        *target = source;
+    }
+}
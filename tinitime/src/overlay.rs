@@ -0,0 +1,292 @@
+use std::time::{Duration, Instant};
+
+use windows::{
+    core::{w, Result, PCWSTR},
+    Win32::{
+        Foundation::*,
+        Graphics::Gdi::{
+            BeginPaint, CreateFontW, CreateSolidBrush, DeleteObject, DrawTextW, EndPaint,
+            EnumDisplayMonitors, FillRect, InvalidateRect, SelectObject, SetBkMode, SetTextColor,
+            CLIP_DEFAULT_PRECIS, DEFAULT_CHARSET, DEFAULT_QUALITY, DT_CENTER, DT_SINGLELINE,
+            DT_VCENTER, HBRUSH, HDC, HFONT, HGDIOBJ, HMONITOR, OUT_DEFAULT_PRECIS, PAINTSTRUCT,
+            TRANSPARENT,
+        },
+        System::LibraryLoader::GetModuleHandleW,
+        UI::{Input::KeyboardAndMouse::VK_ESCAPE, WindowsAndMessaging::*},
+    },
+};
+
+use crate::{error::LogFailure, i18n::tr, log, pomodoro::Strictness};
+
+const OVERLAY_CLASS_NAME: PCWSTR = w!("rxcle.tinitime.overlay");
+pub const WM_SKIP_BREAK: u32 = WM_APP + 1;
+
+const OVERLAY_ALPHA: u8 = 215;
+const HATCH_PRESSES: usize = 5;
+const HATCH_WINDOW: Duration = Duration::from_secs(2);
+
+/// Semi-transparent full-screen windows on all monitors showing the break
+/// countdown. Skipping is reported to `owner` as `WM_SKIP_BREAK`.
+pub struct Overlay {
+    owner: HWND,
+    strictness: Strictness,
+    skip_after: Duration,
+    windows: Vec<HWND>,
+    font: HFONT,
+    hint_font: HFONT,
+    bgbrush: HBRUSH,
+    shown_at: Option<Instant>,
+    remaining: u64,
+    escapes: Vec<Instant>,
+}
+
+impl Overlay {
+    pub fn new(owner: HWND, strictness: Strictness, skip_after: Duration) -> Result<Box<Self>> {
+        unsafe {
+            let instance = GetModuleHandleW(None)?;
+            let wc = WNDCLASSW {
+                hCursor: LoadCursorW(None, IDC_ARROW).ok().unwrap(),
+                hInstance: instance.into(),
+                lpszClassName: OVERLAY_CLASS_NAME,
+                style: CS_HREDRAW | CS_VREDRAW,
+                lpfnWndProc: Some(Self::wnd_proc),
+                ..Default::default()
+            };
+            let atom = RegisterClassW(&wc);
+            debug_assert!(atom != 0);
+
+            Ok(Box::new(Self {
+                owner,
+                strictness,
+                skip_after,
+                windows: vec![],
+                font: create_font(160, 700, w!("Segoe UI")),
+                hint_font: create_font(28, 400, w!("Segoe UI")),
+                bgbrush: CreateSolidBrush(COLORREF(0x00202020)),
+                shown_at: None,
+                remaining: 0,
+                escapes: vec![],
+            }))
+        }
+    }
+
+    pub fn is_shown(&self) -> bool {
+        !self.windows.is_empty()
+    }
+
+    pub unsafe fn show(&mut self, remaining: Duration) {
+        if self.is_shown() {
+            return;
+        }
+        let Ok(instance) = GetModuleHandleW(None) else {
+            return;
+        };
+        let mut monitors: Vec<RECT> = vec![];
+        EnumDisplayMonitors(
+            None,
+            None,
+            Some(Self::monitor_proc),
+            LPARAM(&mut monitors as *mut _ as isize),
+        )
+        .log_failure("EnumDisplayMonitors");
+
+        for rect in monitors {
+            let handle = CreateWindowExW(
+                WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_LAYERED,
+                OVERLAY_CLASS_NAME,
+                w!("tinitime break"),
+                WS_POPUP | WS_VISIBLE,
+                rect.left,
+                rect.top,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                None,
+                None,
+                Some(instance.into()),
+                Some(self as *mut _ as _),
+            );
+            if let Ok(handle) = handle {
+                SetLayeredWindowAttributes(handle, COLORREF::default(), OVERLAY_ALPHA, LWA_ALPHA)
+                    .log_failure("SetLayeredWindowAttributes");
+                self.windows.push(handle);
+            }
+        }
+
+        self.shown_at = Some(Instant::now());
+        self.escapes.clear();
+        self.update(remaining);
+        if let Some(first) = self.windows.first() {
+            // Windows may refuse, then the overlay is topmost all the same.
+            let _ = SetForegroundWindow(*first);
+        }
+    }
+
+    pub unsafe fn hide(&mut self) {
+        for handle in self.windows.drain(..) {
+            DestroyWindow(handle).log_failure("DestroyWindow");
+        }
+        self.shown_at = None;
+    }
+
+    pub unsafe fn update(&mut self, remaining: Duration) {
+        let remaining = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+        if remaining != self.remaining {
+            self.remaining = remaining;
+            for handle in &self.windows {
+                InvalidateRect(Some(*handle), None, false).log_failure("InvalidateRect");
+            }
+        }
+    }
+
+    fn skip_wait(&self) -> Option<Duration> {
+        let shown_for = self
+            .shown_at
+            .map_or(Duration::ZERO, |shown_at| shown_at.elapsed());
+        match self.strictness {
+            Strictness::Gentle => Some(Duration::ZERO),
+            Strictness::Normal => Some(self.skip_after.saturating_sub(shown_for)),
+            Strictness::Strict => None,
+        }
+    }
+
+    fn hint(&self) -> String {
+        match self.skip_wait() {
+            Some(wait) if wait.is_zero() => tr!("overlay-skip"),
+            Some(wait) => tr!("overlay-skip-in", seconds = wait.as_secs() + 1),
+            None => tr!("overlay-break"),
+        }
+    }
+
+    unsafe fn escape_pressed(&mut self) {
+        let now = Instant::now();
+        self.escapes.retain(|pressed| now - *pressed < HATCH_WINDOW);
+        self.escapes.push(now);
+        let may_skip = self.skip_wait().is_some_and(|wait| wait.is_zero());
+        if may_skip || self.escapes.len() >= HATCH_PRESSES {
+            PostMessageW(Some(self.owner), WM_SKIP_BREAK, WPARAM(0), LPARAM(0))
+                .log_failure("PostMessageW");
+        }
+    }
+
+    unsafe fn paint(&self, handle: HWND, ps: &PAINTSTRUCT, hdc: HDC) {
+        let mut rect = RECT::default();
+        GetClientRect(handle, &mut rect).log_failure("GetClientRect");
+        FillRect(hdc, &ps.rcPaint, self.bgbrush);
+        SetBkMode(hdc, TRANSPARENT);
+        SetTextColor(hdc, COLORREF(0x00FFFFFF));
+
+        let time = format!("{}:{:02}", self.remaining / 60, self.remaining % 60);
+        let mut time: Vec<u16> = time.encode_utf16().collect();
+        let mut rtime = rect;
+        rtime.bottom = (rect.top + rect.bottom) / 2 + 80;
+        rtime.top = rtime.bottom - 200;
+        SelectObject(hdc, HGDIOBJ::from(self.font));
+        DrawTextW(
+            hdc,
+            &mut time,
+            &mut rtime,
+            DT_SINGLELINE | DT_VCENTER | DT_CENTER,
+        );
+
+        let mut hint: Vec<u16> = self.hint().encode_utf16().collect();
+        let mut rhint = rect;
+        rhint.top = rtime.bottom;
+        rhint.bottom = rtime.bottom + 60;
+        SelectObject(hdc, HGDIOBJ::from(self.hint_font));
+        DrawTextW(
+            hdc,
+            &mut hint,
+            &mut rhint,
+            DT_SINGLELINE | DT_VCENTER | DT_CENTER,
+        );
+    }
+
+    unsafe fn message_handler(
+        &mut self,
+        handle: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        match message {
+            WM_PAINT => {
+                let mut ps = PAINTSTRUCT::default();
+                let hdc = BeginPaint(handle, &mut ps);
+                self.paint(handle, &ps, hdc);
+                // Always succeeds.
+                let _ = EndPaint(handle, &ps);
+                LRESULT(0)
+            }
+            WM_ERASEBKGND => LRESULT(1),
+            WM_KEYDOWN if wparam.0 as u16 == VK_ESCAPE.0 => {
+                self.escape_pressed();
+                LRESULT(0)
+            }
+            WM_CLOSE => LRESULT(0),
+            _ => DefWindowProcW(handle, message, wparam, lparam),
+        }
+    }
+
+    unsafe extern "system" fn monitor_proc(
+        _monitor: HMONITOR,
+        _hdc: HDC,
+        rect: *mut RECT,
+        data: LPARAM,
+    ) -> BOOL {
+        let monitors = &mut *(data.0 as *mut Vec<RECT>);
+        monitors.push(*rect);
+        TRUE
+    }
+
+    unsafe extern "system" fn wnd_proc(
+        window: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        if message == WM_NCCREATE {
+            let cs = lparam.0 as *const CREATESTRUCTW;
+            SetWindowLongPtrW(window, GWLP_USERDATA, (*cs).lpCreateParams as _);
+        } else {
+            let this = GetWindowLongPtrW(window, GWLP_USERDATA) as *mut Self;
+            if let Some(this) = this.as_mut() {
+                return this.message_handler(window, message, wparam, lparam);
+            }
+        }
+        DefWindowProcW(window, message, wparam, lparam)
+    }
+}
+
+impl Drop for Overlay {
+    fn drop(&mut self) {
+        unsafe {
+            self.hide();
+            DeleteObject(HGDIOBJ::from(self.font)).log_failure("DeleteObject");
+            DeleteObject(HGDIOBJ::from(self.hint_font)).log_failure("DeleteObject");
+            DeleteObject(HGDIOBJ::from(self.bgbrush)).log_failure("DeleteObject");
+        }
+    }
+}
+
+pub unsafe fn create_font(height: i32, weight: i32, face: PCWSTR) -> HFONT {
+    let font = CreateFontW(
+        height,
+        0,
+        0,
+        0,
+        weight,
+        0,
+        0,
+        0,
+        DEFAULT_CHARSET,
+        OUT_DEFAULT_PRECIS,
+        CLIP_DEFAULT_PRECIS,
+        DEFAULT_QUALITY,
+        0,
+        face,
+    );
+    if font.is_invalid() {
+        log::warning!("font {}: falling back to the system font", face.display());
+    }
+    font
+}
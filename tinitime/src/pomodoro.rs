@@ -0,0 +1,170 @@
+use std::time::Duration;
+
+const DEF_SHORT_BREAK: Duration = Duration::from_secs(300);
+const DEF_LONG_BREAK: Duration = Duration::from_secs(900);
+const DEF_LONG_BREAK_EVERY: u32 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Focus,
+    ShortBreak,
+    LongBreak,
+}
+
+impl Phase {
+    pub fn is_break(&self) -> bool {
+        *self != Phase::Focus
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Phase::Focus => "focus",
+            Phase::ShortBreak => "short-break",
+            Phase::LongBreak => "long-break",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [Phase::Focus, Phase::ShortBreak, Phase::LongBreak]
+            .into_iter()
+            .find(|phase| phase.as_str() == value)
+    }
+}
+
+/// How strictly the break overlay holds on to a break.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// Escape skips the break right away.
+    Gentle,
+    /// Escape skips the break once the skip delay has passed.
+    #[default]
+    Normal,
+    /// Only the escape hatch, pressing Escape five times in quick
+    /// succession, ends the break early.
+    Strict,
+}
+
+impl Strictness {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "gentle" => Some(Strictness::Gentle),
+            "normal" => Some(Strictness::Normal),
+            "strict" => Some(Strictness::Strict),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PomodoroConfig {
+    pub short_break: Duration,
+    pub long_break: Duration,
+    pub long_break_every: u32,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        Self {
+            short_break: DEF_SHORT_BREAK,
+            long_break: DEF_LONG_BREAK,
+            long_break_every: DEF_LONG_BREAK_EVERY,
+        }
+    }
+}
+
+/// Tracks the focus/break cycle: every completed focus phase is followed by
+/// a short break, and every `long_break_every`th one by a long break.
+#[derive(Clone)]
+pub struct Pomodoro {
+    config: PomodoroConfig,
+    phase: Phase,
+    completed: u32,
+}
+
+impl Pomodoro {
+    pub fn new(config: PomodoroConfig) -> Self {
+        Self {
+            config,
+            phase: Phase::Focus,
+            completed: 0,
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn break_duration(&self) -> Duration {
+        match self.phase {
+            Phase::LongBreak => self.config.long_break,
+            _ => self.config.short_break,
+        }
+    }
+
+    /// Moves on after the current phase ran to completion.
+    pub fn complete(&mut self) -> Phase {
+        self.phase = match self.phase {
+            Phase::Focus => {
+                self.completed += 1;
+                let every = self.config.long_break_every.max(1);
+                if self.completed.is_multiple_of(every) {
+                    Phase::LongBreak
+                } else {
+                    Phase::ShortBreak
+                }
+            }
+            Phase::ShortBreak | Phase::LongBreak => Phase::Focus,
+        };
+        self.phase
+    }
+
+    /// Ends a break early, or abandons a focus phase without counting it.
+    pub fn skip(&mut self) {
+        self.phase = Phase::Focus;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_fourth_focus_phase_earns_a_long_break() {
+        let mut pomodoro = Pomodoro::new(PomodoroConfig::default());
+        let mut phases = vec![pomodoro.phase()];
+        for _ in 0..16 {
+            phases.push(pomodoro.complete());
+        }
+        let cycle = [
+            Phase::Focus,
+            Phase::ShortBreak,
+            Phase::Focus,
+            Phase::ShortBreak,
+            Phase::Focus,
+            Phase::ShortBreak,
+            Phase::Focus,
+            Phase::LongBreak,
+        ];
+        assert_eq!(phases[..8], cycle);
+        assert_eq!(phases[8..16], cycle);
+        assert_eq!(phases[16], Phase::Focus);
+    }
+
+    #[test]
+    fn break_length_follows_the_phase() {
+        let mut pomodoro = Pomodoro::new(PomodoroConfig {
+            long_break_every: 2,
+            ..PomodoroConfig::default()
+        });
+        pomodoro.complete();
+        assert_eq!(pomodoro.break_duration(), DEF_SHORT_BREAK);
+        pomodoro.complete();
+        // A skipped focus phase does not count towards the long break.
+        pomodoro.skip();
+        assert_eq!(pomodoro.phase(), Phase::Focus);
+        assert_eq!(pomodoro.complete(), Phase::LongBreak);
+        assert_eq!(pomodoro.break_duration(), DEF_LONG_BREAK);
+        pomodoro.skip();
+        assert_eq!(pomodoro.phase(), Phase::Focus);
+    }
+}
@@ -0,0 +1,387 @@
+use std::{fmt, path::Path, path::PathBuf, time::Duration};
+
+use crate::helpers::{load_file, parse_duration};
+
+const MAX_STEPS: usize = 10_000;
+
+/// One timed phase of an interval program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub name: String,
+    pub duration: Duration,
+    pub color: Option<u32>,
+    pub sound: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+struct Token<'a> {
+    text: &'a str,
+    column: usize,
+}
+
+struct Block {
+    count: usize,
+    steps: Vec<Step>,
+    line: usize,
+    column: usize,
+}
+
+/// Parses an interval program. Each line holds either a step,
+/// `NAME DURATION [color=#RRGGBB] [sound=FILE]`, the start of a repeated block,
+/// `repeat COUNT {`, or the `}` closing it. Names containing spaces are
+/// quoted and `#` starts a comment. For example:
+///
+/// ```text
+/// repeat 2 {
+///     Intro 5m color=#2B79D7
+///     Breakout 15m sound=gong.wav
+///     "Share-out" 10m
+/// }
+/// ```
+pub fn parse(source: &str) -> Result<Vec<Step>, Vec<ParseError>> {
+    let mut errors = vec![];
+    let mut blocks = vec![Block {
+        count: 1,
+        steps: vec![],
+        line: 0,
+        column: 0,
+    }];
+
+    for (index, line) in source.lines().enumerate() {
+        let line_no = index + 1;
+        let mut error = |column: usize, message: String| {
+            errors.push(ParseError {
+                line: line_no,
+                column,
+                message,
+            })
+        };
+        let tokens = match tokenize(line) {
+            Ok(tokens) => tokens,
+            Err(column) => {
+                error(column, "unterminated quote".to_string());
+                continue;
+            }
+        };
+        let Some(first) = tokens.first() else {
+            continue;
+        };
+
+        match first.text {
+            "repeat" => {
+                let count = tokens.get(1).map(|token| {
+                    let count = token.text.strip_suffix('x').unwrap_or(token.text);
+                    (
+                        token.column,
+                        count.parse::<usize>().ok().filter(|count| *count > 0),
+                    )
+                });
+                match (count, tokens.get(2).map(|token| token.text), tokens.get(3)) {
+                    (Some((_, Some(count))), Some("{"), None) => blocks.push(Block {
+                        count,
+                        steps: vec![],
+                        line: line_no,
+                        column: first.column,
+                    }),
+                    (Some((column, None)), _, _) => {
+                        error(column, "expected a positive repeat count".to_string())
+                    }
+                    (None, _, _) => error(
+                        first.column + first.text.len(),
+                        "expected a repeat count".to_string(),
+                    ),
+                    (_, _, Some(extra)) => error(extra.column, "unexpected text".to_string()),
+                    (_, _, None) => error(first.column, "expected `repeat COUNT {`".to_string()),
+                }
+            }
+            "}" => {
+                if let Some(extra) = tokens.get(1) {
+                    error(extra.column, "unexpected text".to_string());
+                }
+                if blocks.len() == 1 {
+                    error(first.column, "`}` without matching `repeat`".to_string());
+                    continue;
+                }
+                let block = blocks.pop().unwrap();
+                let parent = blocks.last_mut().unwrap();
+                let total = block
+                    .steps
+                    .len()
+                    .checked_mul(block.count)
+                    .and_then(|steps| steps.checked_add(parent.steps.len()));
+                if total.is_none_or(|total| total > MAX_STEPS) {
+                    error(
+                        first.column,
+                        format!("program has more than {} steps", MAX_STEPS),
+                    );
+                    continue;
+                }
+                for _ in 0..block.count {
+                    parent.steps.extend(block.steps.iter().cloned());
+                }
+            }
+            _ => match parse_step(&tokens) {
+                Ok(step) => blocks.last_mut().unwrap().steps.push(step),
+                Err((column, message)) => error(column, message),
+            },
+        }
+    }
+
+    while blocks.len() > 1 {
+        let block = blocks.pop().unwrap();
+        errors.push(ParseError {
+            line: block.line,
+            column: block.column,
+            message: "`repeat` block is never closed".to_string(),
+        });
+    }
+
+    let steps = blocks.pop().map(|block| block.steps).unwrap_or_default();
+    if errors.is_empty() && steps.is_empty() {
+        errors.push(ParseError {
+            line: 1,
+            column: 1,
+            message: "program has no steps".to_string(),
+        });
+    }
+    if errors.is_empty() {
+        Ok(steps)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Loads and parses a program file; sound files are relative to it.
+pub fn load(path: &Path) -> Result<Vec<Step>, Vec<String>> {
+    let base = path.parent().unwrap_or(Path::new(""));
+    let mut steps = load_file(path, parse)?;
+    for step in &mut steps {
+        if let Some(sound) = &mut step.sound {
+            *sound = base.join(&sound);
+        }
+    }
+    Ok(steps)
+}
+
+fn parse_step(tokens: &[Token]) -> Result<Step, (usize, String)> {
+    let name = &tokens[0];
+    let duration = tokens.get(1).ok_or((
+        name.column + name.text.len(),
+        "expected a duration".to_string(),
+    ))?;
+    let mut step = Step {
+        name: unquote(name.text).to_string(),
+        duration: parse_duration(duration.text).ok_or((
+            duration.column,
+            format!("invalid duration `{}`", duration.text),
+        ))?,
+        color: None,
+        sound: None,
+    };
+    for option in &tokens[2..] {
+        match option.text.split_once('=') {
+            Some(("color", value)) => {
+                step.color = Some(parse_color(value).ok_or((
+                    option.column + "color=".len(),
+                    format!("invalid color `{}`, expected #RRGGBB", value),
+                ))?);
+            }
+            Some(("sound", value)) if !unquote(value).is_empty() => {
+                step.sound = Some(PathBuf::from(unquote(value)));
+            }
+            _ => return Err((option.column, format!("unknown option `{}`", option.text))),
+        }
+    }
+    Ok(step)
+}
+
+fn parse_color(value: &str) -> Option<u32> {
+    let hex = value.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    u32::from_str_radix(hex, 16).ok()
+}
+
+fn unquote(text: &str) -> &str {
+    text.strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+        .unwrap_or(text)
+}
+
+/// Splits a line into whitespace-separated tokens with 1-based columns.
+/// Quotes may span whitespace; a token starting with `#` ends the line.
+fn tokenize(line: &str) -> Result<Vec<Token<'_>>, usize> {
+    let mut tokens = vec![];
+    let mut chars = line.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == '#' {
+            break;
+        }
+        let column = line[..start].chars().count() + 1;
+        let mut quote_column = None;
+        let mut end = line.len();
+        while let Some(&(index, c)) = chars.peek() {
+            if c.is_whitespace() && quote_column.is_none() {
+                end = index;
+                break;
+            }
+            if c == '"' {
+                quote_column = match quote_column {
+                    Some(_) => None,
+                    None => Some(line[..index].chars().count() + 1),
+                };
+            }
+            chars.next();
+        }
+        if let Some(quote_column) = quote_column {
+            return Err(quote_column);
+        }
+        tokens.push(Token {
+            text: &line[start..end],
+            column,
+        });
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(name: &str, minutes: u64) -> Step {
+        Step {
+            name: name.to_string(),
+            duration: Duration::from_secs(minutes * 60),
+            color: None,
+            sound: None,
+        }
+    }
+
+    fn errors(source: &str) -> Vec<String> {
+        let errors = parse(source).unwrap_err();
+        errors.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn parses_steps_with_options() {
+        let source = "# warm-up\n\
+                      Intro 5m color=#2B79D7\n\
+                      \"Share out\" 10m sound=\"gong.wav\"  # the end\n";
+        assert_eq!(
+            parse(source).unwrap(),
+            [
+                Step {
+                    color: Some(0x2B79D7),
+                    ..step("Intro", 5)
+                },
+                Step {
+                    sound: Some(PathBuf::from("gong.wav")),
+                    ..step("Share out", 10)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn expands_nested_repeats() {
+        let source = "repeat 2x {\n\
+                      \tWork 25m\n\
+                      \trepeat 2 {\n\
+                      \t\tStretch 1m\n\
+                      \t}\n\
+                      }\n\
+                      Done 5m\n";
+        let names: Vec<_> = parse(source)
+            .unwrap()
+            .into_iter()
+            .map(|step| step.name)
+            .collect();
+        assert_eq!(
+            names,
+            ["Work", "Stretch", "Stretch", "Work", "Stretch", "Stretch", "Done"]
+        );
+    }
+
+    #[test]
+    fn reports_malformed_lines_with_columns() {
+        assert_eq!(errors("Intro"), ["1:6: expected a duration"]);
+        assert_eq!(errors("Intro soon"), ["1:7: invalid duration `soon`"]);
+        assert_eq!(
+            errors("Intro 5m color=blue"),
+            ["1:16: invalid color `blue`, expected #RRGGBB"]
+        );
+        assert_eq!(errors("Intro 5m loud"), ["1:10: unknown option `loud`"]);
+        assert_eq!(errors("\"Intro 5m"), ["1:1: unterminated quote"]);
+        assert_eq!(errors("# nothing"), ["1:1: program has no steps"]);
+    }
+
+    #[test]
+    fn reports_every_error_in_a_file() {
+        assert_eq!(
+            errors("Intro 5m\nrepeat {\nrepeat 0 {\nBreak\n}\n}\n}"),
+            [
+                "2:8: expected a positive repeat count",
+                "3:8: expected a positive repeat count",
+                "4:6: expected a duration",
+                "5:1: `}` without matching `repeat`",
+                "6:1: `}` without matching `repeat`",
+                "7:1: `}` without matching `repeat`",
+            ]
+        );
+        assert_eq!(
+            errors("repeat 2 {\n  repeat 3 { x\n  Work 1m"),
+            [
+                "2:14: unexpected text",
+                "1:1: `repeat` block is never closed",
+            ]
+        );
+        assert_eq!(errors("repeat"), ["1:7: expected a repeat count"]);
+        assert_eq!(
+            errors("repeat 2 (\n}"),
+            [
+                "1:1: expected `repeat COUNT {`",
+                "2:1: `}` without matching `repeat`",
+            ]
+        );
+    }
+
+    #[test]
+    fn limits_the_number_of_steps() {
+        let at_limit = format!("repeat {} {{\nWork 1m\n}}", MAX_STEPS);
+        assert_eq!(parse(&at_limit).unwrap().len(), MAX_STEPS);
+
+        let over = format!("Intro 1m\nrepeat {} {{\nWork 1m\n}}", MAX_STEPS);
+        assert_eq!(
+            errors(&over),
+            [format!("4:1: program has more than {} steps", MAX_STEPS)]
+        );
+        let nested = "repeat 1000 {\nrepeat 1000 {\nWork 1m\n}\n}";
+        assert_eq!(
+            errors(nested),
+            [format!("5:1: program has more than {} steps", MAX_STEPS)]
+        );
+    }
+
+    #[test]
+    fn huge_repeat_counts_do_not_overflow() {
+        let source = format!("repeat {} {{\nA 1m\nB 1m\n}}", usize::MAX);
+        assert_eq!(
+            errors(&source),
+            [format!("4:1: program has more than {} steps", MAX_STEPS)]
+        );
+    }
+}
@@ -0,0 +1,304 @@
+use std::{
+    ffi::c_void,
+    ptr::null_mut,
+    sync::{
+        atomic::{AtomicPtr, Ordering},
+        Once,
+    },
+    time::SystemTime,
+};
+
+use windows::{
+    core::{w, Result, HSTRING, PCWSTR},
+    Win32::{
+        Foundation::*,
+        Graphics::Gdi::{DeleteObject, GetSysColorBrush, COLOR_BTNFACE, HFONT, HGDIOBJ},
+        System::LibraryLoader::GetModuleHandleW,
+        UI::{Controls::EM_LIMITTEXT, Input::KeyboardAndMouse::SetFocus, WindowsAndMessaging::*},
+    },
+};
+
+use crate::{
+    error::LogFailure, helpers::loword, history::clean_label, i18n::tr, overlay::create_font,
+};
+
+const REFLECTION_CLASS_NAME: PCWSTR = w!("rxcle.tinitime.reflection");
+/// Sent to the owner with `REFLECTION_SAVE` or `REFLECTION_DISMISS` in
+/// `wparam`.
+pub const WM_REFLECTION: u32 = WM_APP + 3;
+pub const REFLECTION_SAVE: usize = 0;
+pub const REFLECTION_DISMISS: usize = 1;
+
+const WIDTH: i32 = 280;
+const HEIGHT: i32 = 120;
+const MARGIN: i32 = 8;
+const GAP: i32 = 2;
+const MAX_NOTE: usize = 200;
+
+static REGISTER_CLASS: Once = Once::new();
+/// The open prompt, whose keyboard input goes through `IsDialogMessageW`.
+static ACTIVE: AtomicPtr<c_void> = AtomicPtr::new(null_mut());
+
+/// Asks what a finished session got done and how often it was interrupted.
+/// Enter saves, Escape dismisses; either is reported to `owner` as
+/// `WM_REFLECTION`.
+pub struct ReflectionPrompt {
+    owner: HWND,
+    handle: HWND,
+    start: SystemTime,
+    note: HWND,
+    interruptions: HWND,
+    font: HFONT,
+}
+
+impl ReflectionPrompt {
+    pub fn new(owner: HWND, start: SystemTime) -> Result<Box<Self>> {
+        unsafe {
+            let instance = GetModuleHandleW(None)?;
+            REGISTER_CLASS.call_once(|| {
+                let wc = WNDCLASSW {
+                    hCursor: LoadCursorW(None, IDC_ARROW).ok().unwrap(),
+                    hInstance: instance.into(),
+                    lpszClassName: REFLECTION_CLASS_NAME,
+                    hbrBackground: GetSysColorBrush(COLOR_BTNFACE),
+                    lpfnWndProc: Some(Self::wnd_proc),
+                    ..Default::default()
+                };
+                let atom = RegisterClassW(&wc);
+                debug_assert!(atom != 0);
+            });
+
+            let style = WS_POPUP | WS_CAPTION | WS_SYSMENU;
+            let ex_style = WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_CONTROLPARENT;
+            let mut rect = RECT {
+                left: 0,
+                top: 0,
+                right: WIDTH,
+                bottom: HEIGHT,
+            };
+            AdjustWindowRectEx(&mut rect, style, false, ex_style)?;
+
+            let mut window = Box::new(Self {
+                owner,
+                handle: HWND::default(),
+                start,
+                note: HWND::default(),
+                interruptions: HWND::default(),
+                font: create_font(15, 400, w!("Segoe UI")),
+            });
+            window.handle = CreateWindowExW(
+                ex_style,
+                REFLECTION_CLASS_NAME,
+                &HSTRING::from(tr!("reflection-title")),
+                style,
+                0,
+                0,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                Some(owner),
+                None,
+                Some(instance.into()),
+                Some(window.as_mut() as *mut _ as _),
+            )?;
+
+            let width = WIDTH - 2 * MARGIN;
+            window.add_control(
+                w!("STATIC"),
+                "reflection-done",
+                0,
+                (MARGIN, 8, width, 18),
+                0,
+            );
+            window.note = window.add_control(
+                w!("EDIT"),
+                "",
+                WS_TABSTOP.0 | WS_BORDER.0 | ES_AUTOHSCROLL as u32,
+                (MARGIN, 28, width, 22),
+                0,
+            );
+            SendMessageW(window.note, EM_LIMITTEXT, Some(WPARAM(MAX_NOTE)), None);
+            window.add_control(
+                w!("STATIC"),
+                "reflection-interruptions",
+                0,
+                (MARGIN, 62, width - 60, 18),
+                0,
+            );
+            window.interruptions = window.add_control(
+                w!("EDIT"),
+                "",
+                WS_TABSTOP.0 | WS_BORDER.0 | ES_NUMBER as u32,
+                (WIDTH - MARGIN - 50, 58, 50, 22),
+                0,
+            );
+            SendMessageW(window.interruptions, EM_LIMITTEXT, Some(WPARAM(4)), None);
+            window.add_control(
+                w!("BUTTON"),
+                "reflection-save",
+                WS_TABSTOP.0 | BS_DEFPUSHBUTTON as u32,
+                (WIDTH - MARGIN - 166, 88, 80, 24),
+                IDOK.0,
+            );
+            window.add_control(
+                w!("BUTTON"),
+                "reflection-skip",
+                WS_TABSTOP.0 | BS_PUSHBUTTON as u32,
+                (WIDTH - MARGIN - 80, 88, 80, 24),
+                IDCANCEL.0,
+            );
+            ACTIVE.store(window.handle.0, Ordering::Relaxed);
+            Ok(window)
+        }
+    }
+
+    unsafe fn add_control(
+        &self,
+        class: PCWSTR,
+        text: &str,
+        style: u32,
+        (x, y, width, height): (i32, i32, i32, i32),
+        id: i32,
+    ) -> HWND {
+        let text = if text.is_empty() {
+            String::new()
+        } else {
+            tr!(text)
+        };
+        let control = CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            class,
+            &HSTRING::from(text),
+            WS_CHILD | WS_VISIBLE | WINDOW_STYLE(style),
+            x,
+            y,
+            width,
+            height,
+            Some(self.handle),
+            Some(HMENU(id as isize as *mut c_void)),
+            None,
+            None,
+        )
+        .unwrap_or_default();
+        SendMessageW(
+            control,
+            WM_SETFONT,
+            Some(WPARAM(self.font.0 as usize)),
+            Some(LPARAM(1)),
+        );
+        control
+    }
+
+    /// When the session the prompt is about started.
+    pub fn start(&self) -> SystemTime {
+        self.start
+    }
+
+    pub fn note(&self) -> Option<String> {
+        clean_label(&text(self.note))
+    }
+
+    pub fn interruptions(&self) -> Option<u32> {
+        text(self.interruptions).trim().parse().ok()
+    }
+
+    /// Shows the prompt below `anchor`, or above it when there is no room,
+    /// and puts the cursor in the note.
+    pub unsafe fn show_near(&self, anchor: RECT, work: RECT) -> Result<()> {
+        let mut rect = RECT::default();
+        GetWindowRect(self.handle, &mut rect)?;
+        let width = rect.right - rect.left;
+        let height = rect.bottom - rect.top;
+        let x = (anchor.right - width).max(work.left);
+        let y = if anchor.bottom + GAP + height <= work.bottom {
+            anchor.bottom + GAP
+        } else {
+            anchor.top - GAP - height
+        };
+        SetWindowPos(
+            self.handle,
+            Some(HWND_TOPMOST),
+            x,
+            y,
+            0,
+            0,
+            SWP_NOSIZE | SWP_SHOWWINDOW,
+        )?;
+        // Windows may refuse, then the prompt waits topmost until clicked.
+        let _ = SetForegroundWindow(self.handle);
+        SetFocus(Some(self.note))?;
+        Ok(())
+    }
+
+    unsafe fn close(&self, event: usize) {
+        PostMessageW(Some(self.owner), WM_REFLECTION, WPARAM(event), LPARAM(0))
+            .log_failure("PostMessageW");
+    }
+
+    unsafe fn message_handler(&mut self, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        match message {
+            WM_COMMAND => {
+                match loword(wparam.0 as isize) {
+                    id if id == IDOK.0 => self.close(REFLECTION_SAVE),
+                    id if id == IDCANCEL.0 => self.close(REFLECTION_DISMISS),
+                    _ => {}
+                }
+                LRESULT(0)
+            }
+            WM_CLOSE => {
+                self.close(REFLECTION_DISMISS);
+                LRESULT(0)
+            }
+            _ => DefWindowProcW(self.handle, message, wparam, lparam),
+        }
+    }
+
+    unsafe extern "system" fn wnd_proc(
+        window: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        if message == WM_NCCREATE {
+            let cs = lparam.0 as *const CREATESTRUCTW;
+            let this = (*cs).lpCreateParams as *mut Self;
+            (*this).handle = window;
+            SetWindowLongPtrW(window, GWLP_USERDATA, this as _);
+        } else {
+            let this = GetWindowLongPtrW(window, GWLP_USERDATA) as *mut Self;
+            if let Some(this) = this.as_mut() {
+                return this.message_handler(message, wparam, lparam);
+            }
+        }
+        DefWindowProcW(window, message, wparam, lparam)
+    }
+}
+
+impl Drop for ReflectionPrompt {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = ACTIVE.compare_exchange(
+                self.handle.0,
+                null_mut(),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            );
+            SetWindowLongPtrW(self.handle, GWLP_USERDATA, 0);
+            DestroyWindow(self.handle).log_failure("DestroyWindow");
+            DeleteObject(HGDIOBJ::from(self.font)).log_failure("DeleteObject");
+        }
+    }
+}
+
+/// The open prompt, if any, for the message loop to pass keys to.
+pub fn active() -> Option<HWND> {
+    let handle = ACTIVE.load(Ordering::Relaxed);
+    (!handle.is_null()).then_some(HWND(handle))
+}
+
+fn text(control: HWND) -> String {
+    unsafe {
+        let mut text = vec![0u16; GetWindowTextLengthW(control).max(0) as usize + 1];
+        let len = GetWindowTextW(control, &mut text);
+        String::from_utf16_lossy(&text[..len.max(0) as usize])
+    }
+}
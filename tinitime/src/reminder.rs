@@ -0,0 +1,253 @@
+use std::time::Duration;
+
+use crate::helpers::parse_duration;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reminder {
+    pub name: String,
+    pub every: Duration,
+    pub break_for: Duration,
+}
+
+impl Reminder {
+    /// Parses `NAME:EVERY:BREAK`, e.g. `eyes:20m:20s`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.splitn(3, ':');
+        let name = parts.next()?.trim();
+        let every = parse_duration(parts.next()?)?;
+        let break_for = parse_duration(parts.next()?)?;
+        (!name.is_empty()).then(|| Self {
+            name: name.to_string(),
+            every,
+            break_for,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReminderEvent {
+    BreakStarted(usize),
+    BreakEnded(usize),
+}
+
+struct Slot {
+    reminder: Reminder,
+    due: Duration,
+    on_break: bool,
+}
+
+/// Schedules recurring reminders independently of the main timer. Each
+/// reminder fires `every` after the end of its previous break.
+pub struct Reminders {
+    slots: Vec<Slot>,
+}
+
+impl Reminders {
+    pub fn new(reminders: &[Reminder], now: Duration) -> Self {
+        let slots = reminders
+            .iter()
+            .map(|reminder| Slot {
+                reminder: reminder.clone(),
+                due: now + reminder.every,
+                on_break: false,
+            })
+            .collect();
+        Self { slots }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Reminder> {
+        self.slots.get(index).map(|slot| &slot.reminder)
+    }
+
+    /// Cycles that were missed entirely (e.g. the clock jumped ahead) are
+    /// skipped without raising events.
+    pub fn tick(&mut self, now: Duration) -> Vec<ReminderEvent> {
+        let mut events = vec![];
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            let cycle = slot.reminder.break_for + slot.reminder.every;
+            if !slot.on_break {
+                while slot.due + slot.reminder.break_for <= now {
+                    slot.due += cycle;
+                }
+                if slot.due <= now {
+                    slot.on_break = true;
+                    events.push(ReminderEvent::BreakStarted(index));
+                }
+            }
+            if slot.on_break && slot.due + slot.reminder.break_for <= now {
+                slot.on_break = false;
+                slot.due += cycle;
+                events.push(ReminderEvent::BreakEnded(index));
+            }
+        }
+        events
+    }
+
+    /// Ends the breaks in progress early. Each reminder comes back `every`
+    /// from now, as if its break had run to the end.
+    pub fn dismiss(&mut self, now: Duration) -> bool {
+        let mut dismissed = false;
+        for slot in self.slots.iter_mut().filter(|slot| slot.on_break) {
+            slot.on_break = false;
+            slot.due = now + slot.reminder.every;
+            dismissed = true;
+        }
+        dismissed
+    }
+
+    /// The reminder break ending soonest, with its remaining time.
+    pub fn active_break(&self, now: Duration) -> Option<(&Reminder, Duration)> {
+        self.slots
+            .iter()
+            .filter(|slot| slot.on_break)
+            .map(|slot| {
+                let end = slot.due + slot.reminder.break_for;
+                (&slot.reminder, end.saturating_sub(now))
+            })
+            .min_by_key(|(_, remaining)| *remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+    use std::time::UNIX_EPOCH;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    fn eyes() -> Reminder {
+        Reminder::parse("eyes:20m:20s").unwrap()
+    }
+
+    #[test]
+    fn parses_name_every_and_break() {
+        assert_eq!(
+            eyes(),
+            Reminder {
+                name: "eyes".to_string(),
+                every: secs(20 * 60),
+                break_for: secs(20),
+            }
+        );
+        assert_eq!(Reminder::parse(":20m:20s"), None);
+        assert_eq!(Reminder::parse("eyes:20m"), None);
+        assert_eq!(Reminder::parse("eyes:soon:20s"), None);
+    }
+
+    #[test]
+    fn fires_every_period_after_the_break() {
+        let clock = ManualClock::new(UNIX_EPOCH);
+        let mut reminders = Reminders::new(&[eyes()], clock.now());
+
+        clock.advance(secs(20 * 60 - 1));
+        assert!(reminders.tick(clock.now()).is_empty());
+        clock.advance(secs(1));
+        assert_eq!(
+            reminders.tick(clock.now()),
+            [ReminderEvent::BreakStarted(0)]
+        );
+        assert_eq!(
+            reminders.active_break(clock.now()),
+            Some((&eyes(), secs(20)))
+        );
+        clock.advance(secs(5));
+        assert!(reminders.tick(clock.now()).is_empty());
+        assert_eq!(reminders.active_break(clock.now()).unwrap().1, secs(15));
+        clock.advance(secs(15));
+        assert_eq!(reminders.tick(clock.now()), [ReminderEvent::BreakEnded(0)]);
+        assert_eq!(reminders.active_break(clock.now()), None);
+
+        // The next one is counted from the end of the break.
+        clock.advance(secs(20 * 60 - 1));
+        assert!(reminders.tick(clock.now()).is_empty());
+        clock.advance(secs(1));
+        assert_eq!(
+            reminders.tick(clock.now()),
+            [ReminderEvent::BreakStarted(0)]
+        );
+    }
+
+    #[test]
+    fn repeats_for_hours_in_one_second_steps() {
+        let clock = ManualClock::new(UNIX_EPOCH);
+        let mut reminders = Reminders::new(&[eyes()], clock.now());
+        let (mut started, mut ended) = (0, 0);
+        for _ in 0..8 * 60 * 60 {
+            clock.advance(secs(1));
+            for event in reminders.tick(clock.now()) {
+                match event {
+                    ReminderEvent::BreakStarted(_) => started += 1,
+                    ReminderEvent::BreakEnded(_) => ended += 1,
+                }
+            }
+        }
+        // A cycle is 20m20s, so 8h hold 23 of them and part of a 24th.
+        assert_eq!((started, ended), (23, 23));
+    }
+
+    #[test]
+    fn reminders_keep_their_own_schedules() {
+        let stand = Reminder::parse("stand:45m:2m").unwrap();
+        let clock = ManualClock::new(UNIX_EPOCH);
+        let mut reminders = Reminders::new(&[eyes(), stand], clock.now());
+
+        clock.advance(secs(20 * 60));
+        assert_eq!(
+            reminders.tick(clock.now()),
+            [ReminderEvent::BreakStarted(0)]
+        );
+        clock.advance(secs(25 * 60));
+        assert_eq!(
+            reminders.tick(clock.now()),
+            [ReminderEvent::BreakEnded(0), ReminderEvent::BreakStarted(1)]
+        );
+        assert_eq!(reminders.active_break(clock.now()).unwrap().0.name, "stand");
+    }
+
+    #[test]
+    fn missed_cycles_are_skipped_quietly() {
+        let clock = ManualClock::new(UNIX_EPOCH);
+        let mut reminders = Reminders::new(&[eyes()], clock.now());
+
+        clock.advance(secs(3 * 60 * 60));
+        assert!(reminders.tick(clock.now()).is_empty());
+        assert_eq!(reminders.active_break(clock.now()), None);
+        // 3h hold 8 cycles and 17m20s, so the 9th break is 2m40s away.
+        clock.advance(secs(2 * 60 + 40));
+        assert_eq!(
+            reminders.tick(clock.now()),
+            [ReminderEvent::BreakStarted(0)]
+        );
+    }
+
+    #[test]
+    fn dismiss_ends_the_break_and_restarts_the_period() {
+        let clock = ManualClock::new(UNIX_EPOCH);
+        let mut reminders = Reminders::new(&[eyes()], clock.now());
+        assert!(!reminders.dismiss(clock.now()));
+
+        clock.advance(secs(20 * 60));
+        reminders.tick(clock.now());
+        clock.advance(secs(5));
+        assert!(reminders.dismiss(clock.now()));
+        assert_eq!(reminders.active_break(clock.now()), None);
+        // No end is reported for a dismissed break.
+        clock.advance(secs(15));
+        assert!(reminders.tick(clock.now()).is_empty());
+
+        clock.advance(secs(20 * 60 - 16));
+        assert!(reminders.tick(clock.now()).is_empty());
+        clock.advance(secs(1));
+        assert_eq!(
+            reminders.tick(clock.now()),
+            [ReminderEvent::BreakStarted(0)]
+        );
+    }
+}
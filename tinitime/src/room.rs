@@ -0,0 +1,615 @@
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind, Write},
+    net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream, UdpSocket},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{config::Config, helpers::same_token, pomodoro::Phase, timer::State};
+
+pub const DEF_PORT: u16 = 7476;
+const GROUP: Ipv4Addr = Ipv4Addr::new(239, 255, 74, 76);
+const QUERY: &str = "tinitime-room?";
+const ANSWER: &str = "tinitime-room";
+const DISCOVERY_WAIT: Duration = Duration::from_secs(1);
+const HEARTBEAT: Duration = Duration::from_secs(5);
+const TIMEOUT: Duration = Duration::from_secs(15);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+/// How often the waiting threads look whether their side has gone away.
+const POLL: Duration = Duration::from_millis(200);
+/// Updates closer than this to what a follower already shows are left to the
+/// next heartbeat.
+const MAX_DRIFT: Duration = Duration::from_millis(500);
+
+/// What the leader's timer shows. Only relative times go over the wire, so
+/// the clocks of leader and followers do not need to agree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RoomState {
+    pub phase: Phase,
+    pub state: State,
+    pub remaining: Duration,
+    pub duration: Duration,
+}
+
+impl RoomState {
+    fn to_line(self) -> String {
+        format!(
+            "state {} {} {} {}\n",
+            self.phase.as_str(),
+            self.state.as_str(),
+            self.remaining.as_millis(),
+            self.duration.as_millis()
+        )
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.strip_prefix("state ")?.split(' ');
+        let phase = Phase::parse(fields.next()?)?;
+        let state = State::parse(fields.next()?)?;
+        let remaining = Duration::from_millis(fields.next()?.parse().ok()?);
+        let duration = Duration::from_millis(fields.next()?.parse().ok()?);
+        Some(Self {
+            phase,
+            state,
+            remaining,
+            duration,
+        })
+    }
+
+    /// The state `elapsed` later, if nobody touches the timer meanwhile.
+    fn after(self, elapsed: Duration) -> Self {
+        match self.state {
+            State::Running => Self {
+                remaining: self.remaining.saturating_sub(elapsed),
+                ..self
+            },
+            State::Stopped | State::Paused => self,
+        }
+    }
+
+    fn differs(&self, other: &Self) -> bool {
+        self.phase != other.phase
+            || self.state != other.state
+            || self.duration != other.duration
+            || self.remaining.abs_diff(other.remaining) > MAX_DRIFT
+    }
+}
+
+/// Opt-in team mode: one instance leads a room and the others follow its
+/// phase and deadline.
+///
+/// A leader listens on every interface and answers anyone on the network
+/// who asks for the room by name. With a token, only followers that know it
+/// are let in; without one, anybody who can reach the machine can follow.
+/// Nothing is encrypted, the token included, so rooms are meant for trusted
+/// networks.
+pub enum Room {
+    Lead(Leader),
+    Follow(Follower),
+}
+
+impl Room {
+    pub fn from_config(config: &Config) -> Option<io::Result<Self>> {
+        let name = config.room.as_deref()?;
+        let port = config.room_port.unwrap_or(DEF_PORT);
+        let token = config.room_token.as_deref();
+        Some(Self::start(name, port, token, config.room_lead))
+    }
+
+    pub fn start(name: &str, port: u16, token: Option<&str>, lead: bool) -> io::Result<Self> {
+        if lead {
+            Leader::start(name, port, token).map(Room::Lead)
+        } else {
+            Ok(Room::Follow(Follower::start(name, port, token)))
+        }
+    }
+}
+
+/// The line a follower opens with. The token follows the room name after a
+/// tab, which neither can contain.
+fn join_line(room: &str, token: Option<&str>) -> String {
+    match token {
+        Some(token) => format!("join {}\t{}", room, token),
+        None => format!("join {}", room),
+    }
+}
+
+/// What the threads of a leader hand to the one that writes to followers.
+enum Message {
+    Publish(RoomState, Instant),
+    Join(u64, TcpStream),
+    Ping(u64),
+    Stop,
+}
+
+/// Answers discovery queries on the multicast group and keeps every follower
+/// up to date over TCP. Late joiners get the current state right away.
+pub struct Leader {
+    messages: Sender<Message>,
+    stopped: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Leader {
+    pub fn start(name: &str, port: u16, token: Option<&str>) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+        listener.set_nonblocking(true)?;
+        let sync_port = listener.local_addr()?.port();
+        let discovery = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port))?;
+        discovery.set_read_timeout(Some(POLL))?;
+        // Without a multicast route, followers on this machine still find us
+        // through loopback.
+        let _ = discovery.join_multicast_v4(&GROUP, &Ipv4Addr::UNSPECIFIED);
+
+        let (messages, received) = mpsc::channel();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let name = name.to_string();
+        let join = Arc::new(join_line(&name, token));
+        let answer = format!("{} {} {}", ANSWER, name, sync_port);
+        let stop = Arc::clone(&stopped);
+        let answering = thread::spawn(move || {
+            let mut buf = [0; 512];
+            while !stop.load(Ordering::Relaxed) {
+                let (len, from) = match discovery.recv_from(&mut buf) {
+                    Ok(received) => received,
+                    Err(error)
+                        if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+                    {
+                        continue
+                    }
+                    Err(_) => return,
+                };
+                let query = String::from_utf8_lossy(&buf[..len]);
+                if query.strip_prefix(QUERY).map(str::trim) == Some(name.as_str()) {
+                    let _ = discovery.send_to(answer.as_bytes(), from);
+                }
+            }
+        });
+
+        let joins = messages.clone();
+        let stop = Arc::clone(&stopped);
+        let accepting = thread::spawn(move || {
+            let mut next_id = 0;
+            while !stop.load(Ordering::Relaxed) {
+                let Ok((stream, _)) = listener.accept() else {
+                    thread::sleep(POLL);
+                    continue;
+                };
+                if stream.set_nonblocking(false).is_err() {
+                    continue;
+                }
+                let (id, join, messages) = (next_id, Arc::clone(&join), joins.clone());
+                next_id += 1;
+                thread::spawn(move || serve(stream, id, &join, &messages));
+            }
+        });
+
+        let broadcasting = thread::spawn(move || Broadcaster::default().run(&received));
+        Ok(Self {
+            messages,
+            stopped,
+            threads: vec![answering, accepting, broadcasting],
+        })
+    }
+
+    /// Hands `state` to the followers, unless it is what they already show.
+    /// Never waits for the network.
+    pub fn publish(&self, state: RoomState) {
+        let _ = self.messages.send(Message::Publish(state, Instant::now()));
+    }
+}
+
+/// Waits for the threads that answer, accept and broadcast; the ones serving
+/// followers end as the broadcaster closes their connections.
+impl Drop for Leader {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        let _ = self.messages.send(Message::Stop);
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Does all the writing to followers, so a slow one holds up only its own
+/// thread and never the timer.
+#[derive(Default)]
+struct Broadcaster {
+    current: Option<(RoomState, Instant)>,
+    followers: Vec<(u64, TcpStream)>,
+}
+
+impl Broadcaster {
+    fn current(&self) -> Option<RoomState> {
+        self.current.map(|(state, at)| state.after(at.elapsed()))
+    }
+
+    fn send(&mut self, line: &str) {
+        self.followers
+            .retain_mut(|(_, follower)| follower.write_all(line.as_bytes()).is_ok());
+    }
+
+    /// Handles messages until the leader goes away, with a heartbeat of the
+    /// current state every `HEARTBEAT`.
+    fn run(mut self, messages: &Receiver<Message>) {
+        let mut next_heartbeat = Instant::now() + HEARTBEAT;
+        loop {
+            let wait = next_heartbeat.saturating_duration_since(Instant::now());
+            match messages.recv_timeout(wait) {
+                Ok(Message::Publish(state, at)) => {
+                    let changed = self.current().is_none_or(|current| current.differs(&state));
+                    self.current = Some((state, at));
+                    if changed {
+                        self.send(&state.to_line());
+                    }
+                }
+                Ok(Message::Join(id, mut stream)) => {
+                    let sent = match self.current() {
+                        Some(state) => stream.write_all(state.to_line().as_bytes()).is_ok(),
+                        None => true,
+                    };
+                    if sent {
+                        self.followers.push((id, stream));
+                    }
+                }
+                Ok(Message::Ping(id)) => {
+                    let follower = self.followers.iter().position(|(other, _)| *other == id);
+                    if let Some(index) = follower {
+                        if self.followers[index].1.write_all(b"pong\n").is_err() {
+                            self.followers.remove(index);
+                        }
+                    }
+                }
+                Ok(Message::Stop) | Err(RecvTimeoutError::Disconnected) => {
+                    for (_, follower) in &self.followers {
+                        let _ = follower.shutdown(Shutdown::Both);
+                    }
+                    return;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+            if Instant::now() >= next_heartbeat {
+                next_heartbeat = Instant::now() + HEARTBEAT;
+                if let Some(state) = self.current() {
+                    self.send(&state.to_line());
+                }
+            }
+        }
+    }
+}
+
+/// Reads from one follower: it has to send the `join` line first, then gets
+/// the state and its pings answered by the broadcasting thread.
+fn serve(stream: TcpStream, id: u64, join: &str, messages: &Sender<Message>) -> Option<()> {
+    stream.set_read_timeout(Some(TIMEOUT)).ok()?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT)).ok()?;
+    stream.set_nodelay(true).ok()?;
+    let writer = stream.try_clone().ok()?;
+    let mut lines = BufReader::new(stream).lines();
+    if !same_token(&lines.next()?.ok()?, join) {
+        return None;
+    }
+    messages.send(Message::Join(id, writer)).ok()?;
+    for line in lines {
+        if line.ok()? == "ping" {
+            messages.send(Message::Ping(id)).ok()?;
+        }
+    }
+    None
+}
+
+/// Finds the leader of a room and reports its state; reconnects whenever the
+/// leader goes away.
+pub struct Follower {
+    updates: Receiver<(RoomState, Instant)>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Follower {
+    pub fn start(name: &str, port: u16, token: Option<&str>) -> Self {
+        let (sender, updates) = mpsc::channel();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let stop = Arc::clone(&stopped);
+        let name = name.to_string();
+        let join = join_line(&name, token);
+        thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                if let Some(leader) = discover(&name, port) {
+                    if let Err(Closed) = follow(leader, &join, &sender, &stop) {
+                        return;
+                    }
+                }
+                thread::sleep(DISCOVERY_WAIT);
+            }
+        });
+        Self { updates, stopped }
+    }
+
+    /// The leader's state as of now, if it sent anything since the last call.
+    pub fn poll(&self) -> Option<RoomState> {
+        let (state, at) = self.updates.try_iter().last()?;
+        Some(state.after(at.elapsed()))
+    }
+}
+
+/// Lets the thread go once it next wakes up; it never waits longer than a
+/// heartbeat.
+impl Drop for Follower {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+/// The follower was dropped, so there is nobody left to report to.
+struct Closed;
+
+fn discover(name: &str, port: u16) -> Option<SocketAddr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.set_read_timeout(Some(DISCOVERY_WAIT)).ok()?;
+    let query = format!("{} {}", QUERY, name);
+    let _ = socket.send_to(query.as_bytes(), (GROUP, port));
+    let _ = socket.send_to(query.as_bytes(), (Ipv4Addr::LOCALHOST, port));
+
+    let mut buf = [0; 512];
+    let deadline = Instant::now() + DISCOVERY_WAIT;
+    while Instant::now() < deadline {
+        let (len, from) = socket.recv_from(&mut buf).ok()?;
+        let answer = String::from_utf8_lossy(&buf[..len]);
+        let Some((room, sync_port)) = answer
+            .strip_prefix(ANSWER)
+            .and_then(|answer| answer.trim().rsplit_once(' '))
+        else {
+            continue;
+        };
+        if let (true, Ok(sync_port)) = (room == name, sync_port.parse()) {
+            return Some(SocketAddr::new(from.ip(), sync_port));
+        }
+    }
+    None
+}
+
+/// Follows the leader until the connection breaks. Half the measured round
+/// trip is taken off every update to make up for the time it spent on the
+/// way.
+fn follow(
+    leader: SocketAddr,
+    join: &str,
+    updates: &Sender<(RoomState, Instant)>,
+    stopped: &AtomicBool,
+) -> Result<(), Closed> {
+    let Ok(mut stream) = TcpStream::connect_timeout(&leader, TIMEOUT) else {
+        return Ok(());
+    };
+    let Ok(reader) = stream.try_clone() else {
+        return Ok(());
+    };
+    let _ = stream.set_read_timeout(Some(HEARTBEAT));
+    let _ = stream.set_nodelay(true);
+    if writeln!(stream, "{}", join).is_err() {
+        return Ok(());
+    }
+
+    let mut reader = BufReader::new(reader);
+    let mut line = String::new();
+    let mut round_trip = Duration::ZERO;
+    let mut ping_sent: Option<Instant> = None;
+    let mut next_ping = Instant::now();
+    let mut last_heard = Instant::now();
+    loop {
+        if stopped.load(Ordering::Relaxed) {
+            return Err(Closed);
+        }
+        if ping_sent.is_none() && Instant::now() >= next_ping {
+            if stream.write_all(b"ping\n").is_err() {
+                return Ok(());
+            }
+            ping_sent = Some(Instant::now());
+        }
+        match reader.read_line(&mut line) {
+            Ok(0) => return Ok(()),
+            Ok(_) => {}
+            Err(error) if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                if last_heard.elapsed() >= TIMEOUT {
+                    return Ok(());
+                }
+                continue;
+            }
+            Err(_) => return Ok(()),
+        }
+        last_heard = Instant::now();
+        if line.trim_end() == "pong" {
+            if let Some(sent) = ping_sent.take() {
+                round_trip = sent.elapsed();
+                next_ping = Instant::now() + HEARTBEAT;
+            }
+        } else if let Some(state) = RoomState::parse(line.trim_end()) {
+            let state = state.after(round_trip / 2);
+            updates.send((state, Instant::now())).map_err(|_| Closed)?;
+        }
+        line.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn running(remaining: u64) -> RoomState {
+        RoomState {
+            phase: Phase::Focus,
+            state: State::Running,
+            remaining: Duration::from_secs(remaining),
+            duration: Duration::from_secs(1500),
+        }
+    }
+
+    /// A discovery port nobody else uses, so tests can run side by side.
+    fn free_port() -> u16 {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).unwrap();
+        socket.local_addr().unwrap().port()
+    }
+
+    /// Waits for the follower to report a state that `matches`.
+    fn wait_for(follower: &Follower, matches: impl Fn(&RoomState) -> bool) -> RoomState {
+        let deadline = Instant::now() + TIMEOUT;
+        while Instant::now() < deadline {
+            if let Some(state) = follower.poll().filter(|state| matches(state)) {
+                return state;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        panic!("the follower never caught up");
+    }
+
+    #[test]
+    fn states_round_trip() {
+        let state = RoomState {
+            phase: Phase::LongBreak,
+            state: State::Paused,
+            remaining: Duration::from_millis(61_250),
+            duration: Duration::from_secs(900),
+        };
+        assert_eq!(RoomState::parse(state.to_line().trim_end()), Some(state));
+        assert_eq!(RoomState::parse("state focus running 1"), None);
+        assert_eq!(RoomState::parse("state lunch running 1 2"), None);
+        assert_eq!(RoomState::parse("pong"), None);
+    }
+
+    #[test]
+    fn only_running_states_move_on() {
+        let later = Duration::from_secs(10);
+        assert_eq!(running(100).after(later), running(90));
+        assert_eq!(running(5).after(later), running(0));
+        let paused = RoomState {
+            state: State::Paused,
+            ..running(100)
+        };
+        assert_eq!(paused.after(later), paused);
+    }
+
+    #[test]
+    fn small_drift_is_no_change() {
+        let ahead = RoomState {
+            remaining: Duration::from_millis(100_400),
+            ..running(100)
+        };
+        assert!(!running(100).differs(&ahead));
+        assert!(running(100).differs(&running(99)));
+        let paused = RoomState {
+            state: State::Paused,
+            ..running(100)
+        };
+        assert!(running(100).differs(&paused));
+    }
+
+    #[test]
+    fn followers_join_late_and_get_updates() {
+        let port = free_port();
+        let leader = Leader::start("standup", port, None).unwrap();
+        leader.publish(running(600));
+        let followers = [
+            Follower::start("standup", port, None),
+            Follower::start("standup", port, None),
+        ];
+        for follower in &followers {
+            let state = wait_for(follower, |_| true);
+            assert_eq!((state.phase, state.state), (Phase::Focus, State::Running));
+            assert!(state.remaining <= Duration::from_secs(600));
+            assert!(state.remaining > Duration::from_secs(590));
+        }
+
+        let paused = RoomState {
+            state: State::Paused,
+            ..running(300)
+        };
+        leader.publish(paused);
+        for follower in &followers {
+            assert_eq!(
+                wait_for(follower, |state| state.state == State::Paused),
+                paused
+            );
+        }
+    }
+
+    #[test]
+    fn rooms_are_kept_apart() {
+        let port = free_port();
+        let leader = Leader::start("design", port, None).unwrap();
+        leader.publish(running(600));
+        let outsider = Follower::start("sales", port, None);
+        let member = Follower::start("design", port, None);
+        wait_for(&member, |_| true);
+        thread::sleep(DISCOVERY_WAIT);
+        assert_eq!(outsider.poll(), None);
+    }
+
+    #[test]
+    fn a_stuck_follower_does_not_hold_up_the_others() {
+        let port = free_port();
+        let leader = Leader::start("standup", port, None).unwrap();
+        leader.publish(running(600));
+
+        // Joins, then never reads what it is sent.
+        let sync = discover("standup", port).unwrap();
+        let mut stuck = TcpStream::connect(sync).unwrap();
+        writeln!(stuck, "join standup").unwrap();
+        // And one that leaves right away.
+        let mut gone = TcpStream::connect(sync).unwrap();
+        writeln!(gone, "join standup").unwrap();
+        drop(gone);
+
+        let follower = Follower::start("standup", port, None);
+        wait_for(&follower, |_| true);
+        let started = Instant::now();
+        for remaining in (0..500).rev() {
+            leader.publish(running(remaining));
+        }
+        assert!(started.elapsed() < WRITE_TIMEOUT);
+        wait_for(&follower, |state| state.remaining.is_zero());
+    }
+
+    #[test]
+    fn rooms_with_a_token_let_in_only_who_knows_it() {
+        let port = free_port();
+        let leader = Leader::start("standup", port, Some("s3cret")).unwrap();
+        leader.publish(running(600));
+        let strangers = [
+            Follower::start("standup", port, None),
+            Follower::start("standup", port, Some("guess")),
+        ];
+        let member = Follower::start("standup", port, Some("s3cret"));
+        wait_for(&member, |_| true);
+        thread::sleep(DISCOVERY_WAIT);
+        for stranger in &strangers {
+            assert_eq!(stranger.poll(), None);
+        }
+    }
+
+    #[test]
+    fn dropping_the_leader_stops_its_threads() {
+        let port = free_port();
+        let leader = Leader::start("standup", port, None).unwrap();
+        leader.publish(running(600));
+        let sync = discover("standup", port).unwrap();
+        let mut follower = TcpStream::connect(sync).unwrap();
+        writeln!(follower, "join standup").unwrap();
+        let mut reader = BufReader::new(follower.try_clone().unwrap());
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        assert!(line.starts_with("state "));
+
+        let started = Instant::now();
+        drop(leader);
+        assert!(started.elapsed() < DISCOVERY_WAIT);
+        // The discovery port is free again, and the follower was let go.
+        UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port)).unwrap();
+        follower.set_read_timeout(Some(TIMEOUT)).unwrap();
+        line.clear();
+        assert_eq!(reader.read_line(&mut line).unwrap(), 0);
+    }
+}
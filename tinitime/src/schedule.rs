@@ -0,0 +1,257 @@
+use std::{
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    clock::Clock,
+    helpers::{load_file, parse_duration, unix_secs},
+    localtime::{Zone, SECS_PER_DAY},
+    until::Until,
+};
+
+/// A planned block of the day, e.g. `09:00 focus 50m`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Block {
+    pub hour: u32,
+    pub minute: u32,
+    pub name: String,
+    pub duration: Duration,
+}
+
+impl Block {
+    fn start_of_day(&self) -> i64 {
+        (self.hour * 3600 + self.minute * 60) as i64
+    }
+}
+
+/// Parses a schedule: one `HH:MM NAME DURATION` block per line, where the
+/// name may contain spaces and `#` starts a comment. For example:
+///
+/// ```text
+/// 09:00 focus 50m
+/// 10:00 focus 50m
+/// 12:00 lunch 45m
+/// ```
+pub fn parse(source: &str) -> std::result::Result<Vec<Block>, Vec<String>> {
+    let mut errors = vec![];
+    let mut blocks = vec![];
+    for (index, line) in source.lines().enumerate() {
+        let line_no = index + 1;
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let [time, name @ .., duration] = words.as_slice() else {
+            errors.push(format!("{}: expected HH:MM NAME DURATION", line_no));
+            continue;
+        };
+        let start = Until::parse(time).filter(|until| !until.tomorrow);
+        let length = parse_duration(duration).filter(|length| !length.is_zero());
+        match (start, length) {
+            (None, _) => errors.push(format!("{}: invalid time '{}'", line_no, time)),
+            (_, None) => errors.push(format!("{}: invalid duration '{}'", line_no, duration)),
+            (_, _) if name.is_empty() => errors.push(format!("{}: expected a name", line_no)),
+            (Some(start), Some(duration)) => blocks.push(Block {
+                hour: start.hour,
+                minute: start.minute,
+                name: name.join(" "),
+                duration,
+            }),
+        }
+    }
+    if errors.is_empty() && blocks.is_empty() {
+        errors.push("1: schedule has no blocks".to_string());
+    }
+    if errors.is_empty() {
+        blocks.sort_by_key(Block::start_of_day);
+        Ok(blocks)
+    } else {
+        Err(errors)
+    }
+}
+
+pub fn load(path: &Path) -> std::result::Result<Vec<Block>, Vec<String>> {
+    load_file(path, parse)
+}
+
+/// Follows a daily schedule, repeating it every day.
+pub struct Scheduler {
+    blocks: Vec<Block>,
+    /// Local start time of the block that was last handed out, so each
+    /// block starts once a day even if it gets stopped early.
+    started: Option<i64>,
+}
+
+impl Scheduler {
+    pub fn new(blocks: Vec<Block>) -> Self {
+        Self {
+            blocks,
+            started: None,
+        }
+    }
+
+    /// Returns the block to start now together with the time left in it.
+    /// A block that is already under way, e.g. when tinitime gets launched
+    /// in the middle of it, is started for what remains of it.
+    pub fn tick(&mut self, clock: &impl Clock, zone: &impl Zone) -> Option<(&Block, Duration)> {
+        let now = local_secs(clock.wall(), zone);
+        let today = now.div_euclid(SECS_PER_DAY) * SECS_PER_DAY;
+        // Yesterday's late blocks may run past midnight.
+        let (start, block) = [today - SECS_PER_DAY, today]
+            .into_iter()
+            .flat_map(|day| {
+                let blocks = self.blocks.iter();
+                blocks.map(move |block| (day + block.start_of_day(), block))
+            })
+            .filter(|(start, block)| *start <= now && now < start + block.duration.as_secs() as i64)
+            .max_by_key(|(start, _)| *start)?;
+        if self.started == Some(start) {
+            return None;
+        }
+        self.started = Some(start);
+        let ends = start + block.duration.as_secs() as i64;
+        Some((block, Duration::from_secs((ends - now) as u64)))
+    }
+
+    /// The next block to start after now, possibly tomorrow's first one.
+    pub fn next(&self, clock: &impl Clock, zone: &impl Zone) -> Option<&Block> {
+        let now = local_secs(clock.wall(), zone);
+        let time_of_day = now.rem_euclid(SECS_PER_DAY);
+        let later = self
+            .blocks
+            .iter()
+            .find(|block| block.start_of_day() > time_of_day);
+        later.or(self.blocks.first())
+    }
+}
+
+fn local_secs(wall: SystemTime, zone: &impl Zone) -> i64 {
+    zone.to_local(unix_secs(wall))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+    use crate::{clock::ManualClock, localtime::FixedZone};
+
+    const DAY: &str = "# a workday\n\
+                       12:00 lunch 45m\n\
+                       09:00 deep work 50m\n\
+                       10:00 focus 50m  # after standup\n";
+    /// 2024-06-03, a Monday, in UTC+2.
+    const ZONE: FixedZone = FixedZone(7200);
+    const MIDNIGHT: u64 = 1_717_372_800 - 7200;
+
+    /// A clock at `hour:minute` local time on the test day.
+    fn at(hour: u64, minute: u64) -> ManualClock {
+        ManualClock::new(UNIX_EPOCH + Duration::from_secs(MIDNIGHT + hour * 3600 + minute * 60))
+    }
+
+    fn mins(minutes: u64) -> Duration {
+        Duration::from_secs(minutes * 60)
+    }
+
+    #[test]
+    fn parses_and_sorts_blocks() {
+        let blocks = parse(DAY).unwrap();
+        let names: Vec<_> = blocks.iter().map(|block| block.name.as_str()).collect();
+        assert_eq!(names, ["deep work", "focus", "lunch"]);
+        assert_eq!(
+            blocks[0],
+            Block {
+                hour: 9,
+                minute: 0,
+                name: "deep work".to_string(),
+                duration: mins(50),
+            }
+        );
+        assert_eq!(parse("1:30pm nap 20m").unwrap()[0].hour, 13);
+    }
+
+    #[test]
+    fn reports_bad_lines() {
+        assert_eq!(
+            parse("9 focus\n25:00 focus 50m\n09:00 focus 0m\n09:00 50m\nfocus\n").unwrap_err(),
+            [
+                "1: invalid time '9'",
+                "2: invalid time '25:00'",
+                "3: invalid duration '0m'",
+                "4: expected a name",
+                "5: expected HH:MM NAME DURATION",
+            ]
+        );
+        assert_eq!(
+            parse("tomorrow 09:00 focus 50m").unwrap_err(),
+            ["1: invalid time 'tomorrow'"]
+        );
+        assert_eq!(
+            parse("# empty\n").unwrap_err(),
+            ["1: schedule has no blocks"]
+        );
+    }
+
+    #[test]
+    fn starts_each_block_on_time_through_the_day() {
+        let clock = at(7, 0);
+        let mut scheduler = Scheduler::new(parse(DAY).unwrap());
+        let mut started = vec![];
+        for minute in 7 * 60..24 * 60 {
+            if let Some((block, remaining)) = scheduler.tick(&clock, &ZONE) {
+                started.push((minute / 60, minute % 60, block.name.clone(), remaining));
+            }
+            clock.advance(mins(1));
+        }
+        assert_eq!(
+            started,
+            [
+                (9, 0, "deep work".to_string(), mins(50)),
+                (10, 0, "focus".to_string(), mins(50)),
+                (12, 0, "lunch".to_string(), mins(45)),
+            ]
+        );
+    }
+
+    #[test]
+    fn names_the_next_block() {
+        let scheduler = Scheduler::new(parse(DAY).unwrap());
+        let next = |clock: &ManualClock| scheduler.next(clock, &ZONE).unwrap().name.clone();
+        assert_eq!(next(&at(7, 0)), "deep work");
+        assert_eq!(next(&at(9, 0)), "focus");
+        assert_eq!(next(&at(11, 59)), "lunch");
+        assert_eq!(next(&at(12, 0)), "deep work");
+        assert_eq!(next(&at(23, 59)), "deep work");
+    }
+
+    #[test]
+    fn joins_a_block_under_way_for_what_is_left() {
+        let clock = at(9, 20);
+        let mut scheduler = Scheduler::new(parse(DAY).unwrap());
+        let (block, remaining) = scheduler.tick(&clock, &ZONE).unwrap();
+        assert_eq!((block.name.as_str(), remaining), ("deep work", mins(30)));
+    }
+
+    #[test]
+    fn starts_a_block_once_a_day() {
+        let clock = at(9, 0);
+        let mut scheduler = Scheduler::new(parse(DAY).unwrap());
+        assert!(scheduler.tick(&clock, &ZONE).is_some());
+        // Stopped early, the block is not started again.
+        clock.advance(mins(10));
+        assert!(scheduler.tick(&clock, &ZONE).is_none());
+        clock.advance(Duration::from_secs(SECS_PER_DAY as u64) - mins(10));
+        let (block, _) = scheduler.tick(&clock, &ZONE).unwrap();
+        assert_eq!(block.name, "deep work");
+    }
+
+    #[test]
+    fn late_blocks_run_past_midnight() {
+        let mut scheduler = Scheduler::new(parse("23:30 late shift 1h").unwrap());
+        let (block, remaining) = scheduler.tick(&at(24, 10), &ZONE).unwrap();
+        assert_eq!((block.name.as_str(), remaining), ("late shift", mins(20)));
+        assert!(scheduler.tick(&at(24, 40), &ZONE).is_none());
+    }
+}
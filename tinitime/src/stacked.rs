@@ -0,0 +1,372 @@
+use std::{sync::Once, time::Duration};
+
+use windows::{
+    core::{w, Error, Result, HSTRING, PCWSTR},
+    Win32::{
+        Foundation::*,
+        Graphics::Gdi::{
+            BeginPaint, CreateSolidBrush, DeleteObject, DrawTextW, EndPaint, FillRect,
+            InvalidateRect, SelectObject, SetBkMode, SetTextColor, DT_END_ELLIPSIS, DT_SINGLELINE,
+            DT_VCENTER, HBRUSH, HDC, HFONT, HGDIOBJ, PAINTSTRUCT, TRANSPARENT,
+        },
+        System::LibraryLoader::GetModuleHandleW,
+        UI::WindowsAndMessaging::*,
+    },
+};
+
+use crate::{
+    clock::{Clock, SystemClock},
+    error::LogFailure,
+    i18n::tr,
+    log,
+    named::NamedTimer,
+    overlay::create_font,
+    timer::State,
+};
+
+const STACKED_CLASS_NAME: PCWSTR = w!("rxcle.tinitime.stacked");
+/// Sent to the owner with one of the `STACKED_*` events in `wparam` and the
+/// stacked window's handle in `lparam`.
+pub const WM_STACKED: u32 = WM_APP + 2;
+pub const STACKED_CHANGED: usize = 0;
+pub const STACKED_FINISHED: usize = 1;
+pub const STACKED_REMOVE: usize = 2;
+
+pub const STACKED_WIDTH: i32 = 150;
+pub const STACKED_HEIGHT: i32 = 22;
+const STACKED_ALPHA: u8 = 230;
+const IDT_STACKED: usize = 1;
+
+const IDM_TOGGLE: usize = 1;
+const IDM_RESET: usize = 2;
+const IDM_REMOVE: usize = 3;
+
+static REGISTER_CLASS: Once = Once::new();
+
+/// A small window below the main one showing a named timer. Double-click
+/// starts or pauses it; right-click opens its menu.
+pub struct StackedWindow {
+    owner: HWND,
+    handle: HWND,
+    clock: SystemClock,
+    named: NamedTimer,
+    shown_secs: u64,
+    /// Whether `IDT_STACKED` is set.
+    ticking: bool,
+    font: HFONT,
+    running_brush: HBRUSH,
+    stopped_brush: HBRUSH,
+    finished_brush: HBRUSH,
+}
+
+impl StackedWindow {
+    pub fn new(owner: HWND, named: NamedTimer, clock: SystemClock) -> Result<Box<Self>> {
+        unsafe {
+            let instance = GetModuleHandleW(None)?;
+            REGISTER_CLASS.call_once(|| {
+                let wc = WNDCLASSW {
+                    hCursor: LoadCursorW(None, IDC_ARROW).ok().unwrap(),
+                    hInstance: instance.into(),
+                    lpszClassName: STACKED_CLASS_NAME,
+                    style: CS_HREDRAW | CS_VREDRAW | CS_DBLCLKS,
+                    lpfnWndProc: Some(Self::wnd_proc),
+                    ..Default::default()
+                };
+                let atom = RegisterClassW(&wc);
+                debug_assert!(atom != 0);
+            });
+
+            let mut window = Box::new(Self {
+                owner,
+                handle: HWND::default(),
+                clock,
+                named,
+                shown_secs: 0,
+                ticking: false,
+                font: create_font(15, 600, w!("Segoe UI")),
+                running_brush: CreateSolidBrush(COLORREF(0x00FFFFFF)),
+                stopped_brush: CreateSolidBrush(COLORREF(0x002B31D7)),
+                finished_brush: CreateSolidBrush(COLORREF(0x0050A028)),
+            });
+            window.handle = CreateWindowExW(
+                WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_LAYERED | WS_EX_NOACTIVATE,
+                STACKED_CLASS_NAME,
+                &HSTRING::from(window.named.name.as_str()),
+                WS_POPUP,
+                0,
+                0,
+                STACKED_WIDTH,
+                STACKED_HEIGHT,
+                Some(owner),
+                None,
+                Some(instance.into()),
+                Some(window.as_mut() as *mut _ as _),
+            )?;
+            SetLayeredWindowAttributes(
+                window.handle,
+                COLORREF::default(),
+                STACKED_ALPHA,
+                LWA_ALPHA,
+            )?;
+            window.update_ticking();
+            window.refresh();
+            Ok(window)
+        }
+    }
+
+    pub fn handle(&self) -> HWND {
+        self.handle
+    }
+
+    pub fn named(&self) -> &NamedTimer {
+        &self.named
+    }
+
+    pub unsafe fn move_to(&self, x: i32, y: i32) {
+        SetWindowPos(
+            self.handle,
+            Some(HWND_TOPMOST),
+            x,
+            y,
+            0,
+            0,
+            SWP_NOSIZE | SWP_NOACTIVATE | SWP_SHOWWINDOW,
+        )
+        .log_failure("SetWindowPos");
+    }
+
+    /// Starts, pauses or resumes the timer; a finished one starts over.
+    pub unsafe fn toggle(&mut self) {
+        let now = self.clock.now();
+        match self.named.timer.state() {
+            State::Stopped => {
+                self.named.timer.stop();
+                self.named.timer.start(now);
+            }
+            State::Running => self.named.timer.pause(now, now),
+            State::Paused => self.named.timer.resume(now),
+        }
+        self.changed();
+    }
+
+    pub unsafe fn restart(&mut self, duration: Duration) {
+        self.named.timer.stop();
+        self.named.timer.set_duration(duration);
+        self.named.timer.start(self.clock.now());
+        self.changed();
+    }
+
+    pub unsafe fn reset(&mut self) {
+        self.named.timer.stop();
+        self.changed();
+    }
+
+    unsafe fn changed(&mut self) {
+        self.update_ticking();
+        self.refresh();
+        PostMessageW(
+            Some(self.owner),
+            WM_STACKED,
+            WPARAM(STACKED_CHANGED),
+            LPARAM(self.handle.0 as isize),
+        )
+        .log_failure("PostMessageW");
+    }
+
+    unsafe fn update_ticking(&mut self) {
+        let running = self.named.timer.state() == State::Running;
+        if running && !self.ticking {
+            self.ticking = SetTimer(Some(self.handle), IDT_STACKED, 200, None) != 0;
+            if !self.ticking {
+                log::warning!("timer {}: {}", IDT_STACKED, Error::from_win32());
+            }
+        } else if !running && self.ticking {
+            self.ticking = false;
+            KillTimer(Some(self.handle), IDT_STACKED).log_failure("KillTimer");
+        }
+    }
+
+    unsafe fn update_timer(&mut self) {
+        if self.named.timer.tick(self.clock.now()) {
+            self.update_ticking();
+            PostMessageW(
+                Some(self.owner),
+                WM_STACKED,
+                WPARAM(STACKED_FINISHED),
+                LPARAM(self.handle.0 as isize),
+            )
+            .log_failure("PostMessageW");
+        }
+        if self.remaining_secs() != self.shown_secs {
+            self.refresh();
+        }
+    }
+
+    fn remaining_secs(&self) -> u64 {
+        let remaining = self.named.timer.remaining(self.clock.now());
+        remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0)
+    }
+
+    unsafe fn refresh(&mut self) {
+        self.shown_secs = self.remaining_secs();
+        InvalidateRect(Some(self.handle), None, false).log_failure("InvalidateRect");
+    }
+
+    unsafe fn paint(&self, ps: &PAINTSTRUCT, hdc: HDC) {
+        let (bg, fg) = if self.named.is_finished(self.clock.now()) {
+            (self.finished_brush, COLORREF(0x00FFFFFF))
+        } else if self.named.timer.state() == State::Running {
+            (self.running_brush, COLORREF(0x00000000))
+        } else {
+            (self.stopped_brush, COLORREF(0x00FFFFFF))
+        };
+        FillRect(hdc, &ps.rcPaint, bg);
+        SelectObject(hdc, HGDIOBJ::from(self.font));
+        SetTextColor(hdc, fg);
+        SetBkMode(hdc, TRANSPARENT);
+
+        let secs = self.shown_secs;
+        let time = if secs >= 3600 {
+            format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+        } else {
+            format!("{}:{:02}", secs / 60, secs % 60)
+        };
+        let mut rect = RECT::default();
+        GetClientRect(self.handle, &mut rect).log_failure("GetClientRect");
+        rect.left += 6;
+        rect.right -= 6;
+        let mut rtime = rect;
+        rtime.right = rtime.left + 52;
+        let mut time: Vec<u16> = time.encode_utf16().collect();
+        DrawTextW(hdc, &mut time, &mut rtime, DT_SINGLELINE | DT_VCENTER);
+
+        let mut rname = rect;
+        rname.left = rtime.right;
+        let mut name: Vec<u16> = self.named.name.encode_utf16().collect();
+        DrawTextW(
+            hdc,
+            &mut name,
+            &mut rname,
+            DT_SINGLELINE | DT_VCENTER | DT_END_ELLIPSIS,
+        );
+    }
+
+    unsafe fn show_menu(&mut self) {
+        let Ok(menu) = CreatePopupMenu() else {
+            return;
+        };
+        let toggle = match self.named.timer.state() {
+            State::Running => tr!("timer-pause"),
+            State::Paused => tr!("timer-resume"),
+            State::Stopped => tr!("timer-start"),
+        };
+        for (flags, id, text) in [
+            (MF_STRING, IDM_TOGGLE, Some(toggle)),
+            (MF_STRING, IDM_RESET, Some(tr!("timer-reset"))),
+            (MF_SEPARATOR, 0, None),
+            (MF_STRING, IDM_REMOVE, Some(tr!("timer-remove"))),
+        ] {
+            let text = text.map(HSTRING::from);
+            let text = text
+                .as_ref()
+                .map_or(PCWSTR::null(), |text| PCWSTR(text.as_ptr()));
+            AppendMenuW(menu, flags, id, text).log_failure("AppendMenuW");
+        }
+
+        let mut cursor = POINT::default();
+        GetCursorPos(&mut cursor).log_failure("GetCursorPos");
+        // Only whether the owner came to the front, which the menu needs
+        // to close when clicked away from.
+        let _ = SetForegroundWindow(self.owner);
+        let command = TrackPopupMenu(
+            menu,
+            TPM_RETURNCMD | TPM_RIGHTBUTTON,
+            cursor.x,
+            cursor.y,
+            None,
+            self.owner,
+            None,
+        );
+        DestroyMenu(menu).log_failure("DestroyMenu");
+
+        match command.0 as usize {
+            IDM_TOGGLE => self.toggle(),
+            IDM_RESET => self.reset(),
+            IDM_REMOVE => {
+                PostMessageW(
+                    Some(self.owner),
+                    WM_STACKED,
+                    WPARAM(STACKED_REMOVE),
+                    LPARAM(self.handle.0 as isize),
+                )
+                .log_failure("PostMessageW");
+            }
+            _ => {}
+        }
+    }
+
+    unsafe fn message_handler(&mut self, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        match message {
+            WM_TIMER => {
+                self.update_timer();
+                LRESULT(0)
+            }
+            WM_PAINT => {
+                let mut ps = PAINTSTRUCT::default();
+                let hdc = BeginPaint(self.handle, &mut ps);
+                self.paint(&ps, hdc);
+                // Always succeeds.
+                let _ = EndPaint(self.handle, &ps);
+                LRESULT(0)
+            }
+            WM_ERASEBKGND => LRESULT(1),
+            WM_LBUTTONDBLCLK => {
+                self.toggle();
+                LRESULT(0)
+            }
+            WM_RBUTTONUP => {
+                self.show_menu();
+                LRESULT(0)
+            }
+            WM_MOUSEACTIVATE => LRESULT(MA_NOACTIVATE as isize),
+            _ => DefWindowProcW(self.handle, message, wparam, lparam),
+        }
+    }
+
+    unsafe extern "system" fn wnd_proc(
+        window: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        if message == WM_NCCREATE {
+            let cs = lparam.0 as *const CREATESTRUCTW;
+            let this = (*cs).lpCreateParams as *mut Self;
+            (*this).handle = window;
+            SetWindowLongPtrW(window, GWLP_USERDATA, this as _);
+        } else {
+            let this = GetWindowLongPtrW(window, GWLP_USERDATA) as *mut Self;
+            if let Some(this) = this.as_mut() {
+                return this.message_handler(message, wparam, lparam);
+            }
+        }
+        DefWindowProcW(window, message, wparam, lparam)
+    }
+}
+
+impl Drop for StackedWindow {
+    fn drop(&mut self) {
+        unsafe {
+            SetWindowLongPtrW(self.handle, GWLP_USERDATA, 0);
+            DestroyWindow(self.handle).log_failure("DestroyWindow");
+            for object in [
+                HGDIOBJ::from(self.font),
+                HGDIOBJ::from(self.running_brush),
+                HGDIOBJ::from(self.stopped_brush),
+                HGDIOBJ::from(self.finished_brush),
+            ] {
+                DeleteObject(object).log_failure("DeleteObject");
+            }
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::{cmp::Reverse, collections::BTreeMap, time::Duration};
+
+use crate::{
+    estimate,
+    helpers::unix_secs,
+    history::Session,
+    i18n::{self, tr},
+    localtime::{DateTime, Zone},
+};
+
+const MAX_NOTES: usize = 10;
+const MAX_TASKS: usize = 10;
+
+#[derive(Default)]
+struct Total {
+    time: Duration,
+    extended: Duration,
+    sessions: usize,
+}
+
+impl Total {
+    fn add(&mut self, session: &Session) {
+        self.time += session.elapsed;
+        self.extended += session.extended;
+        self.sessions += 1;
+    }
+}
+
+/// Labels of the form `project/task` are also summed up per project. The
+/// latest notes are listed with their local date, and estimated tasks with
+/// how accurate the estimates were per month.
+pub fn report(sessions: &[Session], zone: &impl Zone) -> String {
+    let mut total = Total::default();
+    let mut tasks: BTreeMap<&str, Total> = BTreeMap::new();
+    let mut projects: BTreeMap<&str, Total> = BTreeMap::new();
+    let mut apps: BTreeMap<&str, Duration> = BTreeMap::new();
+    let mut notes = vec![];
+    let mut interruptions = (0u32, 0usize);
+    let no_label = tr!("stats-no-label");
+    for session in sessions {
+        let label = session.label.as_deref().unwrap_or(&no_label);
+        total.add(session);
+        tasks.entry(label).or_default().add(session);
+        if let Some((project, _)) = label.split_once('/') {
+            projects.entry(project.trim()).or_default().add(session);
+        }
+        for (app, time) in &session.apps {
+            *apps.entry(app).or_default() += *time;
+        }
+        if let Some(note) = &session.note {
+            notes.push((session, label, note));
+        }
+        if let Some(count) = session.interruptions {
+            interruptions.0 += count;
+            interruptions.1 += 1;
+        }
+    }
+
+    let mut report = format!("{}\n", tr!("stats-tasks"));
+    report.push_str(&format_totals(&tasks));
+    if !projects.is_empty() {
+        report.push_str(&format!("{}\n", tr!("stats-projects")));
+        report.push_str(&format_totals(&projects));
+    }
+    if !apps.is_empty() {
+        let mut apps: Vec<_> = apps.into_iter().collect();
+        apps.sort_by_key(|(_, time)| Reverse(*time));
+        report.push_str(&format!("{}\n", tr!("stats-apps")));
+        for (app, time) in apps {
+            report.push_str(&format!("  {:<30} {:>8}\n", app, format_duration(time)));
+        }
+    }
+    if !notes.is_empty() {
+        report.push_str(&format!("{}\n", tr!("stats-notes")));
+        for (session, label, note) in notes.iter().rev().take(MAX_NOTES).rev() {
+            let date = DateTime::from_secs(zone.to_local(unix_secs(session.start)));
+            report.push_str(&format!(
+                "  {:04}-{:02}-{:02} {}: {}\n",
+                date.year, date.month, date.day, label, note
+            ));
+        }
+    }
+    let tasks = estimate::tasks(sessions);
+    if !tasks.is_empty() {
+        report.push_str(&format!("{}\n", tr!("stats-estimates")));
+        for task in tasks.iter().rev().take(MAX_TASKS).rev() {
+            report.push_str(&format!("  {:<30} {:>8}\n", task.label, task.progress()));
+        }
+        for month in estimate::accuracy_by_month(&tasks, zone) {
+            let text = tr!(
+                "stats-accuracy",
+                accuracy = (month.accuracy * 100.0).round() as u64,
+                tasks = month.tasks,
+                over = month.over,
+            );
+            report.push_str(&format!(
+                "  {:04}-{:02} {}\n",
+                month.year, month.month, text
+            ));
+        }
+    }
+    let text = tr!(
+        "stats-total",
+        time = format_duration(total.time),
+        count = total.sessions,
+        overrun = format_duration(total.extended),
+    );
+    report.push_str(&format!("{}\n", text));
+    let (count, reflected) = interruptions;
+    if reflected > 0 {
+        let text = tr!("stats-interruptions", count = count, sessions = reflected);
+        report.push_str(&format!("{}\n", text));
+    }
+    report
+}
+
+fn format_totals(totals: &BTreeMap<&str, Total>) -> String {
+    let locale = i18n::locale();
+    totals
+        .iter()
+        .map(|(name, total)| {
+            format!(
+                "  {:<30} {:>8} {:>5} {:>8}\n",
+                name,
+                format_duration(total.time),
+                locale.format_number(total.sessions as u64),
+                format_duration(total.extended)
+            )
+        })
+        .collect()
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+    use crate::localtime::FixedZone;
+
+    const TOKYO: FixedZone = FixedZone(9 * 3600);
+
+    /// A session started at `hour`:00 UTC on `day` March 2024.
+    fn session(day: u32, hour: u32, minutes: u64, label: Option<&str>) -> Session {
+        let start = DateTime {
+            year: 2024,
+            month: 3,
+            day,
+            hour,
+            minute: 0,
+            second: 0,
+        };
+        let start = UNIX_EPOCH + Duration::from_secs(start.to_secs() as u64);
+        let mut session = Session::new(start, Duration::from_secs(minutes * 60));
+        session.elapsed = Duration::from_secs(minutes * 60);
+        session.label = label.map(str::to_string);
+        session
+    }
+
+    #[test]
+    fn sums_up_tasks_and_projects_by_local_day() {
+        let mut docs = session(4, 10, 25, Some("tinitime/docs"));
+        docs.extended = Duration::from_secs(5 * 60);
+        docs.note = Some("outline".to_string());
+        // 20:00 UTC is already the next morning in Tokyo.
+        let mut code = session(4, 20, 50, Some("tinitime/code"));
+        code.note = Some("parser".to_string());
+        let sessions = [
+            docs,
+            code,
+            session(5, 8, 10, None),
+            session(5, 9, 25, Some("tinitime/docs")),
+        ];
+        let expected = "\
+Time per task
+  (no label)                         0:10     1     0:00
+  tinitime/code                      0:50     1     0:00
+  tinitime/docs                      0:50     2     0:05
+Time per project
+  tinitime                           1:40     3     0:05
+Notes
+  2024-03-04 tinitime/docs: outline
+  2024-03-05 tinitime/code: parser
+Total 1:50 in 4 sessions, 0:05 overrun
+";
+        assert_eq!(report(&sessions, &TOKYO), expected);
+    }
+}
@@ -0,0 +1,135 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    api::Status,
+    config::Config,
+    helpers::{expand_template, format_clock},
+    log,
+};
+
+const DEF_TEMPLATE: &str = "{remaining}";
+
+/// Mirrors the timer into files that streaming software can show, such as
+/// an OBS text source. A file is only rewritten when its content changes.
+pub struct TextFiles {
+    text_file: Option<PathBuf>,
+    template: String,
+    json_file: Option<PathBuf>,
+    last_text: Option<String>,
+    last_json: Option<String>,
+}
+
+impl TextFiles {
+    pub fn from_config(config: &Config) -> Option<Self> {
+        if config.text_file.is_none() && config.json_file.is_none() {
+            return None;
+        }
+        let template = config.text_template.as_deref().unwrap_or(DEF_TEMPLATE);
+        Some(Self {
+            text_file: config.text_file.clone(),
+            template: template.to_string(),
+            json_file: config.json_file.clone(),
+            last_text: None,
+            last_json: None,
+        })
+    }
+
+    pub fn update(&mut self, status: &Status) {
+        if let Some(path) = &self.text_file {
+            let text = render(&self.template, status);
+            write_if_changed(path, text, &mut self.last_text);
+        }
+        if let Some(path) = &self.json_file {
+            write_if_changed(path, status.to_json(), &mut self.last_json);
+        }
+    }
+}
+
+/// Expands `{remaining}`, `{duration}` (as `M:SS`, or `H:MM:SS` from an hour
+/// up), `{label}`, `{phase}` and `{state}`. `\n` starts a new line.
+fn render(template: &str, status: &Status) -> String {
+    let text = expand_template(template, |name| match name {
+        "remaining" => Some(format_clock(status.remaining)),
+        "duration" => Some(format_clock(status.duration)),
+        "label" => Some(status.label.clone().unwrap_or_default()),
+        "phase" => Some(status.phase.to_string()),
+        "state" => Some(status.state.to_string()),
+        _ => None,
+    });
+    text.replace("\\n", "\n")
+}
+
+fn write_if_changed(path: &Path, content: String, last: &mut Option<String>) {
+    if last.as_ref() == Some(&content) {
+        return;
+    }
+    match write_atomic(path, &content) {
+        Ok(()) => *last = Some(content),
+        Err(error) => log::warning!("{}: {}", path.display(), error),
+    }
+}
+
+/// Writes next to the target and renames over it, so readers never see a
+/// half-written or empty file.
+fn write_atomic(path: &Path, content: &str) -> io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    fs::write(&temp, content)?;
+    fs::rename(&temp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    fn status() -> Status {
+        Status {
+            state: "running",
+            phase: "focus",
+            remaining: 3725,
+            duration: 1500,
+            label: Some("review".to_string()),
+        }
+    }
+
+    #[test]
+    fn renders_placeholders_and_new_lines() {
+        let text = render(
+            "{label} ({phase}, {state})\\n{remaining} of {duration} {other}",
+            &status(),
+        );
+        assert_eq!(text, "review (focus, running)\n1:02:05 of 25:00 {other}");
+    }
+
+    #[test]
+    fn writes_only_changed_content() {
+        let dir = env::temp_dir().join(format!("tinitime-textfile-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("timer.txt");
+        fs::write(&path, "old").unwrap();
+        let mut last = None;
+
+        write_if_changed(&path, "25:00".to_string(), &mut last);
+        let replaced = fs::read_to_string(&path).unwrap();
+        fs::write(&path, "edited").unwrap();
+        write_if_changed(&path, "25:00".to_string(), &mut last);
+        let unchanged = fs::read_to_string(&path).unwrap();
+        write_if_changed(&path, "24:59".to_string(), &mut last);
+        let changed = fs::read_to_string(&path).unwrap();
+        let mut temp = path.clone().into_os_string();
+        temp.push(".tmp");
+        let temp_left = Path::new(&temp).exists();
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(replaced, "25:00");
+        assert_eq!(unchanged, "edited");
+        assert_eq!(changed, "24:59");
+        assert_eq!(last.as_deref(), Some("24:59"));
+        assert!(!temp_left);
+    }
+}
@@ -0,0 +1,502 @@
+use std::time::{Duration, SystemTime};
+
+pub const DEF_TIME: Duration = Duration::from_secs(1500);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum State {
+    Stopped,
+    Running,
+    Paused,
+}
+
+impl State {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            State::Stopped => "stopped",
+            State::Running => "running",
+            State::Paused => "paused",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "stopped" => Some(State::Stopped),
+            "running" => Some(State::Running),
+            "paused" => Some(State::Paused),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SuspendPolicy {
+    /// Keep counting by wall clock; the timer may finish while suspended.
+    #[default]
+    Continue,
+    Pause,
+    Abort,
+}
+
+impl SuspendPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "continue" => Some(SuspendPolicy::Continue),
+            "pause" => Some(SuspendPolicy::Pause),
+            "abort" => Some(SuspendPolicy::Abort),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SuspendReason {
+    Sleep,
+    Lock,
+}
+
+#[derive(Clone)]
+struct Suspension {
+    reason: SuspendReason,
+    policy: SuspendPolicy,
+    at: Duration,
+    wall: SystemTime,
+    paused: bool,
+}
+
+#[derive(Clone)]
+pub struct Timer {
+    duration: Duration,
+    state: State,
+    deadline: Duration,
+    remaining: Duration,
+    /// At most one per reason, e.g. a lock followed by sleep.
+    suspensions: Vec<Suspension>,
+}
+
+impl Timer {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            state: State::Stopped,
+            deadline: Duration::ZERO,
+            remaining: duration,
+            suspensions: vec![],
+        }
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.state == State::Stopped
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn set_duration(&mut self, duration: Duration) {
+        self.duration = duration;
+        if self.is_stopped() {
+            self.remaining = duration;
+        }
+    }
+
+    pub fn remaining(&self, now: Duration) -> Duration {
+        match self.state {
+            State::Running => self.deadline.saturating_sub(now),
+            State::Stopped | State::Paused => self.remaining,
+        }
+    }
+
+    pub fn elapsed(&self, now: Duration) -> Duration {
+        self.duration.saturating_sub(self.remaining(now))
+    }
+
+    /// Adds `by` to the session; a timer that just finished starts running
+    /// again for the extra time.
+    pub fn extend(&mut self, now: Duration, by: Duration) {
+        self.duration += by;
+        match self.state {
+            State::Running => self.deadline += by,
+            State::Paused => self.remaining += by,
+            State::Stopped => {
+                self.state = State::Running;
+                self.deadline = now + self.remaining + by;
+            }
+        }
+    }
+
+    /// Takes `by` off the session, at most what is left of it. Only the
+    /// terminal front-end offers this.
+    #[cfg_attr(windows, allow(dead_code))]
+    pub fn shorten(&mut self, now: Duration, by: Duration) {
+        let by = by.min(self.remaining(now));
+        self.duration -= by;
+        match self.state {
+            State::Running => self.deadline -= by,
+            State::Paused => self.remaining -= by,
+            State::Stopped => {}
+        }
+    }
+
+    /// Puts the timer back into a saved state, e.g. after a restart.
+    pub fn restore(&mut self, now: Duration, state: State, remaining: Duration) {
+        self.state = state;
+        self.remaining = remaining.min(self.duration);
+        self.deadline = now + self.remaining;
+        self.suspensions.clear();
+    }
+
+    pub fn start(&mut self, now: Duration) {
+        self.state = State::Running;
+        self.deadline = now + self.duration;
+    }
+
+    pub fn stop(&mut self) {
+        self.state = State::Stopped;
+        self.remaining = self.duration;
+        self.suspensions.clear();
+    }
+
+    /// Pauses the timer as of `at`, which may lie in the past (e.g. when the
+    /// user turned out to be away), but never before the time already spent.
+    pub fn pause(&mut self, now: Duration, at: Duration) {
+        if self.state == State::Running {
+            let remaining = self.remaining(now);
+            let rewind = now.saturating_sub(at).min(self.duration - remaining);
+            self.remaining = remaining + rewind;
+            self.state = State::Paused;
+        }
+    }
+
+    pub fn resume(&mut self, now: Duration) {
+        if self.state == State::Paused {
+            self.state = State::Running;
+            self.deadline = now + self.remaining;
+        }
+    }
+
+    /// Returns `true` once, when a running timer reaches zero.
+    pub fn tick(&mut self, now: Duration) -> bool {
+        if self.state == State::Running && now >= self.deadline {
+            self.state = State::Stopped;
+            self.remaining = Duration::ZERO;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Applies `policy` when the system goes to sleep or the session gets
+    /// locked. Returns `true` if the session should be aborted. Suspensions
+    /// may overlap, as when a locked machine goes to sleep; the stricter
+    /// policy then wins.
+    pub fn suspend(
+        &mut self,
+        reason: SuspendReason,
+        policy: SuspendPolicy,
+        now: Duration,
+        wall: SystemTime,
+    ) -> bool {
+        let suspended = self
+            .suspensions
+            .iter()
+            .any(|suspension| suspension.reason == reason);
+        if self.is_stopped() || suspended {
+            return false;
+        }
+        if policy == SuspendPolicy::Abort {
+            return true;
+        }
+        let paused = policy == SuspendPolicy::Pause && self.state == State::Running;
+        if paused {
+            self.pause(now, now);
+        }
+        self.suspensions.push(Suspension {
+            reason,
+            policy,
+            at: now,
+            wall,
+            paused,
+        });
+        false
+    }
+
+    /// Undoes a suspension with the same `reason`. Under the continue policy
+    /// any wall-clock time the monotonic clock did not see (e.g. while the
+    /// machine slept) is taken off the deadline.
+    pub fn wake(&mut self, reason: SuspendReason, now: Duration, wall: SystemTime) {
+        let Some(index) = self
+            .suspensions
+            .iter()
+            .position(|suspension| suspension.reason == reason)
+        else {
+            return;
+        };
+        let suspension = self.suspensions.remove(index);
+        match suspension.policy {
+            SuspendPolicy::Continue if self.state == State::Running => {
+                let slept = wall.duration_since(suspension.wall).unwrap_or_default();
+                let missed = slept.saturating_sub(now.saturating_sub(suspension.at));
+                self.deadline = self.deadline.saturating_sub(missed);
+            }
+            SuspendPolicy::Pause if suspension.paused => {
+                self.resume(now);
+                // The time spent paused was not missed by the others.
+                for other in &mut self.suspensions {
+                    other.at = now;
+                    other.wall = wall;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    fn wall(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    fn running(duration: u64) -> Timer {
+        let mut timer = Timer::new(secs(duration));
+        timer.start(secs(0));
+        timer
+    }
+
+    #[test]
+    fn runs_down_and_finishes_once() {
+        let mut timer = running(60);
+        assert_eq!(timer.remaining(secs(20)), secs(40));
+        assert!(!timer.tick(secs(59)));
+        assert!(timer.tick(secs(60)));
+        assert!(!timer.tick(secs(61)));
+        assert_eq!(timer.state(), State::Stopped);
+        assert_eq!(timer.elapsed(secs(61)), secs(60));
+    }
+
+    #[test]
+    fn pause_and_resume() {
+        let mut timer = running(60);
+        timer.pause(secs(10), secs(10));
+        assert_eq!(timer.state(), State::Paused);
+        assert_eq!(timer.remaining(secs(100)), secs(50));
+        timer.resume(secs(100));
+        assert_eq!(timer.remaining(secs(110)), secs(40));
+        assert!(timer.tick(secs(150)));
+    }
+
+    #[test]
+    fn pause_in_the_past_is_limited_to_time_spent() {
+        let mut timer = running(60);
+        timer.pause(secs(10), secs(4));
+        assert_eq!(timer.remaining(secs(10)), secs(56));
+
+        let mut timer = running(60);
+        timer.pause(secs(10), secs(0));
+        timer.resume(secs(10));
+        timer.pause(secs(15), Duration::ZERO);
+        assert_eq!(timer.remaining(secs(15)), secs(60));
+    }
+
+    #[test]
+    fn extend_while_running_paused_and_finished() {
+        let mut timer = running(60);
+        timer.extend(secs(10), secs(30));
+        assert_eq!(timer.duration(), secs(90));
+        assert_eq!(timer.remaining(secs(10)), secs(80));
+
+        timer.pause(secs(20), secs(20));
+        timer.extend(secs(20), secs(10));
+        assert_eq!(timer.remaining(secs(20)), secs(80));
+
+        let mut timer = running(60);
+        assert!(timer.tick(secs(60)));
+        timer.extend(secs(65), secs(30));
+        assert_eq!(timer.state(), State::Running);
+        assert_eq!(timer.remaining(secs(65)), secs(30));
+        assert_eq!(timer.elapsed(secs(65)), secs(60));
+    }
+
+    #[test]
+    fn shorten_stops_at_what_is_left() {
+        let mut timer = running(60);
+        timer.shorten(secs(10), secs(20));
+        assert_eq!(timer.duration(), secs(40));
+        assert_eq!(timer.remaining(secs(10)), secs(30));
+        timer.shorten(secs(10), secs(600));
+        assert_eq!(timer.remaining(secs(10)), Duration::ZERO);
+        assert!(timer.tick(secs(10)));
+
+        let mut timer = running(60);
+        timer.pause(secs(10), secs(10));
+        timer.shorten(secs(10), secs(20));
+        assert_eq!(timer.remaining(secs(10)), secs(30));
+    }
+
+    #[test]
+    fn restore_keeps_state_and_caps_remaining() {
+        let mut timer = Timer::new(secs(60));
+        timer.restore(secs(100), State::Running, secs(20));
+        assert_eq!(timer.state(), State::Running);
+        assert_eq!(timer.remaining(secs(110)), secs(10));
+
+        timer.restore(secs(100), State::Paused, secs(600));
+        assert_eq!(timer.remaining(secs(500)), secs(60));
+    }
+
+    #[test]
+    fn sleep_under_continue_counts_missed_wall_time() {
+        let mut timer = running(600);
+        let aborted = timer.suspend(
+            SuspendReason::Sleep,
+            SuspendPolicy::Continue,
+            secs(100),
+            wall(1000),
+        );
+        assert!(!aborted);
+        // The monotonic clock saw 10 s of a 200 s sleep.
+        timer.wake(SuspendReason::Sleep, secs(110), wall(1200));
+        assert_eq!(timer.remaining(secs(110)), secs(300));
+    }
+
+    #[test]
+    fn sleep_under_continue_may_finish_the_timer() {
+        let mut timer = running(60);
+        timer.suspend(
+            SuspendReason::Sleep,
+            SuspendPolicy::Continue,
+            secs(10),
+            wall(0),
+        );
+        timer.wake(SuspendReason::Sleep, secs(11), wall(3600));
+        assert!(timer.tick(secs(11)));
+    }
+
+    #[test]
+    fn lock_under_pause_pauses_until_unlocked() {
+        let mut timer = running(600);
+        let aborted = timer.suspend(
+            SuspendReason::Lock,
+            SuspendPolicy::Pause,
+            secs(100),
+            wall(100),
+        );
+        assert!(!aborted);
+        assert_eq!(timer.state(), State::Paused);
+        // Waking for another reason leaves the timer alone.
+        timer.wake(SuspendReason::Sleep, secs(200), wall(200));
+        assert_eq!(timer.state(), State::Paused);
+        timer.wake(SuspendReason::Lock, secs(400), wall(400));
+        assert_eq!(timer.state(), State::Running);
+        assert_eq!(timer.remaining(secs(400)), secs(500));
+    }
+
+    #[test]
+    fn pause_policy_keeps_a_paused_timer_paused() {
+        let mut timer = running(600);
+        timer.pause(secs(50), secs(50));
+        timer.suspend(
+            SuspendReason::Lock,
+            SuspendPolicy::Pause,
+            secs(100),
+            wall(100),
+        );
+        timer.wake(SuspendReason::Lock, secs(400), wall(400));
+        assert_eq!(timer.state(), State::Paused);
+        assert_eq!(timer.remaining(secs(400)), secs(550));
+    }
+
+    #[test]
+    fn abort_policy_asks_to_abort() {
+        let mut timer = running(600);
+        assert!(timer.suspend(
+            SuspendReason::Lock,
+            SuspendPolicy::Abort,
+            secs(100),
+            wall(100)
+        ));
+        assert!(!Timer::new(secs(600)).suspend(
+            SuspendReason::Lock,
+            SuspendPolicy::Abort,
+            secs(100),
+            wall(100)
+        ));
+    }
+
+    #[test]
+    fn sleep_while_locked_pauses_under_pause_policy() {
+        let mut timer = running(600);
+        timer.suspend(
+            SuspendReason::Lock,
+            SuspendPolicy::Continue,
+            secs(100),
+            wall(100),
+        );
+        let aborted = timer.suspend(
+            SuspendReason::Sleep,
+            SuspendPolicy::Pause,
+            secs(150),
+            wall(150),
+        );
+        assert!(!aborted);
+        assert_eq!(timer.state(), State::Paused);
+        // The monotonic clock saw 10 s of a 1000 s sleep.
+        timer.wake(SuspendReason::Sleep, secs(160), wall(1160));
+        assert_eq!(timer.state(), State::Running);
+        assert_eq!(timer.remaining(secs(160)), secs(450));
+        // Unlocking does not count the sleep again.
+        timer.wake(SuspendReason::Lock, secs(170), wall(1170));
+        assert_eq!(timer.remaining(secs(170)), secs(440));
+    }
+
+    #[test]
+    fn sleep_while_locked_aborts_under_abort_policy() {
+        let mut timer = running(600);
+        timer.suspend(
+            SuspendReason::Lock,
+            SuspendPolicy::Continue,
+            secs(100),
+            wall(100),
+        );
+        assert!(timer.suspend(
+            SuspendReason::Sleep,
+            SuspendPolicy::Abort,
+            secs(150),
+            wall(150)
+        ));
+    }
+
+    #[test]
+    fn sleep_while_locked_keeps_the_stricter_lock_policy() {
+        let mut timer = running(600);
+        timer.suspend(
+            SuspendReason::Lock,
+            SuspendPolicy::Pause,
+            secs(100),
+            wall(100),
+        );
+        timer.suspend(
+            SuspendReason::Sleep,
+            SuspendPolicy::Continue,
+            secs(150),
+            wall(150),
+        );
+        timer.wake(SuspendReason::Sleep, secs(160), wall(1160));
+        assert_eq!(timer.state(), State::Paused);
+        timer.wake(SuspendReason::Lock, secs(200), wall(1200));
+        assert_eq!(timer.state(), State::Running);
+        assert_eq!(timer.remaining(secs(200)), secs(500));
+    }
+}
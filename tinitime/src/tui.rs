@@ -0,0 +1,255 @@
+use std::{
+    io::{self, Read, Write},
+    process::{self, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    clock::SystemClock,
+    config::Config,
+    controller::{Controller, Notice},
+    ctl::Command,
+    helpers::{ceil_secs, format_clock},
+    history::History,
+    i18n::tr,
+    localtime::SystemZone,
+    pomodoro::Phase,
+    room::Room,
+    timer::State,
+};
+
+const TICK: Duration = Duration::from_millis(200);
+const STEP: Duration = Duration::from_secs(60);
+const CTRL_C: u8 = 3;
+
+/// Digits and the colon, five rows each; `#` marks a lit cell.
+const GLYPHS: [[&str; 5]; 11] = [
+    ["###", "# #", "# #", "# #", "###"],
+    ["  #", "  #", "  #", "  #", "  #"],
+    ["###", "  #", "###", "#  ", "###"],
+    ["###", "  #", "###", "  #", "###"],
+    ["# #", "# #", "###", "  #", "  #"],
+    ["###", "#  ", "###", "  #", "###"],
+    ["###", "#  ", "###", "# #", "###"],
+    ["###", "  #", "  #", "  #", "  #"],
+    ["###", "# #", "###", "# #", "###"],
+    ["###", "# #", "###", "  #", "###"],
+    [" ", "#", " ", "#", " "],
+];
+
+/// Runs the timer in the terminal until `q` is pressed or input ends. Fails
+/// for options that need the window.
+pub fn run(config: Config) -> Result<(), Vec<String>> {
+    if config.idle_timeout.is_some() {
+        return Err(vec![tr!("option-idle-windows-only")]);
+    }
+    let _terminal = Terminal::enter();
+    let keys = read_keys();
+    let mut app = App::new(config);
+    let mut stdout = io::stdout();
+    loop {
+        app.tick();
+        let _ = app.draw(&mut stdout);
+        match keys.recv_timeout(TICK) {
+            Ok(key) => {
+                if !app.handle_key(key) {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    app.controller.close();
+    app.controller.publish();
+    Ok(())
+}
+
+struct App {
+    controller: Controller<SystemClock, SystemZone>,
+    commands: Receiver<Command>,
+    screen: String,
+}
+
+impl App {
+    fn new(config: Config) -> Self {
+        let history = History::open_default();
+        let mut controller = Controller::new(config, SystemClock::new(), SystemZone, history);
+        let (sender, commands) = mpsc::channel();
+        controller.serve_api(move |command: Command| sender.send(command).is_ok());
+        controller.begin();
+        Self {
+            controller,
+            commands,
+            screen: String::new(),
+        }
+    }
+
+    /// Returns `false` once the user asked to quit.
+    fn handle_key(&mut self, key: u8) -> bool {
+        let controller = &mut self.controller;
+        match key {
+            b'q' | b'Q' | CTRL_C => return false,
+            _ if controller.is_following() => {}
+            b' ' if controller.timer().is_stopped() => controller.start(),
+            b' ' => controller.pause(),
+            b'r' | b'R' => controller.stop(),
+            b'+' | b'=' => controller.lengthen(STEP),
+            b'-' | b'_' => controller.shorten(STEP),
+            _ => {}
+        }
+        true
+    }
+
+    /// Takes the commands that came in through the API, then moves
+    /// everything on.
+    fn tick(&mut self) {
+        let commands: Vec<_> = self.commands.try_iter().collect();
+        for command in commands {
+            self.controller.handle_command(command);
+        }
+        self.controller.tick();
+        for notice in self.controller.notices() {
+            if matches!(notice, Notice::Finished | Notice::Reminder(_)) {
+                print!("\x07");
+            }
+        }
+    }
+
+    /// Redraws in place, and only when something changed, to keep slow
+    /// connections quiet.
+    fn draw(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let controller = &self.controller;
+        let digits = format_clock(controller.remaining_secs());
+        let (style, state) = match controller.timer().state() {
+            State::Running => ("", tr!("state-running")),
+            State::Paused => ("\x1b[2m", tr!("state-paused")),
+            State::Stopped if controller.remaining_secs() == 0 => {
+                ("\x1b[1m", tr!("state-finished"))
+            }
+            State::Stopped => ("\x1b[2m", tr!("state-stopped")),
+        };
+
+        let mut screen = String::from("\n");
+        for row in 0..5 {
+            screen.push_str("  ");
+            screen.push_str(style);
+            for c in digits.chars() {
+                let glyph = match c {
+                    ':' => &GLYPHS[10],
+                    c => &GLYPHS[c.to_digit(10).unwrap_or(0) as usize],
+                };
+                for cell in glyph[row].chars() {
+                    screen.push_str(if cell == '#' {
+                        "\u{2588}\u{2588}"
+                    } else {
+                        "  "
+                    });
+                }
+                screen.push_str("  ");
+            }
+            screen.push_str("\x1b[0m\n");
+        }
+        let label = match controller.phase() {
+            Phase::Focus => controller.task_label().unwrap_or_else(|| tr!("no-label")),
+            Phase::ShortBreak => tr!("phase-short-break"),
+            Phase::LongBreak => tr!("phase-long-break"),
+        };
+        screen.push_str(&format!("\n  {} \u{00B7} {}\n", label, state));
+        if let Some((reminder, remaining)) = controller.active_break() {
+            screen.push_str(&format!(
+                "  {} \u{00B7} {}\n",
+                reminder.name,
+                format_clock(ceil_secs(remaining))
+            ));
+        }
+        let help = match (controller.room(), &controller.config().room) {
+            (Some(Room::Follow(_)), Some(name)) => {
+                format!(
+                    "{}  {}",
+                    tr!("tooltip-following", room = name),
+                    tr!("help-quit")
+                )
+            }
+            (Some(Room::Lead(_)), Some(name)) => {
+                format!(
+                    "{}  {}",
+                    tr!("tooltip-leading", room = name),
+                    tr!("help-keys")
+                )
+            }
+            _ => tr!("help-keys"),
+        };
+        screen.push_str(&format!("\n  \x1b[2m{}\x1b[0m\n", help));
+
+        if screen == self.screen {
+            return Ok(());
+        }
+        write!(out, "\x1b[H")?;
+        for line in screen.lines() {
+            writeln!(out, "{}\x1b[K", line)?;
+        }
+        write!(out, "\x1b[J")?;
+        out.flush()?;
+        self.screen = screen;
+        Ok(())
+    }
+}
+
+/// Reads stdin byte by byte on a separate thread; the channel closes when
+/// input ends.
+fn read_keys() -> Receiver<u8> {
+    let (sender, keys) = mpsc::channel();
+    thread::spawn(move || {
+        for byte in io::stdin().lock().bytes() {
+            match byte {
+                Ok(byte) if sender.send(byte).is_ok() => {}
+                _ => return,
+            }
+        }
+    });
+    keys
+}
+
+/// Switches the terminal to unbuffered input without echo, on the alternate
+/// screen, and restores it when dropped. Signal keys are turned off as well,
+/// so Ctrl+C arrives as input and still restores the terminal.
+struct Terminal {
+    saved: Option<String>,
+}
+
+impl Terminal {
+    fn enter() -> Self {
+        let saved = stty(&["-g"]);
+        if saved.is_some() {
+            stty(&["-icanon", "-echo", "-isig", "min", "1", "time", "0"]);
+        }
+        print!("\x1b[?1049h\x1b[?25l");
+        let _ = io::stdout().flush();
+        Self { saved }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+        if let Some(saved) = &self.saved {
+            stty(&[saved]);
+        }
+    }
+}
+
+/// Runs `stty` on our terminal; there is no termios in std.
+fn stty(args: &[&str]) -> Option<String> {
+    let output = process::Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    output.status.success().then(|| stdout.trim().to_string())
+}
@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+/// How long a destructive action can be taken back.
+pub const UNDO_WINDOW: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Stop,
+    SkipBreak,
+    ChangeDuration,
+}
+
+impl Action {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Stop => "stop",
+            Action::SkipBreak => "skip",
+            Action::ChangeDuration => "change",
+        }
+    }
+}
+
+struct Entry<T> {
+    action: Action,
+    at: Duration,
+    state: T,
+}
+
+/// The states before recent destructive actions, latest last. Entries
+/// expire `UNDO_WINDOW` after the action.
+pub struct UndoStack<T> {
+    entries: Vec<Entry<T>>,
+}
+
+impl<T> UndoStack<T> {
+    pub fn new() -> Self {
+        Self { entries: vec![] }
+    }
+
+    pub fn latest(&self) -> Option<Action> {
+        self.entries.last().map(|entry| entry.action)
+    }
+
+    pub fn push(&mut self, action: Action, state: T, now: Duration) {
+        self.entries.push(Entry {
+            action,
+            at: now,
+            state,
+        });
+    }
+
+    pub fn states_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.entries.iter_mut().map(|entry| &mut entry.state)
+    }
+
+    /// Takes back the latest action that has not expired yet.
+    pub fn undo(&mut self, now: Duration) -> Option<T> {
+        let entry = self.entries.pop()?;
+        if now.saturating_sub(entry.at) < UNDO_WINDOW {
+            Some(entry.state)
+        } else {
+            self.entries.push(entry);
+            None
+        }
+    }
+
+    /// Drops expired entries and returns their states, oldest first.
+    pub fn expire(&mut self, now: Duration) -> Vec<T> {
+        let live = self
+            .entries
+            .iter()
+            .position(|entry| now.saturating_sub(entry.at) < UNDO_WINDOW)
+            .unwrap_or(self.entries.len());
+        self.entries
+            .drain(..live)
+            .map(|entry| entry.state)
+            .collect()
+    }
+
+    /// Drops every entry, returning their states oldest first.
+    pub fn clear(&mut self) -> Vec<T> {
+        self.entries.drain(..).map(|entry| entry.state).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn undoes_only_within_the_window() {
+        let mut stack = UndoStack::new();
+        stack.push(Action::Stop, 1, secs(100));
+        assert_eq!(stack.undo(secs(100) + UNDO_WINDOW), None);
+        assert_eq!(stack.latest(), Some(Action::Stop));
+
+        let mut stack = UndoStack::new();
+        stack.push(Action::Stop, 1, secs(100));
+        assert_eq!(stack.undo(secs(109)), Some(1));
+        assert_eq!(stack.latest(), None);
+    }
+
+    #[test]
+    fn undoes_nested_actions_latest_first() {
+        let mut stack = UndoStack::new();
+        stack.push(Action::ChangeDuration, 1, secs(100));
+        stack.push(Action::SkipBreak, 2, secs(102));
+        stack.push(Action::Stop, 3, secs(104));
+        assert_eq!(stack.undo(secs(105)), Some(3));
+        assert_eq!(stack.latest(), Some(Action::SkipBreak));
+        assert_eq!(stack.undo(secs(106)), Some(2));
+        assert_eq!(stack.undo(secs(107)), Some(1));
+        assert_eq!(stack.undo(secs(108)), None);
+    }
+
+    #[test]
+    fn expires_old_entries_and_clears() {
+        let mut stack = UndoStack::new();
+        stack.push(Action::Stop, 1, secs(100));
+        stack.push(Action::SkipBreak, 2, secs(105));
+        stack.push(Action::Stop, 3, secs(112));
+        assert_eq!(stack.expire(secs(115)), vec![1, 2]);
+        assert_eq!(stack.latest(), Some(Action::Stop));
+        assert_eq!(stack.expire(secs(115)), Vec::<i32>::new());
+
+        stack.push(Action::ChangeDuration, 4, secs(116));
+        assert_eq!(stack.clear(), vec![3, 4]);
+        assert_eq!(stack.latest(), None);
+        assert_eq!(stack.undo(secs(117)), None);
+    }
+}
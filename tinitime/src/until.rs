@@ -0,0 +1,161 @@
+use std::time::Duration;
+
+use crate::localtime::{Zone, SECS_PER_DAY};
+
+/// A wall-clock target such as `14:55` or `tomorrow 09:00`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Until {
+    pub tomorrow: bool,
+    pub hour: u32,
+    pub minute: u32,
+}
+
+impl Until {
+    /// Parses `14:55`, `2:55pm`, `9 am` or any of them after `tomorrow`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (tomorrow, time) = match value.split_once(char::is_whitespace) {
+            Some((first, rest)) if first.eq_ignore_ascii_case("tomorrow") => (true, rest.trim()),
+            _ => (false, value),
+        };
+        let time = time.to_ascii_lowercase();
+        let (time, pm) = match (time.strip_suffix("am"), time.strip_suffix("pm")) {
+            (Some(time), _) => (time.trim_end(), Some(false)),
+            (_, Some(time)) => (time.trim_end(), Some(true)),
+            _ => (time.as_str(), None),
+        };
+        let (hour, minute) = match time.split_once(':') {
+            Some((hour, minute)) => (hour, minute),
+            None if pm.is_some() => (time, "00"),
+            None => return None,
+        };
+        let is_number = |text: &str, len| {
+            !text.is_empty() && text.len() <= len && text.chars().all(|c| c.is_ascii_digit())
+        };
+        if !is_number(hour, 2) || !is_number(minute, 2) || minute.len() != 2 {
+            return None;
+        }
+        let hour: u32 = hour.parse().ok()?;
+        let hour = match pm {
+            Some(pm) if (1..=12).contains(&hour) => hour % 12 + if pm { 12 } else { 0 },
+            Some(_) => return None,
+            None => hour,
+        };
+        let minute = minute.parse().ok().filter(|minute| *minute < 60)?;
+        (hour < 24).then_some(Self {
+            tomorrow,
+            hour,
+            minute,
+        })
+    }
+
+    /// Time left from `now` (Unix seconds) until the target in `zone`. A time
+    /// of day that has already passed today refers to tomorrow.
+    pub fn duration_from(&self, now: i64, zone: &impl Zone) -> Duration {
+        let local_now = zone.to_local(now);
+        let today = local_now.div_euclid(SECS_PER_DAY) * SECS_PER_DAY;
+        let time_of_day = (self.hour * 3600 + self.minute * 60) as i64;
+        let mut target = zone.to_utc(today + time_of_day);
+        if self.tomorrow || target <= now {
+            target = zone.to_utc(today + SECS_PER_DAY + time_of_day);
+        }
+        Duration::from_secs((target - now).max(0) as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::localtime::{tests::EuropeZone, FixedZone};
+
+    fn until(tomorrow: bool, hour: u32, minute: u32) -> Option<Until> {
+        Some(Until {
+            tomorrow,
+            hour,
+            minute,
+        })
+    }
+
+    #[test]
+    fn parses_24_hour_times() {
+        assert_eq!(Until::parse("14:55"), until(false, 14, 55));
+        assert_eq!(Until::parse("9:05"), until(false, 9, 5));
+        assert_eq!(Until::parse("00:00"), until(false, 0, 0));
+        assert_eq!(Until::parse(" 23:59 "), until(false, 23, 59));
+    }
+
+    #[test]
+    fn parses_12_hour_times() {
+        assert_eq!(Until::parse("2:55pm"), until(false, 14, 55));
+        assert_eq!(Until::parse("2:55 PM"), until(false, 14, 55));
+        assert_eq!(Until::parse("9am"), until(false, 9, 0));
+        assert_eq!(Until::parse("12:30am"), until(false, 0, 30));
+        assert_eq!(Until::parse("12pm"), until(false, 12, 0));
+    }
+
+    #[test]
+    fn parses_the_next_day() {
+        assert_eq!(Until::parse("tomorrow 09:00"), until(true, 9, 0));
+        assert_eq!(Until::parse("Tomorrow 9 am"), until(true, 9, 0));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        for value in [
+            "",
+            "garbage",
+            "tomorrow",
+            "14",
+            "24:00",
+            "12:60",
+            "1:5",
+            "123:00",
+            "+1:00",
+            "13pm",
+            "0am",
+            "14:55 tomorrow",
+            "tomorrow 14:55 15:00",
+        ] {
+            assert_eq!(Until::parse(value), None, "{:?}", value);
+        }
+    }
+
+    const HOUR: i64 = 3600;
+
+    #[test]
+    fn counts_down_to_today_or_tomorrow() {
+        let zone = FixedZone(2 * HOUR);
+        // 10:00 local.
+        let now = 8 * HOUR;
+        let left = |value| Until::parse(value).unwrap().duration_from(now, &zone);
+        assert_eq!(left("14:55").as_secs() as i64, 4 * HOUR + 55 * 60);
+        assert_eq!(left("9:00").as_secs() as i64, 23 * HOUR);
+        assert_eq!(left("10:00").as_secs() as i64, 24 * HOUR);
+        assert_eq!(left("tomorrow 10:30").as_secs() as i64, 24 * HOUR + 30 * 60);
+        assert_eq!(left("0:30").as_secs() as i64, 14 * HOUR + 30 * 60);
+    }
+
+    #[test]
+    fn counts_real_time_across_dst() {
+        let zone = EuropeZone;
+        // 22:00 CET on the day before clocks go forward.
+        let now = EuropeZone::SUMMER - 4 * HOUR;
+        let left = Until::parse("9:00").unwrap().duration_from(now, &zone);
+        assert_eq!(left.as_secs() as i64, 10 * HOUR);
+
+        // 23:00 CEST on the day before clocks go back.
+        let now = EuropeZone::WINTER - 4 * HOUR;
+        let left = Until::parse("9:00").unwrap().duration_from(now, &zone);
+        assert_eq!(left.as_secs() as i64, 11 * HOUR);
+    }
+
+    #[test]
+    fn skipped_time_moves_forward() {
+        // 00:00 CET, two hours before 02:00 does not happen.
+        let now = EuropeZone::SUMMER - 2 * HOUR;
+        let left = Until::parse("2:30")
+            .unwrap()
+            .duration_from(now, &EuropeZone);
+        assert_eq!(left.as_secs() as i64, 2 * HOUR + 30 * 60);
+    }
+}
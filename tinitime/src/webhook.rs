@@ -0,0 +1,748 @@
+use std::{
+    io,
+    path::Path,
+    sync::mpsc::{self, SyncSender},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    api::Status,
+    helpers::{escape_json, expand_template, load_file, unix_secs},
+    log,
+};
+
+const MAX_QUEUED: usize = 32;
+const MAX_ATTEMPTS: u32 = 4;
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+const TIMEOUT: Duration = Duration::from_secs(10);
+const DEF_BODY: &str = "{\"event\":\"{event}\",\"state\":\"{state}\",\"phase\":\"{phase}\",\
+                        \"label\":\"{label}\",\"remaining\":{remaining},\"duration\":{duration}}";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    Start,
+    Finish,
+    Stop,
+    Break,
+}
+
+impl Event {
+    const ALL: [Event; 4] = [Event::Start, Event::Finish, Event::Stop, Event::Break];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Event::Start => "start",
+            Event::Finish => "finish",
+            Event::Stop => "stop",
+            Event::Break => "break",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.as_str() == value)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Webhook {
+    pub name: String,
+    pub url: String,
+    pub method: String,
+    pub headers: Vec<String>,
+    pub body: String,
+    pub events: Vec<Event>,
+}
+
+impl Webhook {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            url: String::new(),
+            method: "POST".to_string(),
+            headers: vec![],
+            body: DEF_BODY.to_string(),
+            events: Event::ALL.to_vec(),
+        }
+    }
+
+    /// The request announcing `event`, unless the hook is not interested.
+    fn request(&self, event: Event, status: &Status, time: i64) -> Option<Request> {
+        if !self.events.contains(&event) {
+            return None;
+        }
+        let mut headers: String = self
+            .headers
+            .iter()
+            .map(|header| format!("{}\r\n", header))
+            .collect();
+        let has_content_type = self.headers.iter().any(|header| {
+            let name = header.split(':').next().unwrap_or_default();
+            name.trim().eq_ignore_ascii_case("content-type")
+        });
+        if !has_content_type {
+            headers.push_str("Content-Type: application/json\r\n");
+        }
+        let body = expand_template(&self.body, |name| match name {
+            "event" => Some(event.as_str().to_string()),
+            "state" => Some(status.state.to_string()),
+            "phase" => Some(status.phase.to_string()),
+            "label" => Some(escape_json(status.label.as_deref().unwrap_or_default())),
+            "remaining" => Some(status.remaining.to_string()),
+            "duration" => Some(status.duration.to_string()),
+            "time" => Some(time.to_string()),
+            _ => None,
+        });
+        Some(Request {
+            name: self.name.clone(),
+            url: self.url.clone(),
+            method: self.method.clone(),
+            headers,
+            body,
+        })
+    }
+}
+
+/// A webhook call, with each header line ending in CRLF.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Request {
+    name: String,
+    url: String,
+    method: String,
+    headers: String,
+    body: String,
+}
+
+/// Sends a request and returns the HTTP status code.
+trait Transport {
+    fn send(&mut self, request: &Request) -> io::Result<u32>;
+}
+
+/// Sends webhooks from a background thread. Requests that do not fit into the
+/// queue are dropped rather than holding up the timer.
+pub struct Webhooks {
+    hooks: Vec<Webhook>,
+    queue: SyncSender<Request>,
+}
+
+impl Webhooks {
+    pub fn new(hooks: Vec<Webhook>) -> Self {
+        Self::start(hooks, RETRY_DELAY, transport::connect)
+    }
+
+    /// Starts the thread, which makes its own transport with `connect`.
+    fn start<T, F>(hooks: Vec<Webhook>, retry_delay: Duration, connect: F) -> Self
+    where
+        T: Transport,
+        F: FnOnce() -> io::Result<T> + Send + 'static,
+    {
+        let (queue, requests) = mpsc::sync_channel::<Request>(MAX_QUEUED);
+        thread::spawn(move || {
+            let mut transport = match connect() {
+                Ok(transport) => transport,
+                Err(error) => {
+                    log::warning!("webhooks: {}", error);
+                    return;
+                }
+            };
+            for request in requests {
+                deliver(&mut transport, &request, retry_delay);
+            }
+        });
+        Self { hooks, queue }
+    }
+
+    pub fn fire(&self, event: Event, status: &Status) {
+        let time = unix_secs(SystemTime::now());
+        for request in self
+            .hooks
+            .iter()
+            .filter_map(|hook| hook.request(event, status, time))
+        {
+            if self.queue.try_send(request).is_err() {
+                log::warning!("webhooks: queue full, dropping a request");
+            }
+        }
+    }
+}
+
+/// Tries a request a few times, doubling `delay` between attempts. Client
+/// errors other than 429 are not retried since they would fail the same way
+/// again. Returns the last status code.
+fn deliver(transport: &mut impl Transport, request: &Request, delay: Duration) -> Option<u32> {
+    let mut last = None;
+    for attempt in 0..MAX_ATTEMPTS {
+        if attempt > 0 {
+            thread::sleep(delay * 2u32.pow(attempt - 1));
+        }
+        match transport.send(request) {
+            Ok(code @ 200..=299) => return Some(code),
+            Ok(code) if (400..=499).contains(&code) && code != 429 => {
+                log::warning!("webhook {}: HTTP {}", request.name, code);
+                return Some(code);
+            }
+            Ok(code) => {
+                log::debug!("webhook {}: HTTP {}", request.name, code);
+                last = Some(code);
+            }
+            Err(error) => log::debug!("webhook {}: {}", request.name, error),
+        }
+    }
+    log::warning!(
+        "webhook {}: giving up after {} attempts",
+        request.name,
+        MAX_ATTEMPTS
+    );
+    last
+}
+
+/// Parses a webhook file: one `[NAME]` section per webhook with `KEY = VALUE`
+/// lines. `url` is required; `method` defaults to POST, `on` to all events,
+/// and `header` may be repeated. In `body`, `{event}`, `{state}`, `{phase}`,
+/// `{label}`, `{remaining}`, `{duration}` and `{time}` are replaced by their
+/// JSON-escaped values. For example:
+///
+/// ```text
+/// [chat]
+/// url = https://chat.example.com/hooks/123
+/// on = finish break
+/// header = Authorization: Bearer 0123456789
+/// body = {"text": "{label} is done, time for a break"}
+/// ```
+pub fn parse(source: &str) -> std::result::Result<Vec<Webhook>, Vec<String>> {
+    let mut errors = vec![];
+    let mut hooks: Vec<(usize, Webhook)> = vec![];
+
+    for (index, line) in source.lines().enumerate() {
+        let line_no = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            hooks.push((line_no, Webhook::new(name.trim())));
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            errors.push(format!("{}: expected KEY = VALUE", line_no));
+            continue;
+        };
+        let Some((_, hook)) = hooks.last_mut() else {
+            errors.push(format!("{}: expected [NAME] before settings", line_no));
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "url" => match parse_url(value) {
+                #[cfg(not(windows))]
+                Some(target) if target.secure => {
+                    errors.push(format!("{}: https needs Windows, use http", line_no))
+                }
+                Some(_) => hook.url = value.to_string(),
+                None => errors.push(format!("{}: expected an http or https URL", line_no)),
+            },
+            "method" => hook.method = value.to_ascii_uppercase(),
+            "header" if value.contains(':') => hook.headers.push(value.to_string()),
+            "header" => errors.push(format!("{}: expected NAME: VALUE", line_no)),
+            "body" => hook.body = value.to_string(),
+            "on" => {
+                hook.events.clear();
+                for name in value.split_whitespace() {
+                    match Event::parse(name) {
+                        Some(event) => hook.events.push(event),
+                        None => errors.push(format!("{}: unknown event '{}'", line_no, name)),
+                    }
+                }
+            }
+            key => errors.push(format!("{}: unknown setting '{}'", line_no, key)),
+        }
+    }
+    for (line_no, hook) in &hooks {
+        if hook.url.is_empty() {
+            errors.push(format!("{}: webhook '{}' has no url", line_no, hook.name));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(hooks.into_iter().map(|(_, hook)| hook).collect())
+    } else {
+        Err(errors)
+    }
+}
+
+pub fn load(path: &Path) -> std::result::Result<Vec<Webhook>, Vec<String>> {
+    load_file(path, parse)
+}
+
+struct Target<'a> {
+    secure: bool,
+    host: &'a str,
+    port: u16,
+    path: &'a str,
+}
+
+fn parse_url(url: &str) -> Option<Target<'_>> {
+    let (secure, rest) = match url.split_once("://")? {
+        ("https", rest) => (true, rest),
+        ("http", rest) => (false, rest),
+        _ => return None,
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, "/"),
+    };
+    let default_port = if secure { 443 } else { 80 };
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) if !port.contains(']') => (host, port.parse().ok()?),
+        _ => (authority, default_port),
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    (!host.is_empty()).then_some(Target {
+        secure,
+        host,
+        port,
+        path,
+    })
+}
+
+#[cfg(windows)]
+use winhttp as transport;
+
+#[cfg(not(windows))]
+use plain as transport;
+
+/// Sends through WinHTTP, which handles TLS and the system proxy settings.
+#[cfg(windows)]
+mod winhttp {
+    use std::{ffi::c_void, io, ptr::null_mut};
+
+    use windows::{
+        core::{w, Error, Result, HSTRING, PCWSTR},
+        Win32::{
+            Foundation::E_INVALIDARG,
+            Networking::WinHttp::{
+                WinHttpCloseHandle, WinHttpConnect, WinHttpOpen, WinHttpOpenRequest,
+                WinHttpQueryHeaders, WinHttpReceiveResponse, WinHttpSendRequest,
+                WinHttpSetTimeouts, WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY, WINHTTP_FLAG_SECURE,
+                WINHTTP_OPEN_REQUEST_FLAGS, WINHTTP_QUERY_FLAG_NUMBER, WINHTTP_QUERY_STATUS_CODE,
+            },
+        },
+    };
+
+    use super::{parse_url, Request, Transport, TIMEOUT};
+
+    pub struct WinHttp {
+        session: Handle,
+    }
+
+    pub fn connect() -> io::Result<WinHttp> {
+        unsafe {
+            let session = Handle::new(WinHttpOpen(
+                w!("tinitime"),
+                WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY,
+                PCWSTR::null(),
+                PCWSTR::null(),
+                0,
+            ))
+            .map_err(io::Error::other)?;
+            let timeout = TIMEOUT.as_millis() as i32;
+            WinHttpSetTimeouts(session.0, timeout, timeout, timeout, timeout)
+                .map_err(io::Error::other)?;
+            Ok(WinHttp { session })
+        }
+    }
+
+    impl Transport for WinHttp {
+        fn send(&mut self, request: &Request) -> io::Result<u32> {
+            unsafe { self.send_request(request) }.map_err(io::Error::other)
+        }
+    }
+
+    impl WinHttp {
+        unsafe fn send_request(&self, request: &Request) -> Result<u32> {
+            let target = parse_url(&request.url).ok_or(Error::from(E_INVALIDARG))?;
+            let connect = Handle::new(WinHttpConnect(
+                self.session.0,
+                &HSTRING::from(target.host),
+                target.port,
+                0,
+            ))?;
+            let flags = if target.secure {
+                WINHTTP_FLAG_SECURE
+            } else {
+                WINHTTP_OPEN_REQUEST_FLAGS(0)
+            };
+            let handle = Handle::new(WinHttpOpenRequest(
+                connect.0,
+                &HSTRING::from(request.method.as_str()),
+                &HSTRING::from(target.path),
+                PCWSTR::null(),
+                PCWSTR::null(),
+                null_mut(),
+                flags,
+            ))?;
+            let headers: Vec<u16> = request.headers.encode_utf16().collect();
+            let body = request.body.as_bytes();
+            WinHttpSendRequest(
+                handle.0,
+                Some(&headers),
+                Some(body.as_ptr() as *const c_void),
+                body.len() as u32,
+                body.len() as u32,
+                0,
+            )?;
+            WinHttpReceiveResponse(handle.0, null_mut())?;
+            let mut code = 0u32;
+            let mut size = size_of::<u32>() as u32;
+            WinHttpQueryHeaders(
+                handle.0,
+                WINHTTP_QUERY_STATUS_CODE | WINHTTP_QUERY_FLAG_NUMBER,
+                PCWSTR::null(),
+                Some(&mut code as *mut u32 as *mut c_void),
+                &mut size,
+                null_mut(),
+            )?;
+            Ok(code)
+        }
+    }
+
+    struct Handle(*mut c_void);
+
+    impl Handle {
+        fn new(raw: *mut c_void) -> Result<Self> {
+            if raw.is_null() {
+                Err(Error::from_win32())
+            } else {
+                Ok(Self(raw))
+            }
+        }
+    }
+
+    impl Drop for Handle {
+        fn drop(&mut self) {
+            unsafe {
+                let _ = WinHttpCloseHandle(self.0);
+            }
+        }
+    }
+}
+
+/// Speaks plain HTTP/1.1 over TCP; `https` URLs are refused when the file is
+/// read, as there is no TLS without WinHTTP.
+#[cfg(any(test, not(windows)))]
+mod plain {
+    use std::{
+        io::{self, BufRead, BufReader, Write},
+        net::TcpStream,
+    };
+
+    use super::{parse_url, Request, Transport, TIMEOUT};
+
+    pub struct PlainHttp;
+
+    pub fn connect() -> io::Result<PlainHttp> {
+        Ok(PlainHttp)
+    }
+
+    impl Transport for PlainHttp {
+        fn send(&mut self, request: &Request) -> io::Result<u32> {
+            let target = parse_url(&request.url)
+                .filter(|target| !target.secure)
+                .ok_or(io::ErrorKind::InvalidInput)?;
+            let mut stream = TcpStream::connect((target.host, target.port))?;
+            stream.set_read_timeout(Some(TIMEOUT))?;
+            stream.set_write_timeout(Some(TIMEOUT))?;
+            let host = if target.host.contains(':') {
+                format!("[{}]:{}", target.host, target.port)
+            } else {
+                format!("{}:{}", target.host, target.port)
+            };
+            write!(
+                stream,
+                "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: tinitime\r\n{}\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                request.method,
+                target.path,
+                host,
+                request.headers,
+                request.body.len(),
+                request.body
+            )?;
+            let mut status_line = String::new();
+            BufReader::new(stream).read_line(&mut status_line)?;
+            status_line
+                .split_whitespace()
+                .nth(1)
+                .and_then(|code| code.parse().ok())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not an HTTP response"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::{Ipv4Addr, TcpListener},
+        sync::mpsc::Receiver,
+    };
+
+    use super::{plain::PlainHttp, *};
+
+    const HOOKS: &str = "# team chat\n\
+                         [chat]\n\
+                         url = http://127.0.0.1:9/hooks/123\n\
+                         on = finish break\n\
+                         header = Authorization: Bearer 0123456789\n\
+                         body = {\"text\": \"{label} is done after {duration}s\"}\n\
+                         \n\
+                         [log]\n\
+                         url = http://[::1]:8080\n\
+                         method = put\n\
+                         header = Content-Type: text/plain\n";
+
+    fn status() -> Status {
+        Status {
+            state: "stopped",
+            phase: "focus",
+            remaining: 0,
+            duration: 1500,
+            label: Some("Write \"docs\"".to_string()),
+        }
+    }
+
+    /// Answers each connection with the next of `codes` and passes on the
+    /// requests as they were sent.
+    fn serve(codes: &[u32]) -> (String, Receiver<String>) {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let url = format!(
+            "http://127.0.0.1:{}/hook",
+            listener.local_addr().unwrap().port()
+        );
+        let (sender, requests) = mpsc::channel();
+        let codes = codes.to_vec();
+        thread::spawn(move || {
+            for (stream, code) in listener.incoming().zip(codes) {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(&stream);
+                let mut request = String::new();
+                while !request.ends_with("\r\n\r\n") {
+                    reader.read_line(&mut request).unwrap();
+                }
+                let length = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Content-Length: "))
+                    .map_or(0, |length| length.parse().unwrap());
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                request.push_str(&String::from_utf8(body).unwrap());
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Whatever\r\nContent-Length: 0\r\n\r\n",
+                    code
+                )
+                .unwrap();
+                sender.send(request).unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    /// Counts the requests that arrive until the server goes quiet.
+    fn received(requests: &Receiver<String>) -> usize {
+        let mut count = 0;
+        while requests.recv_timeout(Duration::from_millis(100)).is_ok() {
+            count += 1;
+        }
+        count
+    }
+
+    fn hook(url: &str) -> Webhook {
+        Webhook {
+            url: url.to_string(),
+            ..Webhook::new("test")
+        }
+    }
+
+    #[test]
+    fn parses_hooks() {
+        let hooks = parse(HOOKS).unwrap();
+        assert_eq!(
+            hooks,
+            [
+                Webhook {
+                    name: "chat".to_string(),
+                    url: "http://127.0.0.1:9/hooks/123".to_string(),
+                    method: "POST".to_string(),
+                    headers: vec!["Authorization: Bearer 0123456789".to_string()],
+                    body: "{\"text\": \"{label} is done after {duration}s\"}".to_string(),
+                    events: vec![Event::Finish, Event::Break],
+                },
+                Webhook {
+                    name: "log".to_string(),
+                    url: "http://[::1]:8080".to_string(),
+                    method: "PUT".to_string(),
+                    headers: vec!["Content-Type: text/plain".to_string()],
+                    ..Webhook::new("log")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_bad_lines() {
+        let source = "url = http://example.com\n\
+                      [a]\n\
+                      url = ftp://example.com\n\
+                      header = Authorization\n\
+                      on = finish lunch\n\
+                      colour = red\n\
+                      just text\n";
+        assert_eq!(
+            parse(source).unwrap_err(),
+            [
+                "1: expected [NAME] before settings",
+                "3: expected an http or https URL",
+                "4: expected NAME: VALUE",
+                "5: unknown event 'lunch'",
+                "6: unknown setting 'colour'",
+                "7: expected KEY = VALUE",
+                "2: webhook 'a' has no url",
+            ]
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn refuses_https_without_winhttp() {
+        assert_eq!(
+            parse("[a]\nurl = https://example.com").unwrap_err(),
+            [
+                "2: https needs Windows, use http",
+                "1: webhook 'a' has no url"
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_urls() {
+        let target = parse_url("https://example.com:8443/a/b?c=d").unwrap();
+        assert!(target.secure);
+        assert_eq!(
+            (target.host, target.port, target.path),
+            ("example.com", 8443, "/a/b?c=d")
+        );
+        let target = parse_url("http://[::1]").unwrap();
+        assert_eq!((target.host, target.port, target.path), ("::1", 80, "/"));
+        assert!(parse_url("http://").is_none());
+        assert!(parse_url("http://example.com:http/").is_none());
+        assert!(parse_url("example.com").is_none());
+    }
+
+    #[test]
+    fn builds_requests_for_wanted_events() {
+        let hooks = parse(HOOKS).unwrap();
+        assert_eq!(hooks[0].request(Event::Start, &status(), 0), None);
+
+        let request = hooks[0].request(Event::Finish, &status(), 0).unwrap();
+        assert_eq!(
+            request.headers,
+            "Authorization: Bearer 0123456789\r\nContent-Type: application/json\r\n"
+        );
+        assert_eq!(
+            request.body,
+            "{\"text\": \"Write \\\"docs\\\" is done after 1500s\"}"
+        );
+
+        let request = hooks[1]
+            .request(Event::Stop, &status(), 1_700_000_000)
+            .unwrap();
+        assert_eq!(request.method, "PUT");
+        assert_eq!(request.headers, "Content-Type: text/plain\r\n");
+        assert_eq!(
+            request.body,
+            "{\"event\":\"stop\",\"state\":\"stopped\",\"phase\":\"focus\",\
+             \"label\":\"Write \\\"docs\\\"\",\"remaining\":0,\"duration\":1500}"
+        );
+        let timed = Webhook {
+            body: "{time} {unknown}".to_string(),
+            ..hooks[1].clone()
+        };
+        let request = timed
+            .request(Event::Stop, &status(), 1_700_000_000)
+            .unwrap();
+        assert_eq!(request.body, "1700000000 {unknown}");
+    }
+
+    #[test]
+    fn sends_over_http() {
+        let (url, requests) = serve(&[204]);
+        let request = hook(&url).request(Event::Start, &status(), 0).unwrap();
+        assert_eq!(PlainHttp.send(&request).unwrap(), 204);
+        let sent = requests.recv().unwrap();
+        let port = url.split(':').nth(2).unwrap().trim_end_matches("/hook");
+        assert!(sent.starts_with(&format!(
+            "POST /hook HTTP/1.1\r\nHost: 127.0.0.1:{}\r\n",
+            port
+        )));
+        assert!(sent.contains("\r\nContent-Type: application/json\r\n"));
+        assert!(sent.ends_with(&format!("\r\n\r\n{}", request.body)));
+    }
+
+    #[test]
+    fn retries_server_errors() {
+        let (url, requests) = serve(&[500, 429, 503, 200]);
+        let request = hook(&url).request(Event::Start, &status(), 0).unwrap();
+        assert_eq!(deliver(&mut PlainHttp, &request, Duration::ZERO), Some(200));
+        assert_eq!(received(&requests), 4);
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let (url, requests) = serve(&[500; 5]);
+        let request = hook(&url).request(Event::Start, &status(), 0).unwrap();
+        assert_eq!(deliver(&mut PlainHttp, &request, Duration::ZERO), Some(500));
+        assert_eq!(received(&requests), MAX_ATTEMPTS as usize);
+    }
+
+    #[test]
+    fn does_not_retry_client_errors() {
+        let (url, requests) = serve(&[404, 200]);
+        let request = hook(&url).request(Event::Start, &status(), 0).unwrap();
+        assert_eq!(deliver(&mut PlainHttp, &request, Duration::ZERO), Some(404));
+        assert_eq!(received(&requests), 1);
+    }
+
+    #[test]
+    fn retries_when_unreachable() {
+        // Bound and dropped, so nothing listens there any more.
+        let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let request = hook(&format!("http://127.0.0.1:{}/", port))
+            .request(Event::Start, &status(), 0)
+            .unwrap();
+        assert_eq!(deliver(&mut PlainHttp, &request, Duration::ZERO), None);
+    }
+
+    #[test]
+    fn fires_in_the_background() {
+        let (url, requests) = serve(&[200]);
+        let hooks = vec![Webhook {
+            events: vec![Event::Finish],
+            ..hook(&url)
+        }];
+        let webhooks = Webhooks::start(hooks, Duration::ZERO, plain::connect);
+        webhooks.fire(Event::Start, &status());
+        webhooks.fire(Event::Finish, &status());
+        let sent = requests.recv_timeout(TIMEOUT).unwrap();
+        assert!(sent.contains("\"event\":\"finish\""));
+        // Only the one event the hook wanted was sent.
+        assert!(requests.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+}
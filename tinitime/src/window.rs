@@ -1,56 +1,150 @@
-#![allow(unused_must_use)]
-
-use std::{ffi::c_void, ptr::null_mut};
+use std::{
+    ffi::c_void,
+    mem::{self, size_of},
+    path::PathBuf,
+    ptr::null_mut,
+    slice,
+    time::{Duration, SystemTime},
+};
 
 use windows::{
-    core::{w, Result, HSTRING, PCWSTR},
+    core::{w, Error, Result, HSTRING, PCWSTR, PWSTR},
     Win32::{
         Foundation::*,
         Graphics::{
             Gdi::{
-                BeginPaint, CreateCompatibleBitmap, CreateCompatibleDC, CreateFontW,
-                CreateSolidBrush, DeleteDC, DeleteObject, DrawTextW, EndPaint, FillRect,
-                GetStockObject, RedrawWindow, SelectObject, SetBkMode, SetTextColor,
-                CLIP_DEFAULT_PRECIS, DEFAULT_CHARSET, DEFAULT_QUALITY, DT_CENTER, DT_SINGLELINE,
-                DT_VCENTER, HBRUSH, HDC, HFONT, HGDIOBJ, NULL_BRUSH, OUT_DEFAULT_PRECIS,
-                PAINTSTRUCT, RDW_INVALIDATE, RDW_UPDATENOW, SRCCOPY, TRANSPARENT,
+                BeginPaint, CreateCompatibleBitmap, CreateCompatibleDC, CreateSolidBrush, DeleteDC,
+                DeleteObject, DrawTextW, EndPaint, FillRect, GetMonitorInfoW, GetStockObject,
+                MonitorFromWindow, PtInRect, RedrawWindow, ScreenToClient, SelectObject, SetBkMode,
+                SetTextColor, DT_CENTER, DT_END_ELLIPSIS, DT_SINGLELINE, DT_VCENTER, HBRUSH, HDC,
+                HFONT, HGDIOBJ, MONITORINFO, MONITOR_DEFAULTTONEAREST, NULL_BRUSH, PAINTSTRUCT,
+                RDW_INVALIDATE, RDW_UPDATENOW, SRCCOPY, TRANSPARENT,
             },
             GdiPlus::{
                 GdipCreateFromHDC, GdipCreatePen1, GdipDeleteGraphics, GdipDeletePen,
                 GdipDrawRectangleI, GpGraphics, GpPen, UnitPixel,
             },
         },
-        System::LibraryLoader::GetModuleHandleW,
+        Media::Audio::{PlaySoundW, SND_ASYNC, SND_FILENAME, SND_NODEFAULT},
+        System::{
+            DataExchange::COPYDATASTRUCT,
+            LibraryLoader::GetModuleHandleW,
+            RemoteDesktop::{
+                WTSRegisterSessionNotification, WTSUnRegisterSessionNotification,
+                NOTIFY_FOR_THIS_SESSION,
+            },
+        },
         UI::{
-            Input::KeyboardAndMouse::{RegisterHotKey, MOD_CONTROL, VK_F1},
+            Controls::{
+                TOOLTIPS_CLASSW, TTF_IDISHWND, TTM_ADDTOOLW, TTM_RELAYEVENT, TTM_UPDATETIPTEXTW,
+                TTS_ALWAYSTIP, TTS_NOPREFIX, TTTOOLINFOW,
+            },
+            Input::KeyboardAndMouse::{
+                RegisterHotKey, HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_SHIFT, VK_1, VK_F1,
+                VK_F2, VK_F3,
+            },
             WindowsAndMessaging::*,
         },
     },
 };
 
-const WINDOW_CLASS_NAME: PCWSTR = w!("rxcle.tinitime.wc");
+use crate::{
+    clock::{Clock, SystemClock},
+    config::Config,
+    controller::{Controller, Notice},
+    ctl::{Command, TimerAction, CTL_COMMAND},
+    error::LogFailure,
+    foreground::SystemForegroundSource,
+    helpers::{ceil_secs, hiword, loword},
+    history::History,
+    i18n::tr,
+    idle::SystemIdleSource,
+    localtime::SystemZone,
+    log, meeting,
+    named::{self, NamedTimer},
+    notify::Notifier,
+    overlay::{create_font, Overlay, WM_SKIP_BREAK},
+    reflection::{self, ReflectionPrompt, REFLECTION_SAVE, WM_REFLECTION},
+    room::Room,
+    stacked::{
+        StackedWindow, STACKED_FINISHED, STACKED_HEIGHT, STACKED_REMOVE, STACKED_WIDTH, WM_STACKED,
+    },
+    timer::{State, SuspendReason},
+    undo::Action,
+};
+
+pub const WINDOW_CLASS_NAME: PCWSTR = w!("rxcle.tinitime.wc");
 const IDT_TIMER: usize = 1;
+const IDT_WATCH: usize = 2;
+const IDT_REMINDER: usize = 3;
+const IDT_ROOM: usize = 4;
+const IDT_SCHEDULE: usize = 5;
+const IDT_UNDO: usize = 6;
+const IDT_MEETINGS: usize = 7;
 const IDH_HOTKEY: i32 = 100;
+const IDH_EXTEND: i32 = 101;
+const IDH_SNOOZE: i32 = 102;
+const IDH_UNDO: i32 = 103;
+/// Ctrl+Alt+1 to Ctrl+Alt+9 toggle the named timers.
+const IDH_NAMED: i32 = 110;
+const MAX_NAMED_HOTKEYS: i32 = 9;
 
-const DEF_TIME: i32 = 1500;
+const IDM_RESET_POS: usize = 1;
+const IDM_NO_LABEL: usize = 2;
+const IDM_EXTEND: usize = 3;
+const IDM_SNOOZE: usize = 4;
+const IDM_UNDO: usize = 5;
+const IDM_SKIP_REMINDER: usize = 6;
+const IDM_ADD_NAMED: usize = 50;
+const IDM_LABEL: usize = 100;
+
+const MAX_RECENT_LABELS: usize = 8;
+const NAMED_PRESETS: [u64; 6] = [3, 5, 10, 15, 30, 60];
+const STACK_GAP: i32 = 2;
+
+const DEF_SKIP_AFTER: Duration = Duration::from_secs(30);
 
 const WIN_WIDTH: i32 = 68;
+const WIN_WIDTH_WIDE: i32 = 200;
 const WIN_HEIGHT: i32 = 25;
+const WIN_ALPHA: u8 = 230;
 
 pub struct Window {
     handle: HWND,
+    tooltip: HWND,
     font: HFONT,
+    label_font: HFONT,
     fgbrush: HBRUSH,
     fgactive_brush: HBRUSH,
     fgstopped_brush: HBRUSH,
-    time_left: i32,
-    timer_active: bool,
+    controller: Controller<SystemClock, SystemZone>,
+    shown_secs: u64,
+    shown_state: State,
+    shown_tooltip: String,
+    recent_labels: Vec<String>,
+    notifier: Notifier,
+    fgbreak_brush: HBRUSH,
+    step_color: Option<u32>,
+    step_brush: HBRUSH,
+    overlay: Option<Box<Overlay>>,
+    /// Set once the window is going away, when nothing should be asked.
+    closing: bool,
+    /// The `IDT_*` timers that are set.
+    timers: Vec<usize>,
+    // Boxed because each window proc holds a pointer to its StackedWindow.
+    #[allow(clippy::vec_box)]
+    stack: Vec<Box<StackedWindow>>,
+    stack_path: Option<PathBuf>,
+    reflection: Option<Box<ReflectionPrompt>>,
+    meetings_path: Option<PathBuf>,
     window_active: bool,
     client_rect: RECT,
+    alpha: u8,
+    hidden: bool,
 }
 
 impl Window {
-    pub fn new(title: &str) -> Result<Box<Self>> {
+    pub fn new(title: &str, config: Config) -> Result<Box<Self>> {
         unsafe {
             let instance = GetModuleHandleW(None)?;
 
@@ -68,21 +162,59 @@ impl Window {
             let atom = RegisterClassW(&wc);
             debug_assert!(atom != 0);
 
+            let width = if config.wide {
+                WIN_WIDTH_WIDE
+            } else {
+                WIN_WIDTH
+            };
+            let history = History::open_default();
+            let mut recent_labels = history
+                .as_ref()
+                .map(|history| history.recent_labels(MAX_RECENT_LABELS))
+                .unwrap_or_default();
+            if let Some(label) = &config.label {
+                recent_labels.retain(|recent| recent != label);
+                recent_labels.insert(0, label.clone());
+            }
+
+            let mut controller = Controller::new(config, SystemClock::new(), SystemZone, history);
+            controller.watch_idle(SystemIdleSource::default());
+            controller.track_apps(SystemForegroundSource);
+            controller.serve_api(|command: Command| command.send().is_ok());
+
             let mut window = Box::new(Self {
                 handle: HWND::default(),
+                tooltip: HWND::default(),
                 font: HFONT::default(),
+                label_font: HFONT::default(),
                 fgbrush: HBRUSH::default(),
                 fgactive_brush: HBRUSH::default(),
                 fgstopped_brush: HBRUSH::default(),
-                time_left: DEF_TIME,
-                timer_active: false,
+                controller,
+                shown_secs: 0,
+                shown_state: State::Stopped,
+                shown_tooltip: String::new(),
+                recent_labels,
+                notifier: Notifier::new(HWND::default()),
+                fgbreak_brush: HBRUSH::default(),
+                step_color: None,
+                step_brush: HBRUSH::default(),
+                overlay: None,
+                closing: false,
+                timers: vec![],
+                stack: vec![],
+                stack_path: named::default_path(),
+                reflection: None,
+                meetings_path: meeting::default_path(),
                 window_active: false,
                 client_rect: RECT {
                     left: 0,
                     top: 0,
-                    right: WIN_WIDTH,
+                    right: width,
                     bottom: WIN_HEIGHT,
                 },
+                alpha: WIN_ALPHA,
+                hidden: false,
             });
 
             let hinstance: HINSTANCE = instance.into();
@@ -93,7 +225,7 @@ impl Window {
                 WS_POPUP | WS_VISIBLE,
                 CW_USEDEFAULT,
                 CW_USEDEFAULT,
-                WIN_WIDTH,
+                width,
                 WIN_HEIGHT,
                 None,
                 None,
@@ -101,10 +233,42 @@ impl Window {
                 Some(window.as_mut() as *mut _ as _),
             )?;
 
-            SetLayeredWindowAttributes(handle, COLORREF::default(), 230, LWA_ALPHA);
+            window.tooltip = CreateWindowExW(
+                WS_EX_TOPMOST,
+                TOOLTIPS_CLASSW,
+                None,
+                WS_POPUP | WINDOW_STYLE(TTS_ALWAYSTIP | TTS_NOPREFIX),
+                CW_USEDEFAULT,
+                CW_USEDEFAULT,
+                CW_USEDEFAULT,
+                CW_USEDEFAULT,
+                Some(handle),
+                None,
+                Some(hinstance),
+                None,
+            )?;
+
+            SetLayeredWindowAttributes(handle, COLORREF::default(), WIN_ALPHA, LWA_ALPHA)?;
 
             window.init_window(handle);
-            window.reset();
+            window.add_tooltip();
+            let config = window.controller.config();
+            if let Some(strictness) = config.break_overlay {
+                let skip_after = config.skip_after.unwrap_or(DEF_SKIP_AFTER);
+                window.overlay = Overlay::new(handle, strictness, skip_after)
+                    .inspect_err(|error| log::warning!("break overlay: {}", error))
+                    .ok();
+                if window.overlay.is_some() {
+                    window.controller.enforce_breaks();
+                }
+            }
+            window.load_stack();
+            window.check_meetings();
+            window.reset_pos();
+            // Only whether the window was visible before.
+            let _ = ShowWindow(window.handle, SW_SHOW);
+            window.controller.begin();
+            window.changed();
 
             Ok(window)
         }
@@ -112,38 +276,100 @@ impl Window {
 
     unsafe fn init_window(&mut self, window: HWND) {
         self.handle = window;
-        self.font = CreateFontW(
-            20,
-            0,
-            0,
-            0,
-            700i32,
-            0,
-            0,
-            0,
-            DEFAULT_CHARSET,
-            OUT_DEFAULT_PRECIS,
-            CLIP_DEFAULT_PRECIS,
-            DEFAULT_QUALITY,
-            0,
-            w!("Segoe UI Symbol"),
-        );
+        self.font = create_font(20, 700, w!("Segoe UI Symbol"));
+        self.label_font = create_font(15, 400, w!("Segoe UI"));
         self.fgbrush = CreateSolidBrush(COLORREF(0x00FFFFFF));
         self.fgactive_brush = CreateSolidBrush(COLORREF(0x00D7792B));
         self.fgstopped_brush = CreateSolidBrush(COLORREF(0x002B31D7));
+        self.fgbreak_brush = CreateSolidBrush(COLORREF(0x0050A028));
+        self.notifier = Notifier::new(window);
 
-        RegisterHotKey(Some(self.handle), IDH_HOTKEY, MOD_CONTROL, VK_F1.0 as u32);
+        self.register_hotkey(IDH_HOTKEY, MOD_CONTROL, VK_F1.0 as u32, "Ctrl+F1");
+        self.register_hotkey(IDH_EXTEND, MOD_CONTROL, VK_F2.0 as u32, "Ctrl+F2");
+        self.register_hotkey(IDH_SNOOZE, MOD_CONTROL, VK_F3.0 as u32, "Ctrl+F3");
+        self.register_hotkey(
+            IDH_UNDO,
+            MOD_CONTROL | MOD_SHIFT,
+            VK_F1.0 as u32,
+            "Ctrl+Shift+F1",
+        );
+        for index in 0..MAX_NAMED_HOTKEYS {
+            let key = VK_1.0 as u32 + index as u32;
+            let name = format!("Ctrl+Alt+{}", index + 1);
+            self.register_hotkey(IDH_NAMED + index, MOD_CONTROL | MOD_ALT, key, &name);
+        }
+
+        if self.controller.config().needs_watch() {
+            self.set_timer(IDT_WATCH, 250);
+        }
+        if self.controller.has_reminders() {
+            self.set_timer(IDT_REMINDER, 500);
+        }
+        if self.controller.is_following() {
+            self.set_timer(IDT_ROOM, 250);
+        }
+        if self.controller.has_schedule() {
+            self.set_timer(IDT_SCHEDULE, 1000);
+        }
+        // Meetings may get imported while we run.
+        self.set_timer(IDT_MEETINGS, 15_000);
+        // Without it a locked screen is only noticed as idle time.
+        WTSRegisterSessionNotification(self.handle, NOTIFY_FOR_THIS_SESSION)
+            .log_failure("WTSRegisterSessionNotification");
+    }
+
+    /// Another program may already own the key, which is not fatal.
+    unsafe fn register_hotkey(&self, id: i32, modifiers: HOT_KEY_MODIFIERS, key: u32, name: &str) {
+        if let Err(error) = RegisterHotKey(Some(self.handle), id, modifiers, key) {
+            log::warning!("hotkey {}: {}", name, error);
+        }
+    }
+
+    unsafe fn set_timer(&mut self, id: usize, millis: u32) {
+        if SetTimer(Some(self.handle), id, millis, None) == 0 {
+            log::warning!("timer {}: {}", id, Error::from_win32());
+        } else if !self.timers.contains(&id) {
+            self.timers.push(id);
+        }
+    }
+
+    unsafe fn kill_timer(&mut self, id: usize) {
+        if let Some(index) = self.timers.iter().position(|timer| *timer == id) {
+            self.timers.swap_remove(index);
+            KillTimer(Some(self.handle), id).log_failure("KillTimer");
+        }
     }
 
+    /// Runs on WM_QUERYENDSESSION and again on WM_DESTROY; only the first
+    /// call has anything left to release.
     unsafe fn destroy_window(&mut self) {
+        if self.handle.is_invalid() {
+            return;
+        }
+        self.closing = true;
+        self.controller.close();
+        self.controller.publish();
+        self.save_stack();
+        self.reflection = None;
+        for id in mem::take(&mut self.timers) {
+            KillTimer(Some(self.handle), id).log_failure("KillTimer");
+        }
+        self.notifier.remove();
+        WTSUnRegisterSessionNotification(self.handle)
+            .log_failure("WTSUnRegisterSessionNotification");
         PostQuitMessage(0);
         self.handle = HWND::default();
-        DeleteObject(HGDIOBJ::from(self.font));
+        DeleteObject(HGDIOBJ::from(self.font)).log_failure("DeleteObject");
         self.font = HFONT::default();
-        DeleteObject(HGDIOBJ::from(self.fgbrush));
+        DeleteObject(HGDIOBJ::from(self.label_font)).log_failure("DeleteObject");
+        self.label_font = HFONT::default();
+        DeleteObject(HGDIOBJ::from(self.fgbrush)).log_failure("DeleteObject");
         self.fgbrush = HBRUSH::default();
-        DeleteObject(HGDIOBJ::from(self.fgactive_brush));
+        DeleteObject(HGDIOBJ::from(self.fgactive_brush)).log_failure("DeleteObject");
         self.fgactive_brush = HBRUSH::default();
+        DeleteObject(HGDIOBJ::from(self.fgbreak_brush)).log_failure("DeleteObject");
+        self.fgbreak_brush = HBRUSH::default();
+        self.set_step_color(None);
     }
 
     unsafe fn paint(&mut self, ps: PAINTSTRUCT, hdc: HDC) {
@@ -154,9 +380,16 @@ impl Window {
         let mem_bitmap = CreateCompatibleBitmap(hdc, width, height);
         let old_bitmap = SelectObject(mem_dc, mem_bitmap.into());
 
+        let controller = &self.controller;
+        let reminder_break = controller.active_break();
+
         let (bg, fg) = if self.window_active {
             (self.fgactive_brush, COLORREF(0x00FFFFFF))
-        } else if self.timer_active {
+        } else if reminder_break.is_some() || controller.is_on_break() {
+            (self.fgbreak_brush, COLORREF(0x00FFFFFF))
+        } else if !controller.timer().is_stopped() && !self.step_brush.is_invalid() {
+            (self.step_brush, COLORREF(0x00FFFFFF))
+        } else if !controller.timer().is_stopped() {
             (self.fgbrush, COLORREF(0x00000000))
         } else {
             (self.fgstopped_brush, COLORREF(0x00FFFFFF))
@@ -169,16 +402,21 @@ impl Window {
 
         SelectObject(mem_dc, GetStockObject(NULL_BRUSH));
 
-        let minutes = self.time_left / 60;
-        let seconds = self.time_left % 60;
-        let mut time_left_str: Vec<u16> = format!("{:0}:{:02}", minutes, seconds)
-            .encode_utf16()
-            .collect();
+        let time_left = match reminder_break {
+            Some((_, remaining)) => ceil_secs(remaining),
+            None => controller.remaining_secs(),
+        };
+        let time_left_str = if time_left >= 6000 {
+            format!("{}h{:02}", time_left / 3600, time_left / 60 % 60)
+        } else {
+            format!("{:0}:{:02}", time_left / 60, time_left % 60)
+        };
+        let mut time_left_str: Vec<u16> = time_left_str.encode_utf16().collect();
 
         let mut rtime = RECT {
             left: self.client_rect.left + 15,
             top: self.client_rect.top,
-            right: self.client_rect.right,
+            right: self.client_rect.left + WIN_WIDTH,
             bottom: self.client_rect.bottom,
         };
 
@@ -189,7 +427,13 @@ impl Window {
             DT_SINGLELINE | DT_VCENTER | DT_CENTER,
         );
 
-        let state_str = if self.timer_active {
+        let state_str = if controller.undo_latest().is_some() {
+            "\u{21B6}"
+        } else if reminder_break.is_some() {
+            "\u{231B}"
+        } else if controller.is_on_break() {
+            "\u{2615}"
+        } else if controller.timer().state() == State::Running {
             "\u{E102}"
         } else {
             "\u{E103}"
@@ -210,10 +454,54 @@ impl Window {
             DT_SINGLELINE | DT_VCENTER,
         );
 
+        let idle = controller.timer().is_stopped() && !controller.has_session();
+        let undo_hint = controller
+            .undo_latest()
+            .map(|action| tr!("undo-hint", action = undo_name(action)));
+        let task_label = controller.task_label();
+        let label = match reminder_break {
+            _ if undo_hint.is_some() => undo_hint.as_ref(),
+            Some((reminder, _)) => Some(&reminder.name),
+            None if idle && controller.next_block().is_some() => controller.next_block(),
+            None => task_label.as_ref(),
+        };
+        if let Some(label) = label.filter(|_| controller.config().wide) {
+            let mut label_str: Vec<u16> = label.encode_utf16().collect();
+            let mut rlabel = RECT {
+                left: self.client_rect.left + WIN_WIDTH,
+                top: self.client_rect.top,
+                right: self.client_rect.right - 5,
+                bottom: self.client_rect.bottom,
+            };
+            SelectObject(mem_dc, HGDIOBJ::from(self.label_font));
+            DrawTextW(
+                mem_dc,
+                label_str.as_mut_slice(),
+                &mut rlabel,
+                DT_SINGLELINE | DT_VCENTER | DT_END_ELLIPSIS,
+            );
+        }
+
+        if let Some(status) = controller.goal_status() {
+            let bar = if self.window_active {
+                self.fgbrush
+            } else {
+                self.fgactive_brush
+            };
+            let width = (self.client_rect.right as f32 * status.fraction) as i32;
+            let rbar = RECT {
+                left: self.client_rect.left,
+                top: self.client_rect.bottom - 2,
+                right: self.client_rect.left + width,
+                bottom: self.client_rect.bottom,
+            };
+            FillRect(mem_dc, &rbar, bar);
+        }
+
         let mut graphics: *mut GpGraphics = null_mut();
         GdipCreateFromHDC(mem_dc, &mut graphics);
 
-        let grey: u32 = (0x30 << 24) | (0x00 << 16) | (0x00 << 8) | 0x00;
+        let grey: u32 = 0x30 << 24;
         let mut pen: *mut GpPen = null_mut();
         GdipCreatePen1(grey, 1.0, UnitPixel, &mut pen);
 
@@ -236,40 +524,39 @@ impl Window {
             0,
             0,
             SRCCOPY,
-        );
+        )
+        .log_failure("BitBlt");
 
         // Cleanup
         GdipDeletePen(pen);
         GdipDeleteGraphics(graphics);
         SelectObject(mem_dc, old_bitmap);
-        DeleteObject(mem_bitmap.into());
-        DeleteDC(mem_dc);
-    }
-
-    unsafe fn reset(&mut self) {
-        self.stop_timer();
-        self.reset_pos();
-        ShowWindow(self.handle, SW_SHOW);
+        DeleteObject(mem_bitmap.into()).log_failure("DeleteObject");
+        DeleteDC(mem_dc).log_failure("DeleteDC");
     }
 
     unsafe fn reset_pos(&mut self) {
         let mut window_rect = RECT::default();
-        SystemParametersInfoW(
+        if let Err(error) = SystemParametersInfoW(
             SPI_GETWORKAREA,
             0,
             Some(&mut window_rect as *mut _ as *mut c_void),
             SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
-        );
+        ) {
+            log::warning!("work area: {}", error);
+            return;
+        }
 
         SetWindowPos(
             self.handle,
             None,
-            window_rect.right - WIN_WIDTH - 5,
+            window_rect.right - self.client_rect.right - 5,
             window_rect.bottom - WIN_HEIGHT - 5,
             0,
             0,
             SWP_NOSIZE,
-        );
+        )
+        .log_failure("SetWindowPos");
     }
 
     unsafe fn activate_window(&mut self, activate: bool) {
@@ -277,45 +564,686 @@ impl Window {
         self.refresh();
     }
 
-    unsafe fn start_timer(&mut self) {
-        if self.timer_active {
-            self.stop_timer();
+    /// Shows what the controller did after the user or a command changed
+    /// something.
+    unsafe fn changed(&mut self) {
+        self.sync();
+        self.refresh();
+    }
+
+    /// Shows what the controller did on one of the periodic timers, redrawing
+    /// only if that shows.
+    unsafe fn ticked(&mut self) {
+        if self.sync() {
+            self.refresh();
+        }
+    }
+
+    /// Tells the user what happened and brings the timers, the overlay, the
+    /// step color and the tooltip in line with the controller. Returns
+    /// whether the window needs redrawing.
+    unsafe fn sync(&mut self) -> bool {
+        let notices = self.controller.notices();
+        let mut changed = !notices.is_empty();
+        for notice in notices {
+            self.show_notice(notice);
+        }
+
+        let stopped = self.controller.timer().is_stopped();
+        // A session that ran out stays open for a while, which needs ticks.
+        let ticking = !stopped || self.controller.has_session();
+        self.keep_timer(IDT_TIMER, 200, ticking);
+        self.keep_timer(IDT_UNDO, 500, self.controller.undo_latest().is_some());
+        let through =
+            self.controller.config().click_through && !stopped && !self.controller.is_on_break();
+        self.set_click_through(through);
+
+        let step_color = self
+            .controller
+            .program_step()
+            .and_then(|index| self.controller.config().program.get(index))
+            .and_then(|step| step.color);
+        if step_color != self.step_color {
+            self.set_step_color(step_color);
+        }
+        let remaining = self
+            .controller
+            .timer()
+            .remaining(self.controller.clock().now());
+        let enforced = self.controller.is_break_enforced();
+        if let Some(overlay) = &mut self.overlay {
+            if enforced {
+                overlay.show(remaining);
+                overlay.update(remaining);
+            } else if overlay.is_shown() {
+                overlay.hide();
+            }
+        }
+
+        let tooltip = self.tooltip_text();
+        if tooltip != self.shown_tooltip {
+            self.update_tooltip(&tooltip);
+            self.shown_tooltip = tooltip;
+            changed = true;
+        }
+        changed
+            || self.controller.remaining_secs() != self.shown_secs
+            || self.controller.timer().state() != self.shown_state
+            || self.controller.active_break().is_some()
+    }
+
+    unsafe fn show_notice(&mut self, notice: Notice) {
+        match notice {
+            Notice::Finished => {}
+            Notice::TimesUp => {
+                let text = tr!(
+                    "notify-times-up-text",
+                    extend = format_secs(self.controller.extend_by().as_secs()),
+                    snooze = format_secs(self.controller.snooze_for().as_secs()),
+                );
+                self.notifier.notify(&tr!("notify-times-up"), &text);
+            }
+            Notice::Step(index) => self.play_step_sound(index),
+            Notice::Break(duration) => {
+                let text = tr!(
+                    "notify-break-text",
+                    duration = format_secs(duration.as_secs())
+                );
+                self.notifier.notify(&tr!("notify-break"), &text);
+            }
+            Notice::BreakOver => {
+                self.notifier
+                    .notify(&tr!("notify-break-over"), &tr!("notify-break-over-text"));
+            }
+            Notice::Block(name, remaining) => {
+                let text = tr!(
+                    "notify-time-left",
+                    duration = format_secs(ceil_secs(remaining))
+                );
+                self.notifier.notify(&name, &text);
+            }
+            Notice::Reminder(index) => {
+                if let Some(reminder) = self.controller.reminder(index) {
+                    let text = tr!(
+                        "notify-reminder-text",
+                        duration = format_secs(reminder.break_for.as_secs())
+                    );
+                    self.notifier.notify(&reminder.name, &text);
+                }
+            }
+            Notice::GoalReached(status) => {
+                let text = tr!(
+                    "notify-goal-text",
+                    progress = &status.text,
+                    streak = status.streak
+                );
+                self.notifier.notify(&tr!("notify-goal"), &text);
+            }
+            Notice::Completed(start) => self.ask_reflection(start),
+            Notice::LabelChanged => self.show_label(),
+        }
+    }
+
+    /// Sets or kills an `IDT_*` timer, leaving one that is already set alone.
+    unsafe fn keep_timer(&mut self, id: usize, millis: u32, on: bool) {
+        if !on {
+            self.kill_timer(id);
+        } else if !self.timers.contains(&id) {
+            self.set_timer(id, millis);
+        }
+    }
+
+    unsafe fn play_step_sound(&mut self, index: usize) {
+        let Some(sound) = self
+            .controller
+            .config()
+            .program
+            .get(index)
+            .and_then(|step| step.sound.as_ref())
+        else {
+            return;
+        };
+        let played = PlaySoundW(
+            &HSTRING::from(sound.as_os_str()),
+            None,
+            SND_FILENAME | SND_ASYNC | SND_NODEFAULT,
+        );
+        if !played.as_bool() {
+            log::warning!("{}: the sound could not be played", sound.display());
+        }
+    }
+
+    unsafe fn set_step_color(&mut self, color: Option<u32>) {
+        if !self.step_brush.is_invalid() {
+            DeleteObject(HGDIOBJ::from(self.step_brush)).log_failure("DeleteObject");
+            self.step_brush = HBRUSH::default();
+        }
+        if let Some(rgb) = color {
+            let bgr = ((rgb & 0xFF) << 16) | (rgb & 0xFF00) | ((rgb >> 16) & 0xFF);
+            self.step_brush = CreateSolidBrush(COLORREF(bgr));
+        }
+        self.step_color = color;
+    }
+
+    /// Asks what the session that just completed got done, unless that is
+    /// turned off or a full-screen app hides the timer.
+    unsafe fn ask_reflection(&mut self, start: SystemTime) {
+        self.reflection = None;
+        if self.controller.config().no_reflection || self.hidden || self.closing {
+            return;
+        }
+        let prompt = match ReflectionPrompt::new(self.handle, start) {
+            Ok(prompt) => prompt,
+            Err(error) => {
+                log::warning!("reflection prompt: {}", error);
+                return;
+            }
+        };
+        let mut anchor = RECT::default();
+        GetWindowRect(self.handle, &mut anchor).log_failure("GetWindowRect");
+        for stacked in &self.stack {
+            let mut rect = RECT::default();
+            if GetWindowRect(stacked.handle(), &mut rect).is_ok() {
+                anchor.top = anchor.top.min(rect.top);
+                anchor.bottom = anchor.bottom.max(rect.bottom);
+            }
+        }
+        if let Err(error) = prompt.show_near(anchor, self.work_area()) {
+            log::warning!("reflection prompt: {}", error);
+            return;
+        }
+        self.reflection = Some(prompt);
+    }
+
+    unsafe fn handle_reflection(&mut self, event: usize) {
+        let Some(prompt) = self.reflection.take() else {
+            return;
+        };
+        let (note, interruptions) = (prompt.note(), prompt.interruptions());
+        if event != REFLECTION_SAVE || (note.is_none() && interruptions.is_none()) {
+            return;
+        }
+        self.controller.reflect(prompt.start(), note, interruptions);
+    }
+
+    /// Puts the label first among the recent ones and in the title.
+    unsafe fn show_label(&mut self) {
+        let label = self.controller.label().cloned();
+        if let Some(label) = &label {
+            self.recent_labels.retain(|recent| recent != label);
+            self.recent_labels.insert(0, label.clone());
+            self.recent_labels.truncate(MAX_RECENT_LABELS);
+        }
+        let title = match &label {
+            Some(label) => format!("{} - tinitime", label),
+            None => "tinitime".to_string(),
+        };
+        SetWindowTextW(self.handle, &HSTRING::from(title)).log_failure("SetWindowTextW");
+    }
+
+    unsafe fn add_tooltip(&mut self) {
+        let mut text: Vec<u16> = "tinitime\0".encode_utf16().collect();
+        let tool = self.tool_info(&mut text);
+        SendMessageW(
+            self.tooltip,
+            TTM_ADDTOOLW,
+            None,
+            Some(LPARAM(&tool as *const _ as isize)),
+        );
+    }
+
+    fn tooltip_text(&self) -> String {
+        let controller = &self.controller;
+        let mut text = controller.task_label().unwrap_or_else(|| tr!("no-label"));
+        if let Some(next_block) = controller.next_block() {
+            text = format!("{} \u{00B7} {}", text, next_block);
+        }
+        if let (Some(room), Some(name)) = (controller.room(), &controller.config().room) {
+            let role = match room {
+                Room::Lead(_) => tr!("tooltip-leading", room = name),
+                Room::Follow(_) => tr!("tooltip-following", room = name),
+            };
+            text = format!("{} \u{00B7} {}", text, role);
+        }
+        if let Some(status) = controller.goal_status() {
+            text = format!(
+                "{} \u{00B7} {} \u{00B7} {}",
+                text,
+                tr!("tooltip-goal", progress = &status.text),
+                tr!("tooltip-streak", streak = status.streak)
+            );
+        }
+        text
+    }
+
+    unsafe fn update_tooltip(&mut self, text: &str) {
+        let mut text: Vec<u16> = text.encode_utf16().chain(Some(0)).collect();
+        let tool = self.tool_info(&mut text);
+        SendMessageW(
+            self.tooltip,
+            TTM_UPDATETIPTEXTW,
+            None,
+            Some(LPARAM(&tool as *const _ as isize)),
+        );
+    }
+
+    fn tool_info(&self, text: &mut [u16]) -> TTTOOLINFOW {
+        TTTOOLINFOW {
+            cbSize: size_of::<TTTOOLINFOW>() as u32,
+            uFlags: TTF_IDISHWND,
+            hwnd: self.handle,
+            uId: self.handle.0 as usize,
+            lpszText: PWSTR(text.as_mut_ptr()),
+            ..Default::default()
         }
-        self.timer_active = true;
-        SetTimer(Some(self.handle), IDT_TIMER, 1000, None);
-        self.update_timer(DEF_TIME);
     }
 
-    unsafe fn stop_timer(&mut self) {
-        KillTimer(Some(self.handle), IDT_TIMER);
-        self.timer_active = false;
-        self.update_timer(DEF_TIME);
+    unsafe fn relay_tooltip(&mut self, lparam: LPARAM) {
+        let mut point = POINT {
+            x: loword(lparam.0),
+            y: hiword(lparam.0),
+        };
+        ScreenToClient(self.handle, &mut point).log_failure("ScreenToClient");
+        let message = MSG {
+            hwnd: self.handle,
+            message: WM_MOUSEMOVE,
+            lParam: LPARAM(((point.y as u16 as isize) << 16) | point.x as u16 as isize),
+            ..Default::default()
+        };
+        SendMessageW(
+            self.tooltip,
+            TTM_RELAYEVENT,
+            None,
+            Some(LPARAM(&message as *const _ as isize)),
+        );
     }
 
-    unsafe fn toggle_timer(&mut self) {
-        if self.timer_active {
-            self.stop_timer();
+    /// Adds the items of the context menu; one that cannot be added leaves
+    /// out the rest.
+    unsafe fn fill_menu(&self, menu: HMENU) -> Result<()> {
+        let controller = &self.controller;
+        if let Some(action) = controller.undo_latest() {
+            let undo = format!(
+                "{}\tCtrl+Shift+F1",
+                tr!("undo-menu", action = undo_name(action))
+            );
+            AppendMenuW(menu, MF_STRING, IDM_UNDO, &HSTRING::from(undo))?;
+            AppendMenuW(menu, MF_SEPARATOR, 0, PCWSTR::null())?;
+        }
+        for (index, label) in self.recent_labels.iter().enumerate() {
+            let checked = if controller.label() == Some(label) {
+                MF_CHECKED
+            } else {
+                MF_UNCHECKED
+            };
+            AppendMenuW(
+                menu,
+                MF_STRING | checked,
+                IDM_LABEL + index,
+                &HSTRING::from(label),
+            )?;
+        }
+        let checked = if controller.label().is_none() {
+            MF_CHECKED
+        } else {
+            MF_UNCHECKED
+        };
+        AppendMenuW(
+            menu,
+            MF_STRING | checked,
+            IDM_NO_LABEL,
+            &HSTRING::from(tr!("no-label")),
+        )?;
+        AppendMenuW(menu, MF_SEPARATOR, 0, PCWSTR::null())?;
+        let enabled = if controller.has_session() && controller.program_step().is_none() {
+            MF_ENABLED
         } else {
-            self.start_timer();
+            MF_GRAYED
+        };
+        let extend = tr!(
+            "menu-extend",
+            duration = format_secs(controller.extend_by().as_secs())
+        );
+        AppendMenuW(
+            menu,
+            MF_STRING | enabled,
+            IDM_EXTEND,
+            &HSTRING::from(extend),
+        )?;
+        let snooze = tr!(
+            "menu-snooze",
+            duration = format_secs(controller.snooze_for().as_secs())
+        );
+        AppendMenuW(
+            menu,
+            MF_STRING | enabled,
+            IDM_SNOOZE,
+            &HSTRING::from(snooze),
+        )?;
+        AppendMenuW(menu, MF_SEPARATOR, 0, PCWSTR::null())?;
+        if let Ok(presets) = CreatePopupMenu() {
+            for (index, minutes) in NAMED_PRESETS.iter().enumerate() {
+                let text = format_secs(minutes * 60);
+                AppendMenuW(
+                    presets,
+                    MF_STRING,
+                    IDM_ADD_NAMED + index,
+                    &HSTRING::from(text),
+                )?;
+            }
+            let add = HSTRING::from(tr!("menu-add-timer"));
+            AppendMenuW(menu, MF_POPUP, presets.0 as usize, &add)?;
+        }
+        if let Some((reminder, _)) = controller.active_break() {
+            let skip = tr!("menu-skip-reminder", name = &reminder.name);
+            AppendMenuW(menu, MF_STRING, IDM_SKIP_REMINDER, &HSTRING::from(skip))?;
+        }
+        let reset = HSTRING::from(tr!("menu-reset-position"));
+        AppendMenuW(menu, MF_STRING, IDM_RESET_POS, &reset)?;
+        Ok(())
+    }
+
+    unsafe fn show_menu(&mut self, lparam: LPARAM) {
+        let Ok(menu) = CreatePopupMenu() else {
+            return;
+        };
+        if let Err(error) = self.fill_menu(menu) {
+            log::warning!("menu: {}", error);
+        }
+
+        // Only whether the window came to the front, which the menu needs
+        // to close when clicked away from.
+        let _ = SetForegroundWindow(self.handle);
+        let command = TrackPopupMenu(
+            menu,
+            TPM_RETURNCMD | TPM_RIGHTBUTTON,
+            loword(lparam.0),
+            hiword(lparam.0),
+            None,
+            self.handle,
+            None,
+        );
+        DestroyMenu(menu).log_failure("DestroyMenu");
+
+        let controller = &mut self.controller;
+        match command.0 as usize {
+            IDM_RESET_POS => self.reset_pos(),
+            IDM_EXTEND => controller.extend(controller.extend_by()),
+            IDM_SNOOZE => controller.extend(controller.snooze_for()),
+            IDM_UNDO => controller.undo(),
+            IDM_SKIP_REMINDER => controller.dismiss_reminder(),
+            IDM_NO_LABEL => controller.set_label(None),
+            id if (IDM_ADD_NAMED..IDM_ADD_NAMED + NAMED_PRESETS.len()).contains(&id) => {
+                let duration = Duration::from_secs(NAMED_PRESETS[id - IDM_ADD_NAMED] * 60);
+                self.add_named(format_secs(duration.as_secs()), duration);
+            }
+            id if id >= IDM_LABEL => {
+                let label = self.recent_labels.get(id - IDM_LABEL).cloned();
+                if label.is_some() {
+                    controller.set_label(label);
+                }
+            }
+            _ => {}
+        }
+        self.changed();
+    }
+
+    unsafe fn handle_command(&mut self, command: Command) {
+        log::debug!("command {:?}", command);
+        match command {
+            Command::Timer(name, TimerAction::Add(duration)) => self.add_named(name, duration),
+            Command::Timer(name, action) => {
+                let Some(index) = self.find_named(&name) else {
+                    return;
+                };
+                match action {
+                    TimerAction::Toggle => self.stack[index].toggle(),
+                    TimerAction::Reset => self.stack[index].reset(),
+                    TimerAction::Remove => self.remove_named(index),
+                    TimerAction::Add(_) => {}
+                }
+            }
+            command => {
+                self.controller.handle_command(command);
+                self.changed();
+            }
+        }
+    }
+
+    unsafe fn load_stack(&mut self) {
+        let Some(path) = &self.stack_path else {
+            return;
+        };
+        let clock = *self.controller.clock();
+        for named in named::load(path, clock.now(), clock.wall()) {
+            if let Ok(stacked) = StackedWindow::new(self.handle, named, clock) {
+                self.stack.push(stacked);
+            }
+        }
+        self.layout_stack();
+    }
+
+    fn save_stack(&self) {
+        if let Some(path) = &self.stack_path {
+            let timers = self.stack.iter().map(|stacked| stacked.named());
+            let clock = self.controller.clock();
+            if let Err(error) = named::save(path, timers, clock.now(), clock.wall()) {
+                log::warning!("{}: {}", path.display(), error);
+            }
+        }
+    }
+
+    fn find_named(&self, name: &str) -> Option<usize> {
+        self.stack
+            .iter()
+            .position(|stacked| stacked.named().name.eq_ignore_ascii_case(name))
+    }
+
+    /// Starts a new named timer, or restarts the one with that name.
+    unsafe fn add_named(&mut self, name: String, duration: Duration) {
+        if let Some(index) = self.find_named(&name) {
+            self.stack[index].restart(duration);
+            return;
+        }
+        let mut named = NamedTimer::new(name, duration);
+        let clock = *self.controller.clock();
+        named.timer.start(clock.now());
+        match StackedWindow::new(self.handle, named, clock) {
+            Ok(stacked) => {
+                self.stack.push(stacked);
+                self.layout_stack();
+                self.save_stack();
+            }
+            Err(error) => log::warning!("timer window: {}", error),
         }
     }
 
-    unsafe fn update_timer(&mut self, new_time: i32) {
-        if new_time < 0 {
-            self.stop_timer();
+    /// Starts a named countdown for every meeting coming up soon and forgets
+    /// the meetings that are over.
+    unsafe fn check_meetings(&mut self) {
+        let Some(path) = self.meetings_path.clone() else {
+            return;
+        };
+        let mut meetings = meeting::load(&path);
+        let count = meetings.len();
+        let now = self.controller.clock().wall();
+        let mut due = vec![];
+        meetings.retain(|meeting| match meeting.countdown(now) {
+            Some((name, left)) => {
+                due.push((name.to_string(), left));
+                false
+            }
+            None => meeting.start > now,
+        });
+        if meetings.len() != count {
+            if let Err(error) = meeting::save(&path, &meetings) {
+                log::warning!("{}: {}", path.display(), error);
+            }
+        }
+        for (name, left) in due {
+            self.add_named(name, left);
+        }
+    }
+
+    unsafe fn remove_named(&mut self, index: usize) {
+        self.stack.remove(index);
+        self.layout_stack();
+        self.save_stack();
+    }
+
+    unsafe fn handle_stacked(&mut self, event: usize, handle: HWND) {
+        let index = self
+            .stack
+            .iter()
+            .position(|stacked| stacked.handle() == handle);
+        match (event, index) {
+            (STACKED_REMOVE, Some(index)) => self.remove_named(index),
+            (STACKED_FINISHED, Some(index)) => {
+                let name = self.stack[index].named().name.clone();
+                self.notifier.notify(&name, &tr!("notify-times-up"));
+                self.save_stack();
+            }
+            _ => self.save_stack(),
+        }
+    }
+
+    /// Lines the named timers up below the main window, or above it when
+    /// they would run off the bottom of the screen.
+    unsafe fn layout_stack(&self) {
+        if self.stack.is_empty() || self.hidden {
+            return;
+        }
+        let mut window_rect = RECT::default();
+        if GetWindowRect(self.handle, &mut window_rect).is_err() {
+            return;
+        }
+        let step = STACKED_HEIGHT + STACK_GAP;
+        let height = step * self.stack.len() as i32;
+        let downwards = window_rect.bottom + height <= self.work_area().bottom;
+        for (index, stacked) in self.stack.iter().enumerate() {
+            let offset = step * index as i32;
+            let y = if downwards {
+                window_rect.bottom + STACK_GAP + offset
+            } else {
+                window_rect.top - step - offset
+            };
+            stacked.move_to(window_rect.right - STACKED_WIDTH, y);
+        }
+    }
+
+    /// The part of the main window's monitor not taken by the taskbar.
+    unsafe fn work_area(&self) -> RECT {
+        let monitor = MonitorFromWindow(self.handle, MONITOR_DEFAULTTONEAREST);
+        let mut monitor_info = MONITORINFO {
+            cbSize: size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        GetMonitorInfoW(monitor, &mut monitor_info).log_failure("GetMonitorInfoW");
+        monitor_info.rcWork
+    }
+
+    unsafe fn set_click_through(&mut self, enabled: bool) {
+        let ex_style = GetWindowLongPtrW(self.handle, GWL_EXSTYLE);
+        let ex_style = if enabled {
+            ex_style | WS_EX_TRANSPARENT.0 as isize
         } else {
-            self.time_left = new_time;
-            self.refresh();
+            ex_style & !(WS_EX_TRANSPARENT.0 as isize)
+        };
+        SetWindowLongPtrW(self.handle, GWL_EXSTYLE, ex_style);
+    }
+
+    unsafe fn set_alpha(&mut self, alpha: u8) {
+        if self.alpha != alpha {
+            self.alpha = alpha;
+            SetLayeredWindowAttributes(self.handle, COLORREF::default(), alpha, LWA_ALPHA)
+                .log_failure("SetLayeredWindowAttributes");
+        }
+    }
+
+    unsafe fn set_hidden(&mut self, hidden: bool) {
+        if self.hidden != hidden {
+            self.hidden = hidden;
+            let show = if hidden { SW_HIDE } else { SW_SHOWNOACTIVATE };
+            // Only whether each window was visible before.
+            let _ = ShowWindow(self.handle, show);
+            for stacked in &self.stack {
+                let _ = ShowWindow(stacked.handle(), show);
+            }
         }
     }
 
+    unsafe fn watch(&mut self) {
+        let config = self.controller.config();
+        let (hide_fullscreen, hover_fade) = (config.hide_fullscreen, config.hover_fade);
+        if hide_fullscreen {
+            self.set_hidden(self.is_fullscreen_app_active());
+        }
+        if let Some(fade_alpha) = hover_fade {
+            let alpha = if !self.controller.timer().is_stopped() && self.is_hovered() {
+                fade_alpha
+            } else {
+                WIN_ALPHA
+            };
+            self.set_alpha(alpha);
+        }
+    }
+
+    unsafe fn is_hovered(&self) -> bool {
+        let mut cursor = POINT::default();
+        let mut window_rect = RECT::default();
+        GetCursorPos(&mut cursor).is_ok()
+            && GetWindowRect(self.handle, &mut window_rect).is_ok()
+            && PtInRect(&window_rect, cursor).as_bool()
+    }
+
+    unsafe fn is_fullscreen_app_active(&self) -> bool {
+        let foreground = GetForegroundWindow();
+        if foreground.is_invalid()
+            || foreground == self.handle
+            || foreground == GetDesktopWindow()
+            || foreground == GetShellWindow()
+        {
+            return false;
+        }
+
+        let mut class_name = [0u16; 32];
+        let len = GetClassNameW(foreground, &mut class_name) as usize;
+        if String::from_utf16_lossy(&class_name[..len]) == "WorkerW" {
+            return false;
+        }
+
+        let mut window_rect = RECT::default();
+        if GetWindowRect(foreground, &mut window_rect).is_err() {
+            return false;
+        }
+
+        let monitor = MonitorFromWindow(foreground, MONITOR_DEFAULTTONEAREST);
+        let mut monitor_info = MONITORINFO {
+            cbSize: size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if !GetMonitorInfoW(monitor, &mut monitor_info).as_bool() {
+            return false;
+        }
+
+        let screen = monitor_info.rcMonitor;
+        window_rect.left <= screen.left
+            && window_rect.top <= screen.top
+            && window_rect.right >= screen.right
+            && window_rect.bottom >= screen.bottom
+    }
+
     unsafe fn refresh(&mut self) {
+        self.shown_secs = self.controller.remaining_secs();
+        self.shown_state = self.controller.timer().state();
+        self.controller.publish();
         RedrawWindow(
             Some(self.handle),
             None,
             None,
             RDW_INVALIDATE | RDW_UPDATENOW,
-        );
+        )
+        .log_failure("RedrawWindow");
     }
 
     unsafe fn message_handler(&mut self, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
@@ -333,11 +1261,72 @@ impl Window {
                 LRESULT(0)
             }
             WM_HOTKEY => {
-                self.toggle_timer();
+                let controller = &mut self.controller;
+                match wparam.0 as i32 {
+                    IDH_EXTEND => controller.extend(controller.extend_by()),
+                    IDH_SNOOZE => controller.extend(controller.snooze_for()),
+                    IDH_UNDO => controller.undo(),
+                    id if (IDH_NAMED..IDH_NAMED + MAX_NAMED_HOTKEYS).contains(&id) => {
+                        if let Some(stacked) = self.stack.get_mut((id - IDH_NAMED) as usize) {
+                            stacked.toggle();
+                        }
+                        return LRESULT(0);
+                    }
+                    _ => controller.toggle(),
+                }
+                self.changed();
                 LRESULT(0)
             }
             WM_TIMER => {
-                self.update_timer(self.time_left - 1);
+                match wparam.0 {
+                    IDT_TIMER => {
+                        self.controller.update_timer();
+                        self.ticked();
+                    }
+                    IDT_WATCH => self.watch(),
+                    IDT_REMINDER => {
+                        self.controller.update_reminders();
+                        self.ticked();
+                    }
+                    IDT_ROOM => {
+                        self.controller.follow_room();
+                        self.ticked();
+                    }
+                    IDT_SCHEDULE => {
+                        self.controller.follow_schedule();
+                        self.ticked();
+                    }
+                    IDT_UNDO => {
+                        self.controller.expire_undo();
+                        self.changed();
+                    }
+                    IDT_MEETINGS => self.check_meetings(),
+                    _ => {}
+                }
+                LRESULT(0)
+            }
+            WM_POWERBROADCAST => {
+                let controller = &mut self.controller;
+                match wparam.0 as u32 {
+                    PBT_APMSUSPEND => {
+                        let policy = controller.config().on_sleep;
+                        controller.suspend(SuspendReason::Sleep, policy);
+                    }
+                    PBT_APMRESUMEAUTOMATIC | PBT_APMRESUMESUSPEND => {
+                        controller.wake(SuspendReason::Sleep)
+                    }
+                    _ => return LRESULT(1),
+                }
+                self.changed();
+                LRESULT(1)
+            }
+            WM_WTSSESSION_CHANGE => {
+                match wparam.0 as u32 {
+                    WTS_SESSION_LOCK => self.controller.set_locked(true),
+                    WTS_SESSION_UNLOCK => self.controller.set_locked(false),
+                    _ => return LRESULT(0),
+                }
+                self.changed();
                 LRESULT(0)
             }
             WM_PAINT => {
@@ -345,7 +1334,8 @@ impl Window {
                 let psp = &mut ps as *mut PAINTSTRUCT;
                 let hdc = BeginPaint(self.handle, psp);
                 self.paint(ps, hdc);
-                EndPaint(self.handle, &ps);
+                // Always succeeds.
+                let _ = EndPaint(self.handle, &ps);
                 LRESULT(0)
             }
             WM_ERASEBKGND => LRESULT(1),
@@ -358,11 +1348,47 @@ impl Window {
                 }
             }
             WM_NCRBUTTONDOWN => {
-                self.reset_pos();
+                self.show_menu(lparam);
                 LRESULT(0)
             }
+            WM_NCMOUSEMOVE => {
+                self.relay_tooltip(lparam);
+                DefWindowProcW(self.handle, message, wparam, lparam)
+            }
+            WM_MOVE => {
+                self.layout_stack();
+                LRESULT(0)
+            }
+            WM_STACKED => {
+                self.handle_stacked(wparam.0, HWND(lparam.0 as *mut c_void));
+                LRESULT(0)
+            }
+            WM_REFLECTION => {
+                self.handle_reflection(wparam.0);
+                LRESULT(0)
+            }
+            WM_SKIP_BREAK => {
+                self.controller.skip_break();
+                self.changed();
+                LRESULT(0)
+            }
+            WM_COPYDATA => {
+                let data = &*(lparam.0 as *const COPYDATASTRUCT);
+                if data.dwData != CTL_COMMAND {
+                    return LRESULT(0);
+                }
+                let bytes = slice::from_raw_parts(data.lpData as *const u8, data.cbData as usize);
+                match Command::decode(bytes) {
+                    Some(command) => {
+                        self.handle_command(command);
+                        LRESULT(1)
+                    }
+                    None => LRESULT(0),
+                }
+            }
             WM_NCLBUTTONDBLCLK => {
-                self.toggle_timer();
+                self.controller.toggle();
+                self.changed();
                 LRESULT(0)
             }
             _ => DefWindowProcW(self.handle, message, wparam, lparam),
@@ -392,9 +1418,28 @@ impl Window {
         let mut message = MSG::default();
         unsafe {
             while GetMessageW(&mut message, None, 0, 0).into() {
-                TranslateMessage(&message);
+                // Gives the reflection prompt Tab, Enter and Escape.
+                if let Some(prompt) = reflection::active() {
+                    if IsDialogMessageW(prompt, &message).as_bool() {
+                        continue;
+                    }
+                }
+                // Only whether a character message was posted.
+                let _ = TranslateMessage(&message);
                 DispatchMessageW(&message);
             }
         }
     }
 }
+
+fn format_secs(secs: u64) -> String {
+    match (secs / 60, secs % 60) {
+        (0, seconds) => tr!("duration-secs", seconds = seconds),
+        (minutes, 0) => tr!("duration-mins", minutes = minutes),
+        (minutes, seconds) => tr!("duration-mins-secs", minutes = minutes, seconds = seconds),
+    }
+}
+
+fn undo_name(action: Action) -> String {
+    tr!(&format!("undo-{}", action.as_str()))
+}